@@ -0,0 +1,488 @@
+//! # Key Actions
+//!
+//! A [`KeyAction`] is what a key position does when pressed. Most keys simply send a
+//! [`KeyboardUsage`], but firmware also supports media and other consumer controls
+//! ([`ConsumerUsage`]), mouse keys ([`MouseAction`]), modifier-wrapped keys (`Ctrl+C`),
+//! dual-role keys (mod-tap, layer-tap), layer switching, transparent keys and no-op keys.
+//!
+//! Every action has a text label that is used by the editor and can be parsed back:
+//!
+//! | Action                  | Label             |
+//! |-------------------------|-------------------|
+//! | Plain key               | `A`, `Enter`      |
+//! | Consumer control        | `Play`, `Vol+`    |
+//! | Mouse key               | `Btn1`, `MsUp`    |
+//! | Modifier-wrapped key    | `Ctrl+Shift+Z`    |
+//! | Mod-tap                 | `MT(Ctrl, Esc)`   |
+//! | Layer-tap               | `LT(1, Space)`    |
+//! | Momentary layer         | `MO(1)`           |
+//! | Toggle layer            | `TG(1)`           |
+//! | One-shot layer          | `OSL(1)`          |
+//! | Transparent             | `Trans`           |
+//! | No-op                   | (empty)           |
+
+use std::fmt;
+use std::ops::{BitOr, BitOrAssign};
+use std::str::FromStr;
+use serde::{Serialize, Deserialize};
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+
+/// A set of modifier keys, using the bit layout of the HID boot keyboard report
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Modifiers(u8);
+
+/// Modifier bits with their display name and accepted (lowercase) aliases
+const MODIFIER_NAMES: [(Modifiers, &str, &[&str]); 8] = [
+    (Modifiers::LEFT_CTRL, "Ctrl", &["ctrl", "control", "lctrl", "lcontrol"]),
+    (Modifiers::LEFT_SHIFT, "Shift", &["shift", "lshift"]),
+    (Modifiers::LEFT_ALT, "Alt", &["alt", "lalt", "opt", "option"]),
+    (Modifiers::LEFT_GUI, "GUI", &["gui", "lgui", "cmd", "command", "win", "super", "meta"]),
+    (Modifiers::RIGHT_CTRL, "RCtrl", &["rctrl", "rcontrol"]),
+    (Modifiers::RIGHT_SHIFT, "RShift", &["rshift"]),
+    (Modifiers::RIGHT_ALT, "RAlt", &["ralt", "altgr", "ropt", "roption"]),
+    (Modifiers::RIGHT_GUI, "RGUI", &["rgui", "rcmd", "rwin"]),
+];
+
+impl Modifiers {
+    pub const NONE: Self = Self(0x00);
+    pub const LEFT_CTRL: Self = Self(0x01);
+    pub const LEFT_SHIFT: Self = Self(0x02);
+    pub const LEFT_ALT: Self = Self(0x04);
+    pub const LEFT_GUI: Self = Self(0x08);
+    pub const RIGHT_CTRL: Self = Self(0x10);
+    pub const RIGHT_SHIFT: Self = Self(0x20);
+    pub const RIGHT_ALT: Self = Self(0x40);
+    pub const RIGHT_GUI: Self = Self(0x80);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Look up a single modifier by name, e.g. `Ctrl`, `cmd` or `RShift`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        MODIFIER_NAMES.iter()
+            .find(|(_, _, aliases)| aliases.contains(&name.as_str()))
+            .map(|&(modifier, _, _)| modifier)
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = MODIFIER_NAMES.iter()
+            .filter(|(modifier, _, _)| self.contains(*modifier))
+            .map(|&(_, name, _)| name)
+            .collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+impl FromStr for Modifiers {
+    type Err = String;
+
+    /// Parse a `+`-separated list of modifier names, e.g. `Ctrl+Shift`
+    fn from_str(names: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Self::NONE;
+        for name in names.split('+') {
+            modifiers |= Self::from_name(name)
+                .ok_or_else(|| format!("Unknown modifier '{}'", name.trim()))?;
+        }
+        Ok(modifiers)
+    }
+}
+
+/// A mouse key: a button, a step of pointer or wheel movement, or a pointer speed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum MouseAction {
+    /// Left button
+    Button1,
+    /// Right button
+    Button2,
+    /// Middle button
+    Button3,
+    /// Back button
+    Button4,
+    /// Forward button
+    Button5,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    WheelUp,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+    /// Slowest pointer and wheel speed while held
+    Accel0,
+    /// Medium pointer and wheel speed while held
+    Accel1,
+    /// Fastest pointer and wheel speed while held
+    Accel2,
+}
+
+/// Mouse actions with their display name and accepted (lowercase) aliases
+const MOUSE_NAMES: [(MouseAction, &str, &[&str]); 16] = [
+    (MouseAction::Button1, "Btn1", &["btn1", "lclick", "mb1"]),
+    (MouseAction::Button2, "Btn2", &["btn2", "rclick", "mb2"]),
+    (MouseAction::Button3, "Btn3", &["btn3", "mclick", "mb3"]),
+    (MouseAction::Button4, "Btn4", &["btn4", "mb4"]),
+    (MouseAction::Button5, "Btn5", &["btn5", "mb5"]),
+    (MouseAction::MoveUp, "MsUp", &["msup", "ms up"]),
+    (MouseAction::MoveDown, "MsDown", &["msdown", "msdn", "ms down"]),
+    (MouseAction::MoveLeft, "MsLeft", &["msleft", "ms left"]),
+    (MouseAction::MoveRight, "MsRght", &["msrght", "msright", "ms right"]),
+    (MouseAction::WheelUp, "WhUp", &["whup", "wheel up"]),
+    (MouseAction::WheelDown, "WhDown", &["whdown", "whdn", "wheel down"]),
+    (MouseAction::WheelLeft, "WhLeft", &["whleft", "wheel left"]),
+    (MouseAction::WheelRight, "WhRght", &["whrght", "whright", "wheel right"]),
+    (MouseAction::Accel0, "Accel0", &["accel0", "acl0"]),
+    (MouseAction::Accel1, "Accel1", &["accel1", "acl1"]),
+    (MouseAction::Accel2, "Accel2", &["accel2", "acl2"]),
+];
+
+impl MouseAction {
+    /// Every mouse action, buttons first
+    pub fn all() -> impl Iterator<Item = Self> {
+        MOUSE_NAMES.iter().map(|&(action, _, _)| action)
+    }
+
+    /// Look up a mouse action by name, e.g. `Btn1`, `lclick` or `MsRight`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        MOUSE_NAMES.iter()
+            .find(|(_, _, aliases)| aliases.contains(&name.as_str()))
+            .map(|&(action, _, _)| action)
+    }
+}
+
+impl fmt::Display for MouseAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let &(_, name, _) = MOUSE_NAMES.iter()
+            .find(|&&(action, _, _)| action == *self)
+            .expect("every mouse action is named");
+        write!(f, "{}", name)
+    }
+}
+
+/// What a key position does when pressed
+#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum KeyAction {
+    /// Send a single key
+    Key(KeyboardUsage),
+    /// Send a media, brightness or application launch control
+    Consumer(ConsumerUsage),
+    /// Press a mouse button, move the pointer or wheel, or change their speed
+    Mouse(MouseAction),
+    /// Send a key with modifiers held, e.g. Ctrl+C
+    Modified { modifiers: Modifiers, key: KeyboardUsage },
+    /// Modifiers when held, a key when tapped
+    ModTap { modifiers: Modifiers, tap: KeyboardUsage },
+    /// Layer when held, a key when tapped
+    LayerTap { layer: usize, tap: KeyboardUsage },
+    /// Activate a layer while held
+    MomentaryLayer(usize),
+    /// Toggle a layer on or off
+    ToggleLayer(usize),
+    /// Activate a layer for the next key press only
+    OneShotLayer(usize),
+    /// Fall through to the next active layer below
+    Transparent,
+    /// Do nothing
+    NoOp,
+}
+
+impl KeyAction {
+    /// The layer this action switches to, if any
+    pub fn layer(&self) -> Option<usize> {
+        match *self {
+            Self::LayerTap { layer, .. }
+            | Self::MomentaryLayer(layer)
+            | Self::ToggleLayer(layer)
+            | Self::OneShotLayer(layer) => Some(layer),
+            _ => None,
+        }
+    }
+
+    /// Point a layer action at another layer. With `None` the layer part is dropped:
+    /// layer-taps become their tap key and pure layer keys become no-ops.
+    pub fn retarget_layer(self, layer: Option<usize>) -> Self {
+        match (self, layer) {
+            (Self::LayerTap { tap, .. }, Some(layer)) => Self::LayerTap { layer, tap },
+            (Self::LayerTap { tap, .. }, None) => Self::Key(tap),
+            (Self::MomentaryLayer(_), Some(layer)) => Self::MomentaryLayer(layer),
+            (Self::ToggleLayer(_), Some(layer)) => Self::ToggleLayer(layer),
+            (Self::OneShotLayer(_), Some(layer)) => Self::OneShotLayer(layer),
+            (Self::MomentaryLayer(_) | Self::ToggleLayer(_) | Self::OneShotLayer(_), None) => Self::NoOp,
+            (action, _) => action,
+        }
+    }
+}
+
+impl From<KeyboardUsage> for KeyAction {
+    fn from(usage: KeyboardUsage) -> Self {
+        Self::Key(usage)
+    }
+}
+
+impl fmt::Display for KeyAction {
+    /// Format the action as its editor label
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::actions::{KeyAction, Modifiers};
+    /// use layout_rs::keycodes::KeyboardUsage;
+    ///
+    /// let action = KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeyboardUsage::KeyboardCc };
+    /// assert_eq!(action.to_string(), "Ctrl+C");
+    ///
+    /// let action = KeyAction::LayerTap { layer: 1, tap: KeyboardUsage::KeyboardSpacebar };
+    /// assert_eq!(action.to_string(), "LT(1, Space)");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Key(usage) => write!(f, "{}", <&str>::from(usage)),
+            Self::Consumer(usage) => write!(f, "{}", <&str>::from(usage)),
+            Self::Mouse(action) => write!(f, "{}", action),
+            Self::Modified { modifiers, key } => write!(f, "{}+{}", modifiers, <&str>::from(key)),
+            Self::ModTap { modifiers, tap } => write!(f, "MT({}, {})", modifiers, <&str>::from(tap)),
+            Self::LayerTap { layer, tap } => write!(f, "LT({}, {})", layer, <&str>::from(tap)),
+            Self::MomentaryLayer(layer) => write!(f, "MO({})", layer),
+            Self::ToggleLayer(layer) => write!(f, "TG({})", layer),
+            Self::OneShotLayer(layer) => write!(f, "OSL({})", layer),
+            Self::Transparent => write!(f, "Trans"),
+            Self::NoOp => Ok(()),
+        }
+    }
+}
+
+impl FromStr for KeyAction {
+    type Err = String;
+
+    /// Parse an editor label into an action
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::actions::KeyAction;
+    /// use layout_rs::keycodes::KeyboardUsage;
+    ///
+    /// let action: KeyAction = "Enter".parse().unwrap();
+    /// assert_eq!(action, KeyAction::Key(KeyboardUsage::KeyboardEnter));
+    ///
+    /// let action: KeyAction = "mo(2)".parse().unwrap();
+    /// assert_eq!(action, KeyAction::MomentaryLayer(2));
+    ///
+    /// assert!("Invalid".parse::<KeyAction>().is_err());
+    /// ```
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        let label = label.trim();
+
+        match label.to_ascii_lowercase().as_str() {
+            "" | "none" => return Ok(Self::NoOp),
+            "trans" | "transparent" => return Ok(Self::Transparent),
+            // Conventional names for the first two layers above the base layer
+            "lower" => return Ok(Self::MomentaryLayer(1)),
+            "raise" => return Ok(Self::MomentaryLayer(2)),
+            _ => {}
+        }
+
+        // Names both pages share, such as Kanata's `mute`, mean the consumer control
+        if let Some(usage) = ConsumerUsage::from_label(label) {
+            return Ok(Self::Consumer(usage));
+        }
+        if let Some(usage) = parse_usage(label) {
+            return Ok(Self::Key(usage));
+        }
+        if let Some(action) = MouseAction::from_name(label) {
+            return Ok(Self::Mouse(action));
+        }
+
+        if let Some((function, args)) = parse_call(label) {
+            return match function.to_ascii_uppercase().as_str() {
+                "MO" => Ok(Self::MomentaryLayer(parse_layer(args)?)),
+                "TG" => Ok(Self::ToggleLayer(parse_layer(args)?)),
+                "OSL" => Ok(Self::OneShotLayer(parse_layer(args)?)),
+                "LT" => {
+                    let (layer, tap) = split_args(args)?;
+                    Ok(Self::LayerTap { layer: parse_layer(layer)?, tap: parse_key(tap)? })
+                }
+                "MT" => {
+                    let (modifiers, tap) = split_args(args)?;
+                    Ok(Self::ModTap { modifiers: modifiers.parse()?, tap: parse_key(tap)? })
+                }
+                _ => Err(format!("Unknown action '{}'", function)),
+            };
+        }
+
+        // Modifier-wrapped key: strip modifier prefixes until the rest is a key label
+        let mut modifiers = Modifiers::NONE;
+        let mut rest = label;
+        while let Some((name, remainder)) = rest.split_once('+') {
+            match Modifiers::from_name(name) {
+                Some(modifier) => modifiers |= modifier,
+                None => break,
+            }
+            rest = remainder.trim();
+            if let Some(key) = parse_usage(rest) {
+                return Ok(Self::Modified { modifiers, key });
+            }
+        }
+
+        Err(format!("Unknown key '{}'", label))
+    }
+}
+
+/// Look up a key label, rejecting labels that map to `Reserved`
+fn parse_usage(label: &str) -> Option<KeyboardUsage> {
+    match KeyboardUsage::from(label) {
+        KeyboardUsage::Reserved => None,
+        usage => Some(usage),
+    }
+}
+
+fn parse_key(label: &str) -> Result<KeyboardUsage, String> {
+    parse_usage(label.trim()).ok_or_else(|| format!("Unknown key '{}'", label.trim()))
+}
+
+fn parse_layer(layer: &str) -> Result<usize, String> {
+    layer.trim().parse().map_err(|_| format!("Invalid layer '{}'", layer.trim()))
+}
+
+/// Split `NAME(args)` into its name and argument list
+fn parse_call(label: &str) -> Option<(&str, &str)> {
+    let (function, args) = label.strip_suffix(')')?.split_once('(')?;
+    Some((function.trim(), args))
+}
+
+/// Split a two-argument list on its first comma
+fn split_args(args: &str) -> Result<(&str, &str), String> {
+    args.split_once(',').ok_or_else(|| format!("Expected two arguments, got '{}'", args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keycodes::KeyboardUsage::*;
+
+    #[test]
+    fn test_parse_plain_keys() {
+        assert_eq!("A".parse(), Ok(KeyAction::Key(KeyboardAa)));
+        assert_eq!("Num +".parse(), Ok(KeyAction::Key(KeypadPlus)));
+        assert_eq!("Vol+".parse(), Ok(KeyAction::Consumer(ConsumerUsage::VolumeIncrement)));
+        assert_eq!("KbVol+".parse(), Ok(KeyAction::Key(KeyboardVolumeUp)));
+        assert_eq!("KC_MPLY".parse(), Ok(KeyAction::Consumer(ConsumerUsage::PlayPause)));
+        assert_eq!("Btn1".parse(), Ok(KeyAction::Mouse(MouseAction::Button1)));
+        assert_eq!("MsRight".parse(), Ok(KeyAction::Mouse(MouseAction::MoveRight)));
+        assert_eq!("".parse(), Ok(KeyAction::NoOp));
+        assert_eq!("Trans".parse(), Ok(KeyAction::Transparent));
+        assert_eq!("KC_ESC".parse(), Ok(KeyAction::Key(KeyboardEscape)));
+        assert_eq!("LT(1, spc)".parse(), Ok(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }));
+        assert!("Invalid".parse::<KeyAction>().is_err());
+    }
+
+    #[test]
+    fn test_parse_modified_keys() {
+        assert_eq!(
+            "Ctrl+C".parse(),
+            Ok(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeyboardCc })
+        );
+        assert_eq!(
+            "cmd+shift+Z".parse(),
+            Ok(KeyAction::Modified { modifiers: Modifiers::LEFT_GUI | Modifiers::LEFT_SHIFT, key: KeyboardZz })
+        );
+        assert_eq!(
+            "Ctrl+Num +".parse(),
+            Ok(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeypadPlus })
+        );
+        assert!("Ctrl+Invalid".parse::<KeyAction>().is_err());
+    }
+
+    #[test]
+    fn test_parse_layer_actions() {
+        assert_eq!("MO(1)".parse(), Ok(KeyAction::MomentaryLayer(1)));
+        assert_eq!("tg(3)".parse(), Ok(KeyAction::ToggleLayer(3)));
+        assert_eq!("OSL( 2 )".parse(), Ok(KeyAction::OneShotLayer(2)));
+        assert_eq!("Lower".parse(), Ok(KeyAction::MomentaryLayer(1)));
+        assert_eq!("Raise".parse(), Ok(KeyAction::MomentaryLayer(2)));
+        assert_eq!("LT(1, Space)".parse(), Ok(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }));
+        assert_eq!("LT(1, ,)".parse(), Ok(KeyAction::LayerTap { layer: 1, tap: KeyboardCommaLess }));
+        assert_eq!(
+            "MT(Ctrl+Shift, Esc)".parse(),
+            Ok(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardEscape })
+        );
+        assert!("MO(x)".parse::<KeyAction>().is_err());
+        assert!("LT(1)".parse::<KeyAction>().is_err());
+        assert!("MT(Hyper, A)".parse::<KeyAction>().is_err());
+        assert!("XY(1)".parse::<KeyAction>().is_err());
+    }
+
+    #[test]
+    fn test_label_round_trip() {
+        let actions = [
+            KeyAction::Key(KeyboardAa),
+            KeyAction::Key(KeypadPlus),
+            KeyAction::Consumer(ConsumerUsage::ACBack),
+            KeyAction::Mouse(MouseAction::WheelLeft),
+            KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::RIGHT_ALT, key: KeyboardDelete },
+            KeyAction::ModTap { modifiers: Modifiers::RIGHT_GUI, tap: KeyboardSemiColon },
+            KeyAction::LayerTap { layer: 4, tap: KeyboardBackspace },
+            KeyAction::MomentaryLayer(1),
+            KeyAction::ToggleLayer(2),
+            KeyAction::OneShotLayer(3),
+            KeyAction::Transparent,
+            KeyAction::NoOp,
+        ];
+
+        for action in actions {
+            assert_eq!(action.to_string().parse(), Ok(action));
+        }
+    }
+
+    #[test]
+    fn test_every_key_label_round_trip() {
+        let usages = (0..=u8::MAX).map(KeyboardUsage::from).filter(|&usage| usage != Reserved);
+        for key in usages {
+            for action in [
+                KeyAction::Key(key),
+                KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key },
+                KeyAction::ModTap { modifiers: Modifiers::LEFT_ALT, tap: key },
+                KeyAction::LayerTap { layer: 1, tap: key },
+            ] {
+                assert_eq!(action.to_string().parse(), Ok(action), "label '{}'", action);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_mouse_label_round_trip() {
+        for action in MouseAction::all().map(KeyAction::Mouse) {
+            assert_eq!(action.to_string().parse(), Ok(action), "label '{}'", action);
+        }
+        assert_eq!(MouseAction::all().count(), MOUSE_NAMES.len());
+    }
+
+    #[test]
+    fn test_retarget_layer() {
+        let layer_tap = KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar };
+        assert_eq!(layer_tap.layer(), Some(1));
+        assert_eq!(layer_tap.retarget_layer(Some(3)), KeyAction::LayerTap { layer: 3, tap: KeyboardSpacebar });
+        assert_eq!(layer_tap.retarget_layer(None), KeyAction::Key(KeyboardSpacebar));
+        assert_eq!(KeyAction::ToggleLayer(2).retarget_layer(None), KeyAction::NoOp);
+        assert_eq!(KeyAction::Key(KeyboardAa).retarget_layer(Some(1)), KeyAction::Key(KeyboardAa));
+    }
+}
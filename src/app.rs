@@ -1,11 +1,84 @@
 use yew::prelude::*;
+use gloo::events::EventListener;
+use gloo::file::callbacks::FileReader;
+use wasm_bindgen::JsCast;
 
-use crate::components::{Header, Layout, Keymap};
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use crate::components::{Header, LayerTabs, Layout};
+use crate::download::download_file;
+use crate::formats::ExportFormat;
+use crate::keyboards;
+use crate::keymap::{Keymap, PendingSave};
+use crate::store::{IndexedDbStore, KeymapStore, LocalStorageStore};
+
+const DATABASE_NAME: &str = "layout-rs";
+
+/// Opens the keymap store, then shows the editor.
+///
+/// Layouts are kept in IndexedDB when the browser supports it; anything still in
+/// localStorage from earlier versions is moved over on first start. Without IndexedDB
+/// the editor falls back to localStorage.
 #[function_component(App)]
 pub fn app() -> Html {
+    let store = use_state(|| None::<Rc<dyn KeymapStore>>);
+
+    {
+        let store = store.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let opened: Rc<dyn KeymapStore> = match IndexedDbStore::open(DATABASE_NAME).await {
+                    Ok(database) => {
+                        match Keymap::move_entries(&LocalStorageStore, &database).await {
+                            Ok(0) => {}
+                            Ok(moved) => web_sys::console::log_1(&format!("Moved {} entries from localStorage to IndexedDB", moved).into()),
+                            Err(e) => web_sys::console::log_1(&format!("Failed to move localStorage entries: {}", e).into()),
+                        }
+                        Rc::new(database)
+                    }
+                    Err(e) => {
+                        web_sys::console::log_1(&format!("IndexedDB unavailable, using localStorage: {}", e).into());
+                        Rc::new(LocalStorageStore)
+                    }
+                };
+                store.set(Some(opened));
+            });
+            || ()
+        });
+    }
+
+    match &*store {
+        Some(store) => html! { <Editor store={store.clone()} /> },
+        None => html! { <div class="app loading">{"Loading layouts..."}</div> },
+    }
+}
+
+#[derive(Properties, Clone)]
+pub struct EditorProps {
+    pub store: Rc<dyn KeymapStore>,
+}
+
+impl PartialEq for EditorProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.store, &other.store)
+    }
+}
+
+#[function_component(Editor)]
+pub fn editor(props: &EditorProps) -> Html {
     let selected_key = use_state(|| None::<(usize, usize)>);
-    let keymap = use_state(|| Keymap::new());
+    let keymap = {
+        let store = props.store.clone();
+        use_state(move || Keymap::with_store(store))
+    };
+    // Keeps the pending import alive until the file has been read
+    let import_reader = use_mut_ref(|| None::<FileReader>);
+    // Handle of the latest render, so a save finishing later applies to the keymap as it is
+    // by then instead of the copy it started from
+    let latest_keymap = use_mut_ref(|| keymap.clone());
+    *latest_keymap.borrow_mut() = keymap.clone();
+    let is_file_drag_over = use_state(|| false);
 
     let on_key_click = {
         let selected_key = selected_key.clone();
@@ -20,7 +93,9 @@ pub fn app() -> Html {
         Callback::from(move |value: String| {
             if let Some((row, col)) = *selected_key {
                 let mut new_keymap = (*keymap).clone();
-                new_keymap.update_key(row, col, value);
+                if let Err(e) = new_keymap.update_key(row, col, value) {
+                    web_sys::console::log_1(&format!("Key update error: {}", e).into());
+                }
                 keymap.set(new_keymap);
             }
         })
@@ -28,12 +103,12 @@ pub fn app() -> Html {
 
     let on_save_layout = {
         let keymap = keymap.clone();
+        let latest_keymap = latest_keymap.clone();
         Callback::from(move |_| {
             let mut new_keymap = (*keymap).clone();
-            if let Err(e) = new_keymap.save() {
-                web_sys::console::log_1(&format!("Save error: {}", e).into());
-            }
+            let save = new_keymap.start_save();
             keymap.set(new_keymap);
+            finish_save(save, "Save error", latest_keymap.clone());
         })
     };
 
@@ -73,10 +148,266 @@ pub fn app() -> Html {
     let on_export_layout = {
         let keymap = keymap.clone();
         Callback::from(move |_| {
-            let current_keymap = (*keymap).clone();
-            if let Err(e) = current_keymap.download_json() {
+            let file_name = format!("{}.json", keymap.keyboard().storage_key);
+            match keymap.export_json().and_then(|json| download_file(&file_name, &json)) {
+                Ok(()) => web_sys::console::log_1(&"Layout exported successfully!".into()),
+                Err(e) => web_sys::console::log_1(&format!("Export error: {}", e).into()),
+            }
+        })
+    };
+
+    let on_export_firmware = {
+        let keymap = keymap.clone();
+        Callback::from(move |id: String| {
+            let Some(format) = ExportFormat::from_id(&id) else {
+                web_sys::console::log_1(&format!("Unknown export format: {}", id).into());
+                return;
+            };
+            let export = keymap.export_as(format);
+            let file_name = format.file_name(keymap.keyboard());
+            if let Err(e) = download_file(&file_name, &export.output) {
                 web_sys::console::log_1(&format!("Export error: {}", e).into());
+                return;
+            }
+            if !export.warnings.is_empty() {
+                let heading = format!("Exported {} with {} approximated keys:", file_name, export.warnings.len());
+                gloo::dialogs::alert(&problem_list(&heading, &export.warnings));
+            }
+        })
+    };
+
+    let on_select_keyboard = {
+        let keymap = keymap.clone();
+        let selected_key = selected_key.clone();
+        Callback::from(move |id: String| {
+            if keymap.has_unsaved_changes()
+                && !gloo::dialogs::confirm("Discard unsaved changes and switch keyboard?")
+            {
+                // Re-render so the picker shows the current keyboard again
+                keymap.set((*keymap).clone());
+                return;
+            }
+
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.switch_keyboard(&id) {
+                web_sys::console::log_1(&format!("Keyboard switch error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+            selected_key.set(None);
+        })
+    };
+
+    let on_undo = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.undo() {
+                web_sys::console::log_1(&format!("Undo error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_redo = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.redo() {
+                web_sys::console::log_1(&format!("Redo error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    // Ctrl+Z / Ctrl+Shift+Z (Cmd on macOS), except while typing in a text field. The listener
+    // is added once and calls whichever callbacks the latest render made.
+    {
+        let shortcuts = use_mut_ref(|| (on_undo.clone(), on_redo.clone()));
+        *shortcuts.borrow_mut() = (on_undo.clone(), on_redo.clone());
+        use_effect_with((), move |_| {
+            let listener = EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+                let event = event.dyn_ref::<web_sys::KeyboardEvent>().unwrap();
+                let in_text_field = event.target()
+                    .map(|target| target.has_type::<web_sys::HtmlInputElement>() || target.has_type::<web_sys::HtmlTextAreaElement>())
+                    .unwrap_or(false);
+                if !(event.ctrl_key() || event.meta_key()) || !event.key().eq_ignore_ascii_case("z") || in_text_field {
+                    return;
+                }
+
+                event.prevent_default();
+                let (on_undo, on_redo) = shortcuts.borrow().clone();
+                if event.shift_key() {
+                    on_redo.emit(());
+                } else {
+                    on_undo.emit(());
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
+    let on_import_layout = {
+        let keymap = keymap.clone();
+        let selected_key = selected_key.clone();
+        let import_reader = import_reader.clone();
+        Callback::from(move |file: gloo::file::File| {
+            if keymap.has_unsaved_changes()
+                && !gloo::dialogs::confirm("Discard unsaved changes and import a layout?")
+            {
+                return;
+            }
+
+            let keymap = keymap.clone();
+            let selected_key = selected_key.clone();
+            let name = file.name();
+            let reader = gloo::file::callbacks::read_as_text(&file, move |result| {
+                let json = match result {
+                    Ok(json) => json,
+                    Err(e) => {
+                        web_sys::console::log_1(&format!("Import error: failed to read {}: {}", name, e).into());
+                        return;
+                    }
+                };
+
+                let mut new_keymap = (*keymap).clone();
+                match new_keymap.import_file(&name, &json) {
+                    Ok(problems) => {
+                        if !problems.is_empty() {
+                            let heading = format!("Imported {} with {} warnings:", name, problems.len());
+                            gloo::dialogs::alert(&problem_list(&heading, &problems));
+                        }
+                        keymap.set(new_keymap);
+                        selected_key.set(None);
+                    }
+                    Err(e) => {
+                        web_sys::console::log_1(&format!("Import error: {}", e).into());
+                        gloo::dialogs::alert(&format!("Could not import {}: {}", name, e));
+                    }
+                }
+            });
+            *import_reader.borrow_mut() = Some(reader);
+        })
+    };
+
+    let on_file_drag_over = {
+        let is_file_drag_over = is_file_drag_over.clone();
+        Callback::from(move |e: DragEvent| {
+            let has_files = e.data_transfer()
+                .map(|data| data.types().includes(&"Files".into(), 0))
+                .unwrap_or(false);
+            if has_files {
+                e.prevent_default();
+                is_file_drag_over.set(true);
+            }
+        })
+    };
+
+    let on_file_drag_leave = {
+        let is_file_drag_over = is_file_drag_over.clone();
+        Callback::from(move |e: DragEvent| {
+            // Only when leaving the app, not when moving between its children
+            if e.related_target().is_none() {
+                is_file_drag_over.set(false);
+            }
+        })
+    };
+
+    let on_file_drop = {
+        let is_file_drag_over = is_file_drag_over.clone();
+        let on_import_layout = on_import_layout.clone();
+        Callback::from(move |e: DragEvent| {
+            is_file_drag_over.set(false);
+            let file = e.data_transfer()
+                .and_then(|data| data.files())
+                .and_then(|files| files.get(0));
+            if let Some(file) = file {
+                e.prevent_default();
+                on_import_layout.emit(gloo::file::File::from(file));
+            }
+        })
+    };
+
+    let on_select_profile = {
+        let keymap = keymap.clone();
+        Callback::from(move |id: String| {
+            if keymap.has_unsaved_changes()
+                && !gloo::dialogs::confirm("Discard unsaved changes and switch profile?")
+            {
+                // Re-render so the picker shows the current profile again
+                keymap.set((*keymap).clone());
+                return;
+            }
+
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.switch_profile(&id) {
+                web_sys::console::log_1(&format!("Profile switch error: {}", e).into());
             }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_create_profile = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            if keymap.has_unsaved_changes()
+                && !gloo::dialogs::confirm("Discard unsaved changes and create a new profile?")
+            {
+                return;
+            }
+            let Some(name) = gloo::dialogs::prompt("Name of the new profile", Some("New profile")) else {
+                return;
+            };
+
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.create_profile(name) {
+                web_sys::console::log_1(&format!("Create profile error: {}", e).into());
+                gloo::dialogs::alert(&e);
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_rename_profile = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            let current_name = keymap.profiles().active().name.clone();
+            let Some(name) = gloo::dialogs::prompt("New profile name", Some(&current_name)) else {
+                return;
+            };
+
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.rename_profile(name) {
+                web_sys::console::log_1(&format!("Rename profile error: {}", e).into());
+                gloo::dialogs::alert(&e);
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_duplicate_profile = {
+        let keymap = keymap.clone();
+        let latest_keymap = latest_keymap.clone();
+        Callback::from(move |_| {
+            let mut new_keymap = (*keymap).clone();
+            let save = new_keymap.start_duplicate_profile();
+            keymap.set(new_keymap);
+            finish_save(save, "Duplicate profile error", latest_keymap.clone());
+        })
+    };
+
+    let on_delete_profile = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            let name = keymap.profiles().active().name.clone();
+            if !gloo::dialogs::confirm(&format!("Delete profile '{}' and its saved layout?", name)) {
+                return;
+            }
+
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.delete_profile() {
+                web_sys::console::log_1(&format!("Delete profile error: {}", e).into());
+            }
+            keymap.set(new_keymap);
         })
     };
 
@@ -86,7 +417,9 @@ pub fn app() -> Html {
         Callback::from(move |((row, col), key): ((usize, usize), String)| {
             // Update the key directly without needing selection
             let mut new_keymap = (*keymap).clone();
-            new_keymap.update_key(row, col, key);
+            if let Err(e) = new_keymap.update_key(row, col, key) {
+                web_sys::console::log_1(&format!("Key drop error: {}", e).into());
+            }
             keymap.set(new_keymap);
             
             // Also select the key that was dropped on
@@ -94,19 +427,138 @@ pub fn app() -> Html {
         })
     };
 
+    let on_select_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |index: usize| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.set_active_layer(index) {
+                web_sys::console::log_1(&format!("Layer select error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_add_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |_| {
+            let mut new_keymap = (*keymap).clone();
+            let mut number = new_keymap.layers().len();
+            while new_keymap.layers().iter().any(|layer| layer.name == format!("Layer {}", number)) {
+                number += 1;
+            }
+            if let Err(e) = new_keymap.add_layer(format!("Layer {}", number)) {
+                web_sys::console::log_1(&format!("Add layer error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_rename_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |(index, name): (usize, String)| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.rename_layer(index, name) {
+                web_sys::console::log_1(&format!("Rename layer error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_move_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |(from, to): (usize, usize)| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.move_layer(from, to) {
+                web_sys::console::log_1(&format!("Move layer error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_duplicate_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |index: usize| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.duplicate_layer(index) {
+                web_sys::console::log_1(&format!("Duplicate layer error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let on_delete_layer = {
+        let keymap = keymap.clone();
+        Callback::from(move |index: usize| {
+            let mut new_keymap = (*keymap).clone();
+            if let Err(e) = new_keymap.delete_layer(index) {
+                web_sys::console::log_1(&format!("Delete layer error: {}", e).into());
+            }
+            keymap.set(new_keymap);
+        })
+    };
+
+    let layer_names: Vec<String> = keymap.layers().iter()
+        .map(|layer| layer.name.clone())
+        .collect();
+
+    let keyboard_options: Vec<(String, String)> = keyboards::keyboard_models().iter()
+        .chain(keymap.custom_keyboards().iter())
+        .map(|model| (model.id.clone(), model.name.clone()))
+        .collect();
+
+    let export_formats: Vec<(String, String)> = ExportFormat::ALL.iter()
+        .map(|format| (format.id().to_string(), format.name().to_string()))
+        .collect();
+
     html! {
-        <div class="app">
+        <div
+            class={classes!("app", (*is_file_drag_over).then_some("file-drag-over"))}
+            ondragover={on_file_drag_over}
+            ondragleave={on_file_drag_leave}
+            ondrop={on_file_drop}
+        >
             <Header 
                 on_save_layout={on_save_layout}
                 on_load_layout={on_load_layout}
                 on_reset_layout={on_reset_layout}
                 on_factory_reset_layout={on_factory_reset_layout}
                 on_export_layout={on_export_layout}
+                on_import_layout={on_import_layout}
+                export_formats={export_formats}
+                on_export_firmware={on_export_firmware}
+                on_undo={on_undo}
+                on_redo={on_redo}
                 has_unsaved_changes={keymap.has_unsaved_changes()}
+                can_undo={keymap.can_undo()}
+                can_redo={keymap.can_redo()}
+                keyboard_name={keymap.keyboard().name.clone()}
+                keyboard_id={keymap.keyboard().id.clone()}
+                keyboard_options={keyboard_options}
+                on_select_keyboard={on_select_keyboard}
+                profiles={keymap.profiles().profiles().to_vec()}
+                active_profile={keymap.profiles().active().id.clone()}
+                on_select_profile={on_select_profile}
+                on_create_profile={on_create_profile}
+                on_rename_profile={on_rename_profile}
+                on_duplicate_profile={on_duplicate_profile}
+                on_delete_profile={on_delete_profile}
+            />
+
+            <LayerTabs
+                layer_names={layer_names}
+                active_layer={keymap.active_layer()}
+                on_select_layer={on_select_layer}
+                on_add_layer={on_add_layer}
+                on_rename_layer={on_rename_layer}
+                on_move_layer={on_move_layer}
+                on_duplicate_layer={on_duplicate_layer}
+                on_delete_layer={on_delete_layer}
             />
             
             <Layout 
+                keyboard={keymap.keyboard().clone()}
                 keymap={keymap.current().clone()}
+                unmapped={keymap.layers()[keymap.active_layer()].unmapped.clone()}
                 selected_key={*selected_key}
                 on_key_click={on_key_click}
                 on_key_change={on_key_change}
@@ -116,3 +568,42 @@ pub fn app() -> Html {
     }
 }
 
+/// Wait for a started save in the background, then mark it saved on the keymap of the latest
+/// render, keeping any edits made in the meantime
+fn finish_save(
+    save: Result<PendingSave, String>,
+    context: &'static str,
+    latest_keymap: Rc<RefCell<UseStateHandle<Keymap>>>,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let save = match save {
+            Ok(save) => save.wait().await,
+            Err(e) => Err(e),
+        };
+        match save {
+            Ok(save) => {
+                let keymap = latest_keymap.borrow().clone();
+                let mut new_keymap = (*keymap).clone();
+                new_keymap.finish_save(save);
+                keymap.set(new_keymap);
+            }
+            Err(e) => {
+                web_sys::console::log_1(&format!("{}: {}", context, e).into());
+                gloo::dialogs::alert(&e);
+            }
+        }
+    });
+}
+
+/// A heading followed by the first few problems of a list, for an alert
+fn problem_list(heading: &str, problems: &[String]) -> String {
+    const SHOWN: usize = 10;
+    let mut message = format!("{}\n", heading);
+    for problem in problems.iter().take(SHOWN) {
+        message.push_str(&format!("\n• {}", problem));
+    }
+    if problems.len() > SHOWN {
+        message.push_str(&format!("\n… and {} more", problems.len() - SHOWN));
+    }
+    message
+}
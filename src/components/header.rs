@@ -1,4 +1,7 @@
 use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use wasm_bindgen::JsValue;
+use crate::storage::Profile;
 
 #[derive(Properties, PartialEq)]
 pub struct HeaderProps {
@@ -7,7 +10,39 @@ pub struct HeaderProps {
     pub on_reset_layout: Callback<()>,
     pub on_factory_reset_layout: Callback<()>,
     pub on_export_layout: Callback<()>,
+    /// `(id, name)` of every firmware format that can be downloaded
+    pub export_formats: Vec<(String, String)>,
+    pub on_export_firmware: Callback<String>,
+    pub on_import_layout: Callback<gloo::file::File>,
+    pub on_undo: Callback<()>,
+    pub on_redo: Callback<()>,
     pub has_unsaved_changes: bool,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub keyboard_name: String,
+    pub keyboard_id: String,
+    /// `(id, name)` of every selectable keyboard model
+    pub keyboard_options: Vec<(String, String)>,
+    pub on_select_keyboard: Callback<String>,
+    pub profiles: Vec<Profile>,
+    pub active_profile: String,
+    pub on_select_profile: Callback<String>,
+    pub on_create_profile: Callback<()>,
+    pub on_rename_profile: Callback<()>,
+    pub on_duplicate_profile: Callback<()>,
+    pub on_delete_profile: Callback<()>,
+}
+
+/// "Saved <local time>" for a profile's ISO 8601 modification time
+fn modified_label(profile: Option<&Profile>) -> String {
+    match profile.and_then(|profile| profile.modified_at.as_deref()) {
+        Some(modified_at) => {
+            let date = js_sys::Date::new(&JsValue::from_str(modified_at));
+            let local = date.to_locale_string("default", &JsValue::UNDEFINED);
+            format!("Saved {}", local.as_string().unwrap_or_else(|| modified_at.to_string()))
+        }
+        None => "Never saved".to_string(),
+    }
 }
 
 #[function_component(Header)]
@@ -47,13 +82,141 @@ pub fn header(props: &HeaderProps) -> Html {
         })
     };
 
+    let export_format = use_node_ref();
+
+    let on_export_firmware = {
+        let export_format = export_format.clone();
+        let on_export_firmware = props.on_export_firmware.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            if let Some(select) = export_format.cast::<HtmlSelectElement>() {
+                on_export_firmware.emit(select.value());
+            }
+        })
+    };
+
+    let on_undo_click = {
+        let on_undo = props.on_undo.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_undo.emit(());
+        })
+    };
+
+    let on_redo_click = {
+        let on_redo = props.on_redo.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_redo.emit(());
+        })
+    };
+
+    let import_input = use_node_ref();
+
+    let on_import_click = {
+        let import_input = import_input.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            if let Some(input) = import_input.cast::<HtmlInputElement>() {
+                input.click();
+            }
+        })
+    };
+
+    let on_import_file = {
+        let on_import_layout = props.on_import_layout.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                on_import_layout.emit(gloo::file::File::from(file));
+            }
+            // Allow importing the same file again
+            input.set_value("");
+        })
+    };
+
+    let on_profile_change = {
+        let on_select_profile = props.on_select_profile.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_dyn_into::<HtmlSelectElement>().unwrap();
+            on_select_profile.emit(select.value());
+        })
+    };
+
+    let on_create = {
+        let on_create_profile = props.on_create_profile.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_create_profile.emit(());
+        })
+    };
+
+    let on_rename = {
+        let on_rename_profile = props.on_rename_profile.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_rename_profile.emit(());
+        })
+    };
+
+    let on_duplicate = {
+        let on_duplicate_profile = props.on_duplicate_profile.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_duplicate_profile.emit(());
+        })
+    };
+
+    let on_delete = {
+        let on_delete_profile = props.on_delete_profile.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_delete_profile.emit(());
+        })
+    };
+
+    let active_profile = props.profiles.iter().find(|profile| profile.id == props.active_profile);
+
+    let on_keyboard_change = {
+        let on_select_keyboard = props.on_select_keyboard.clone();
+        Callback::from(move |e: Event| {
+            let select = e.target_dyn_into::<HtmlSelectElement>().unwrap();
+            on_select_keyboard.emit(select.value());
+        })
+    };
+
     html! {
         <header class="header">
-            <h1>{"Dactyl Manuform 5x7 Layout Editor"}</h1>
+            <h1>{format!("{} Layout Editor", props.keyboard_name)}</h1>
             <div class="header-controls">
+                <select class="keyboard-select" onchange={on_keyboard_change} title="Keyboard model">
+                    {for props.keyboard_options.iter().map(|(id, name)| html! {
+                        <option value={id.clone()} selected={*id == props.keyboard_id}>{name}</option>
+                    })}
+                </select>
+                <div class="profile-controls">
+                    <select class="profile-select" onchange={on_profile_change} title="Layout profile">
+                        {for props.profiles.iter().map(|profile| html! {
+                            <option value={profile.id.clone()} selected={profile.id == props.active_profile}>{&profile.name}</option>
+                        })}
+                    </select>
+                    <span class="profile-modified">{modified_label(active_profile)}</span>
+                    <button onclick={on_create} title="New profile">{"New"}</button>
+                    <button onclick={on_rename} title="Rename profile">{"Rename"}</button>
+                    <button onclick={on_duplicate} title="Duplicate profile">{"Duplicate"}</button>
+                    <button class="profile-delete-btn" onclick={on_delete} title="Delete profile" disabled={props.profiles.len() <= 1}>{"Delete"}</button>
+                </div>
+                <div class="history-controls">
+                    <button class="undo-btn" onclick={on_undo_click} title="Undo (Ctrl+Z)" disabled={!props.can_undo}>
+                        <svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                            <path d="M9 14 4 9l5-5"/>
+                            <path d="M4 9h10.5a5.5 5.5 0 0 1 0 11H11"/>
+                        </svg>
+                        {"Undo"}
+                    </button>
+                    <button class="redo-btn" onclick={on_redo_click} title="Redo (Ctrl+Shift+Z)" disabled={!props.can_redo}>
+                        <svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                            <path d="m15 14 5-5-5-5"/>
+                            <path d="M20 9H9.5a5.5 5.5 0 0 0 0 11H13"/>
+                        </svg>
+                        {"Redo"}
+                    </button>
+                </div>
                 <div class="layout-controls">
                     <button 
-                        class={classes!("save-btn", props.has_unsaved_changes.then(|| "has-changes"))} 
+                        class={classes!("save-btn", props.has_unsaved_changes.then_some("has-changes"))} 
                         onclick={on_save} 
                         title={if props.has_unsaved_changes { "Save Layout (unsaved changes)" } else { "Save Layout" }}
                     >
@@ -84,6 +247,33 @@ pub fn header(props: &HeaderProps) -> Html {
                         </svg>
                         {"Export"}
                     </button>
+
+                    <div class="firmware-export">
+                        <select class="firmware-select" ref={export_format} title="Firmware format">
+                            {for props.export_formats.iter().map(|(id, name)| html! {
+                                <option value={id.clone()}>{name}</option>
+                            })}
+                        </select>
+                        <button class="firmware-export-btn" onclick={on_export_firmware} title="Download the layout for your firmware">
+                            {"Download"}
+                        </button>
+                    </div>
+
+                    <button class="import-btn" onclick={on_import_click} title="Import a layout, a QMK keymap.json or keymap.c, a ZMK .keymap, a VIA/Vial backup, or a KLE layout as a new keyboard (or drop a file onto the page)">
+                        <svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                            <path d="M21 15v4a2 2 0 0 1-2 2H5a2 2 0 0 1-2-2v-4"/>
+                            <polyline points="17 8 12 3 7 8"/>
+                            <line x1="12" y1="3" x2="12" y2="15"/>
+                        </svg>
+                        {"Import"}
+                    </button>
+                    <input
+                        type="file"
+                        accept=".json,.c,.keymap,.vil,application/json"
+                        class="import-input"
+                        ref={import_input}
+                        onchange={on_import_file}
+                    />
                     
                     <button class="reset-btn" onclick={on_reset} title="Reset unsaved changes" disabled={!props.has_unsaved_changes}>
                         <svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
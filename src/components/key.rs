@@ -1,14 +1,17 @@
 use yew::prelude::*;
 use wasm_bindgen::JsCast;
-use crate::keycodes::KeyboardUsage;
+use crate::actions::KeyAction;
 
 #[derive(Properties, PartialEq)]
 pub struct KeyProps {
-    pub key_config: KeyboardUsage,
+    pub key_config: KeyAction,
+    /// Firmware keycode the editor cannot represent, shown as the label
+    #[prop_or_default]
+    pub unmapped: Option<AttrValue>,
     pub is_selected: bool,
     pub onclick: Callback<()>,
     #[prop_or_default]
-    pub is_thumb: bool,
+    pub style: Option<AttrValue>,
     #[prop_or_default]
     pub on_drop: Option<Callback<String>>,
 }
@@ -68,16 +71,22 @@ pub fn key(props: &KeyProps) -> Html {
         <button 
             class={classes!(
                 "key", 
-                props.is_thumb.then(|| "thumb-key"),
-                props.is_selected.then(|| "selected"),
-                (*is_drag_over && props.on_drop.is_some()).then(|| "drag-over")
+                (!matches!(props.key_config, KeyAction::Key(_) | KeyAction::Consumer(_) | KeyAction::Mouse(_))).then_some("action-key"),
+                matches!(props.key_config, KeyAction::Mouse(_)).then_some("mouse-key"),
+                props.unmapped.is_some().then_some("unmapped-key"),
+                props.is_selected.then_some("selected"),
+                (*is_drag_over && props.on_drop.is_some()).then_some("drag-over")
             )}
+            style={props.style.clone()}
             onclick={onclick}
             ondragover={on_drag_over}
             ondragleave={on_drag_leave}
             ondrop={on_drop}
         >
-            {Into::<&'static str>::into(props.key_config)}
+            {match &props.unmapped {
+                Some(keycode) => keycode.to_string(),
+                None => props.key_config.to_string(),
+            }}
         </button>
     }
 }
@@ -1,11 +1,11 @@
 use yew::prelude::*;
 use web_sys::HtmlInputElement;
-use crate::keycodes::KeyboardUsage;
+use crate::actions::KeyAction;
 
 #[derive(Properties, PartialEq)]
 pub struct KeyEditorProps {
     pub selected_key: Option<(usize, usize)>,
-    pub key_config: Option<KeyboardUsage>,
+    pub key_config: Option<KeyAction>,
     pub on_key_change: Callback<String>,
 }
 
@@ -21,16 +21,19 @@ pub fn key_editor(props: &KeyEditorProps) -> Html {
     };
 
     if let Some((row, col)) = props.selected_key {
-        let key_config = props.key_config.clone().unwrap_or(KeyboardUsage::KeyboardErrorRollOver);
+        let key_config = props.key_config.unwrap_or(KeyAction::NoOp);
         html! {
             <div class="key-editor">
                 <h3>{format!("Editing Key [{}, {}]", row, col)}</h3>
                 <input 
                     type="text" 
-                    value={Into::<&'static str>::into(key_config)} 
+                    value={key_config.to_string()} 
                     placeholder="Key label"
                     onchange={on_change}
                 />
+                <p class="editor-instructions">
+                    {"Enter a key label or a QMK, ZMK, Kanata or evdev key name (KC_ESC, esc), a shortcut like Ctrl+C, a mouse key (Btn1, MsUp, WhDown), or a layer action: MO(1), TG(1), OSL(1), LT(1, Space), MT(Ctrl, Esc), Trans."}
+                </p>
             </div>
         }
     } else {
@@ -1,56 +1,79 @@
 use yew::prelude::*;
 use once_cell::sync::Lazy;
 use wasm_bindgen::JsCast;
-use crate::keycodes::KeyboardUsage;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
 
-static KEY_CATEGORIES: Lazy<Vec<(&'static str, Vec<KeyboardUsage>)>> = Lazy::new(|| {
+static KEY_CATEGORIES: Lazy<Vec<(&'static str, Vec<KeyAction>)>> = Lazy::new(|| {
     use KeyboardUsage::*;
+    use KeyAction::*;
+
+    let keys = |usages: Vec<KeyboardUsage>| -> Vec<KeyAction> {
+        usages.into_iter().map(KeyAction::Key).collect()
+    };
+    let ctrl = |key| Modified { modifiers: Modifiers::LEFT_CTRL, key };
     
     vec![
-        ("Letters", vec![
+        ("Letters", keys(vec![
             KeyboardAa, KeyboardBb, KeyboardCc, KeyboardDd, KeyboardEe, KeyboardFf, KeyboardGg, 
             KeyboardHh, KeyboardIi, KeyboardJj, KeyboardKk, KeyboardLl, KeyboardMm, KeyboardNn, 
             KeyboardOo, KeyboardPp, KeyboardQq, KeyboardRr, KeyboardSs, KeyboardTt, KeyboardUu, 
             KeyboardVv, KeyboardWw, KeyboardXx, KeyboardYy, KeyboardZz
-        ]),
-        ("Numbers", vec![
+        ])),
+        ("Numbers", keys(vec![
             Keyboard0CloseParens, Keyboard1Exclamation, Keyboard2At, Keyboard3Hash, Keyboard4Dollar, 
             Keyboard5Percent, Keyboard6Caret, Keyboard7Ampersand, Keyboard8Asterisk, Keyboard9OpenParens
-        ]),
-        ("Function Keys", vec![
+        ])),
+        ("Function Keys", keys(vec![
             KeyboardF1, KeyboardF2, KeyboardF3, KeyboardF4, KeyboardF5, KeyboardF6, 
             KeyboardF7, KeyboardF8, KeyboardF9, KeyboardF10, KeyboardF11, KeyboardF12
-        ]),
-        ("Modifiers", vec![
+        ])),
+        ("Modifiers", keys(vec![
             KeyboardLeftShift, KeyboardRightShift, KeyboardLeftControl, KeyboardRightControl, 
             KeyboardLeftAlt, KeyboardRightAlt, KeyboardLeftGUI, KeyboardRightGUI
-        ]),
-        ("Navigation", vec![
+        ])),
+        ("Navigation", keys(vec![
             KeyboardUpArrow, KeyboardDownArrow, KeyboardLeftArrow, KeyboardRightArrow, 
             KeyboardHome, KeyboardEnd, KeyboardPageUp, KeyboardPageDown, KeyboardInsert, 
             KeyboardDelete, KeyboardBackspace, KeyboardTab, KeyboardEscape, KeyboardPrintScreen, 
             KeyboardPause, KeyboardScrollLock
-        ]),
-        ("Symbols", vec![
+        ])),
+        ("Symbols", keys(vec![
             KeyboardSpacebar, KeyboardEnter, KeyboardDashUnderscore, KeyboardEqualPlus, 
             KeyboardOpenBracketBrace, KeyboardCloseBracketBrace, KeyboardBackslashBar, 
             KeyboardSemiColon, KeyboardSingleDoubleQuote, KeyboardCommaLess, KeyboardPeriodGreater, 
             KeyboardSlashQuestion, KeyboardBacktickTilde
-        ]),
-        ("Special", vec![
-            KeyboardCapsLock, KeypadNumLock, KeyboardScrollLock, KeyboardPause, KeyboardPower, 
-            KeyboardMute, KeyboardVolumeUp, KeyboardVolumeDown, KeyboardEmpty
-        ]),
-        ("Numpad", vec![
+        ])),
+        ("Special", keys(vec![
+            KeyboardCapsLock, KeypadNumLock, KeyboardScrollLock, KeyboardPause, KeyboardPower
+        ])),
+        ("Media", ConsumerUsage::all().map(Consumer).collect()),
+        ("Mouse", MouseAction::all().map(Mouse).collect()),
+        ("Numpad", keys(vec![
             Keypad0Insert, Keypad1End, Keypad2DownArrow, Keypad3PageDown, Keypad4LeftArrow, 
             Keypad5, Keypad6RightArrow, Keypad7Home, Keypad8UpArrow, Keypad9PageUp, 
             KeypadPeriodDelete, KeypadEnter, KeypadPlus, KeypadMinus, KeypadMultiply, 
             KeypadDivide, KeypadEqual
+        ])),
+        ("Layers", vec![
+            MomentaryLayer(1), MomentaryLayer(2), MomentaryLayer(3),
+            ToggleLayer(1), ToggleLayer(2), ToggleLayer(3),
+            OneShotLayer(1), OneShotLayer(2),
+            LayerTap { layer: 1, tap: KeyboardSpacebar }, LayerTap { layer: 2, tap: KeyboardEnter },
+            ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape },
+            ModTap { modifiers: Modifiers::LEFT_SHIFT, tap: KeyboardSpacebar },
+            Transparent, NoOp
+        ]),
+        ("Shortcuts", vec![
+            ctrl(KeyboardCc), ctrl(KeyboardVv), ctrl(KeyboardXx), ctrl(KeyboardZz), ctrl(KeyboardYy),
+            ctrl(KeyboardAa), ctrl(KeyboardSs), ctrl(KeyboardFf),
+            Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz },
+            Modified { modifiers: Modifiers::LEFT_ALT, key: KeyboardTab }
         ]),
     ]
 });
 
-pub fn get_all_categories() -> Vec<(String, Vec<KeyboardUsage>)> {
+pub fn get_all_categories() -> Vec<(String, Vec<KeyAction>)> {
     KEY_CATEGORIES
         .iter()
         .map(|(name, keys)| (name.to_string(), keys.clone()))
@@ -59,7 +82,7 @@ pub fn get_all_categories() -> Vec<(String, Vec<KeyboardUsage>)> {
 
 #[derive(Properties, PartialEq)]
 pub struct KeyLibraryProps {
-    pub on_key_select: Callback<KeyboardUsage>,
+    pub on_key_select: Callback<KeyAction>,
 }
 
 #[function_component(KeyLibrary)]
@@ -80,20 +103,18 @@ pub fn key_library(props: &KeyLibraryProps) -> Html {
                                 <h4 class="category-title">{&category}</h4>
                                 <div class="key-grid">
                                     {
-                                        keys.into_iter().map(|keycode| {
-                                            let label: &str = keycode.into();
+                                        keys.into_iter().map(|action| {
+                                            let label = action.to_string();
                                             let on_select = {
-                                                let keycode = keycode.clone();
                                                 let on_key_select = props.on_key_select.clone();
                                                 Callback::from(move |_| {
-                                                    on_key_select.emit(keycode.clone());
+                                                    on_key_select.emit(action);
                                                 })
                                             };
                                             
                                             let on_drag_start = {
-                                                let keycode = keycode.clone();
                                                 Callback::from(move |e: DragEvent| {
-                                                    let label: String = keycode.clone().into();
+                                                    let label = action.to_string();
                                                     web_sys::console::log_1(&format!("Dragging library key: {}", label).into());
                                                     if let Some(target) = e.target() {
                                                         if let Ok(element) = target.dyn_into::<web_sys::HtmlElement>() {
@@ -103,16 +124,19 @@ pub fn key_library(props: &KeyLibraryProps) -> Html {
                                                 })
                                             };
                                             
+                                            // No-op keys have an empty label, so give them a visible name here
+                                            let display = if label.is_empty() { "None".to_string() } else { label.clone() };
+                                            
                                             html! {
                                                 <button 
                                                     class="library-key"
                                                     onclick={on_select}
-                                                    key={label}
-                                                    title={format!("Click to use '{}' or drag to keyboard", label)}
+                                                    key={display.clone()}
+                                                    title={format!("Click to use '{}' or drag to keyboard", display)}
                                                     draggable="true"
                                                     ondragstart={on_drag_start}
                                                 >
-                                                    {label}
+                                                    {display}
                                                 </button>
                                             }
                                         }).collect::<Html>()
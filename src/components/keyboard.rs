@@ -1,12 +1,23 @@
 use yew::prelude::*;
-use super::hand::Hand;
-use super::thumb_cluster::ThumbCluster;
+use super::key::Key;
 use std::collections::HashMap;
-use crate::keycodes::KeyboardUsage;
+use std::rc::Rc;
+use crate::actions::KeyAction;
+use crate::keyboards::KeyboardModel;
+use crate::keymap::UnmappedKeycode;
+
+/// Size of one key unit in pixels, including the gap between keys
+const KEY_UNIT_PX: f32 = 54.0;
+/// Gap between neighbouring keys in pixels
+const KEY_GAP_PX: f32 = 4.0;
 
 #[derive(Properties, PartialEq)]
 pub struct KeyboardProps {
-    pub keymap: HashMap<(usize, usize), KeyboardUsage>,
+    pub keyboard: Rc<KeyboardModel>,
+    pub keymap: HashMap<(usize, usize), KeyAction>,
+    /// Keycodes kept from imported firmware files, shown instead of the action
+    #[prop_or_default]
+    pub unmapped: HashMap<(usize, usize), UnmappedKeycode>,
     pub selected_key: Option<(usize, usize)>,
     pub on_key_click: Callback<(usize, usize)>,
     #[prop_or_default]
@@ -15,48 +26,54 @@ pub struct KeyboardProps {
 
 #[function_component(Keyboard)]
 pub fn keyboard(props: &KeyboardProps) -> Html {
+    let geometry = &props.keyboard.geometry;
+    let (min_x, min_y, max_x, max_y) = geometry.bounds();
+    let board_style = format!(
+        "width: {}px; height: {}px;",
+        (max_x - min_x) * KEY_UNIT_PX,
+        (max_y - min_y) * KEY_UNIT_PX,
+    );
+
     html! {
         <div class="keyboard-container">
-            <div class="keyboard">
-                <div class="left-hand">
-                    <Hand 
-                        keymap={props.keymap.clone()}
-                        selected_key={props.selected_key}
-                        on_key_click={props.on_key_click.clone()}
-                        is_left={true}
-                        on_key_drop={props.on_key_drop.clone()}
-                    />
-                </div>
-                <div class="right-hand">
-                    <Hand 
-                        keymap={props.keymap.clone()}
-                        selected_key={props.selected_key}
-                        on_key_click={props.on_key_click.clone()}
-                        is_left={false}
-                        on_key_drop={props.on_key_drop.clone()}
-                    />
-                </div>
-            </div>
-            
-            <div class="thumb-clusters">
-                <div class="left-thumb">
-                    <ThumbCluster 
-                        keymap={props.keymap.clone()}
-                        selected_key={props.selected_key}
-                        on_key_click={props.on_key_click.clone()}
-                        is_left={true}
-                        on_key_drop={props.on_key_drop.clone()}
-                    />
-                </div>
-                <div class="right-thumb">
-                    <ThumbCluster 
-                        keymap={props.keymap.clone()}
-                        selected_key={props.selected_key}
-                        on_key_click={props.on_key_click.clone()}
-                        is_left={false}
-                        on_key_drop={props.on_key_drop.clone()}
-                    />
-                </div>
+            <div class="keyboard" style={board_style}>
+                {for geometry.keys().iter().map(|key| {
+                    let (row, col) = key.position();
+                    let key_config = props.keymap.get(&(row, col)).copied().unwrap_or(KeyAction::NoOp);
+                    let is_selected = props.selected_key == Some((row, col));
+                    let onclick = {
+                        let on_key_click = props.on_key_click.clone();
+                        move |_| on_key_click.emit((row, col))
+                    };
+
+                    let on_drop = props.on_key_drop.as_ref().map(|callback| {
+                        let callback = callback.clone();
+                        Callback::from(move |key: String| {
+                            callback.emit(((row, col), key));
+                        })
+                    });
+
+                    let style = format!(
+                        "left: {}px; top: {}px; width: {}px; height: {}px; --rotation: {}deg;",
+                        (key.x - min_x) * KEY_UNIT_PX,
+                        (key.y - min_y) * KEY_UNIT_PX,
+                        key.width * KEY_UNIT_PX - KEY_GAP_PX,
+                        key.height * KEY_UNIT_PX - KEY_GAP_PX,
+                        key.rotation,
+                    );
+
+                    html! {
+                        <Key 
+                            key={format!("{}-{}", row, col)}
+                            key_config={key_config}
+                            unmapped={props.unmapped.get(&(row, col)).map(|unmapped| AttrValue::from(unmapped.keycode.clone()))}
+                            is_selected={is_selected}
+                            onclick={onclick}
+                            style={style}
+                            on_drop={on_drop}
+                        />
+                    }
+                })}
             </div>
         </div>
     }
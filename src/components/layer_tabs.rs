@@ -0,0 +1,102 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+
+#[derive(Properties, PartialEq)]
+pub struct LayerTabsProps {
+    pub layer_names: Vec<String>,
+    pub active_layer: usize,
+    pub on_select_layer: Callback<usize>,
+    pub on_add_layer: Callback<()>,
+    pub on_rename_layer: Callback<(usize, String)>,
+    pub on_move_layer: Callback<(usize, usize)>,
+    pub on_duplicate_layer: Callback<usize>,
+    pub on_delete_layer: Callback<usize>,
+}
+
+#[function_component(LayerTabs)]
+pub fn layer_tabs(props: &LayerTabsProps) -> Html {
+    let active = props.active_layer;
+    let layer_count = props.layer_names.len();
+
+    let on_add = {
+        let on_add_layer = props.on_add_layer.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_add_layer.emit(());
+        })
+    };
+
+    let on_rename = {
+        let on_rename_layer = props.on_rename_layer.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_dyn_into::<HtmlInputElement>().unwrap();
+            on_rename_layer.emit((active, input.value()));
+        })
+    };
+
+    let on_move_left = {
+        let on_move_layer = props.on_move_layer.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_move_layer.emit((active, active - 1));
+        })
+    };
+
+    let on_move_right = {
+        let on_move_layer = props.on_move_layer.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_move_layer.emit((active, active + 1));
+        })
+    };
+
+    let on_duplicate = {
+        let on_duplicate_layer = props.on_duplicate_layer.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_duplicate_layer.emit(active);
+        })
+    };
+
+    let on_delete = {
+        let on_delete_layer = props.on_delete_layer.clone();
+        Callback::from(move |_: web_sys::MouseEvent| {
+            on_delete_layer.emit(active);
+        })
+    };
+
+    html! {
+        <nav class="layer-tabs">
+            <div class="layer-tab-list">
+                {for props.layer_names.iter().enumerate().map(|(index, name)| {
+                    let onclick = {
+                        let on_select_layer = props.on_select_layer.clone();
+                        move |_| on_select_layer.emit(index)
+                    };
+
+                    html! {
+                        <button
+                            class={classes!("layer-tab", (index == active).then_some("active"))}
+                            onclick={onclick}
+                            title={format!("Layer {}: {}", index, name)}
+                        >
+                            <span class="layer-index">{index}</span>
+                            {name}
+                        </button>
+                    }
+                })}
+                <button class="layer-add-btn" onclick={on_add} title="Add Layer">{"+"}</button>
+            </div>
+
+            <div class="layer-controls">
+                <input
+                    type="text"
+                    class="layer-name-input"
+                    value={props.layer_names.get(active).cloned().unwrap_or_default()}
+                    placeholder="Layer name"
+                    onchange={on_rename}
+                />
+                <button onclick={on_move_left} title="Move layer left" disabled={active == 0}>{"◀"}</button>
+                <button onclick={on_move_right} title="Move layer right" disabled={active + 1 >= layer_count}>{"▶"}</button>
+                <button onclick={on_duplicate} title="Duplicate layer">{"Duplicate"}</button>
+                <button class="layer-delete-btn" onclick={on_delete} title="Delete layer" disabled={layer_count <= 1}>{"Delete"}</button>
+            </div>
+        </nav>
+    }
+}
@@ -3,11 +3,17 @@ use super::keyboard::Keyboard;
 use super::key_editor::KeyEditor;
 use super::key_library::KeyLibrary;
 use std::collections::HashMap;
-use crate::keycodes::KeyboardUsage;
+use std::rc::Rc;
+use crate::actions::KeyAction;
+use crate::keyboards::KeyboardModel;
+use crate::keymap::UnmappedKeycode;
 
 #[derive(Properties, PartialEq)]
 pub struct LayoutProps {
-    pub keymap: HashMap<(usize, usize), KeyboardUsage>,
+    pub keyboard: Rc<KeyboardModel>,
+    pub keymap: HashMap<(usize, usize), KeyAction>,
+    #[prop_or_default]
+    pub unmapped: HashMap<(usize, usize), UnmappedKeycode>,
     pub selected_key: Option<(usize, usize)>,
     pub on_key_click: Callback<(usize, usize)>,
     pub on_key_change: Callback<String>,
@@ -17,13 +23,12 @@ pub struct LayoutProps {
 #[function_component(Layout)]
 pub fn layout(props: &LayoutProps) -> Html {
     let key_config = props.selected_key
-        .and_then(|(row, col)| props.keymap.get(&(row, col)).cloned());
+        .and_then(|(row, col)| props.keymap.get(&(row, col)).copied());
 
     let on_carousel_key_select = {
         let on_key_change = props.on_key_change.clone();
-        Callback::from(move |keycode: KeyboardUsage| {
-            let label: String = keycode.into();
-            on_key_change.emit(label);
+        Callback::from(move |action: KeyAction| {
+            on_key_change.emit(action.to_string());
         })
     };
 
@@ -32,7 +37,9 @@ pub fn layout(props: &LayoutProps) -> Html {
             <div class="layout-container">
                 <div class="keyboard-section">
                     <Keyboard 
+                        keyboard={props.keyboard.clone()}
                         keymap={props.keymap.clone()}
+                        unmapped={props.unmapped.clone()}
                         selected_key={props.selected_key}
                         on_key_click={props.on_key_click.clone()}
                         on_key_drop={Some(props.on_key_drop.clone())}
@@ -1,13 +1,11 @@
 pub mod header;
 pub mod layout;
 pub mod keyboard;
-pub mod hand;
-pub mod thumb_cluster;
 pub mod key_editor;
 pub mod key;
-pub mod keymap;
 pub mod key_library;
+pub mod layer_tabs;
 
 pub use header::Header;
 pub use layout::Layout;
-pub use keymap::Keymap;
+pub use layer_tabs::LayerTabs;
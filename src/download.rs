@@ -0,0 +1,49 @@
+//! Browser file downloads
+
+use wasm_bindgen::JsCast;
+
+/// Offer `contents` to the user as a file download
+pub fn download_file(file_name: &str, contents: &str) -> Result<(), String> {
+    let window = web_sys::window().ok_or("Window not available")?;
+    let document = window.document().ok_or("Document not available")?;
+
+    // Create a blob with the file contents
+    let array = js_sys::Array::new();
+    array.push(&contents.into());
+
+    let blob = web_sys::Blob::new_with_str_sequence(&array)
+        .map_err(|_| "Failed to create blob")?;
+
+    // Create a download URL
+    let url = web_sys::Url::create_object_url_with_blob(&blob)
+        .map_err(|_| "Failed to create object URL")?;
+
+    // Create a temporary anchor element for download
+    let anchor = document.create_element("a")
+        .map_err(|_| "Failed to create anchor element")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| "Failed to cast to anchor element")?;
+
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+
+    // Set style using setAttribute
+    anchor.set_attribute("style", "display: none")
+        .map_err(|_| "Failed to set style")?;
+
+    // Append to body, click, and remove
+    let body = document.body().ok_or("Body not available")?;
+    body.append_child(&anchor)
+        .map_err(|_| "Failed to append anchor")?;
+
+    anchor.click();
+
+    body.remove_child(&anchor)
+        .map_err(|_| "Failed to remove anchor")?;
+
+    // Clean up the URL
+    web_sys::Url::revoke_object_url(&url)
+        .map_err(|_| "Failed to revoke object URL")?;
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! # Kanata
+//!
+//! `.kbd` configuration export, so a keymap can be emulated on any keyboard that Kanata
+//! remaps, such as a laptop's built-in one. `defsrc` lists the physical keys being remapped
+//! and every `deflayer` lists what those keys do on that layer, in the same order.
+//!
+//! The source key of each matrix position is the key it sends on the base layer, so the
+//! letters stay where they are printed. Positions that have no key of their own there, such
+//! as Lower and Raise or a second Backspace, are read from spare keys instead and reported
+//! as warnings. Layer keys become aliases, e.g. `(defalias lower (layer-while-held lower))`,
+//! as do mouse movement, wheel and speed keys, e.g. `(defalias ms_up (movemouse-accel-up ...))`.
+
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{KeyboardUsage, NamingScheme};
+use crate::keymap::Layer;
+use super::{action_at, ascii_art, layer_identifiers, source_keys, Conversion};
+
+/// Milliseconds a dual-role key waits before it counts as held
+const TAP_HOLD_TIMEOUT: u32 = 200;
+
+/// Milliseconds a one-shot layer stays active waiting for the next key
+const ONE_SHOT_TIMEOUT: u32 = 2000;
+
+/// Modifier bits with their Kanata prefix (`C-`) and key name
+const KANATA_MODIFIERS: [(Modifiers, &str, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "C", "lctl"),
+    (Modifiers::LEFT_SHIFT, "S", "lsft"),
+    (Modifiers::LEFT_ALT, "A", "lalt"),
+    (Modifiers::LEFT_GUI, "M", "lmet"),
+    (Modifiers::RIGHT_CTRL, "RC", "rctl"),
+    (Modifiers::RIGHT_SHIFT, "RS", "rsft"),
+    (Modifiers::RIGHT_ALT, "RA", "ralt"),
+    (Modifiers::RIGHT_GUI, "RM", "rmet"),
+];
+
+/// Mouse buttons with their Kanata key name
+const KANATA_MOUSE_BUTTONS: [(MouseAction, &str); 5] = [
+    (MouseAction::Button1, "mlft"),
+    (MouseAction::Button2, "mrgt"),
+    (MouseAction::Button3, "mmid"),
+    (MouseAction::Button4, "mbck"),
+    (MouseAction::Button5, "mfwd"),
+];
+
+/// Mouse movement, wheel and speed keys with their alias name and Kanata action. The
+/// pointer speeds up from 1 to 5 pixels every 4 ms over a second; the wheel scrolls a notch
+/// every 50 ms.
+const KANATA_MOUSE_ALIASES: [(MouseAction, &str, &str); 11] = [
+    (MouseAction::MoveUp, "ms_up", "(movemouse-accel-up 4 1000 1 5)"),
+    (MouseAction::MoveDown, "ms_down", "(movemouse-accel-down 4 1000 1 5)"),
+    (MouseAction::MoveLeft, "ms_left", "(movemouse-accel-left 4 1000 1 5)"),
+    (MouseAction::MoveRight, "ms_right", "(movemouse-accel-right 4 1000 1 5)"),
+    (MouseAction::WheelUp, "wh_up", "(mwheel-up 50 120)"),
+    (MouseAction::WheelDown, "wh_down", "(mwheel-down 50 120)"),
+    (MouseAction::WheelLeft, "wh_left", "(mwheel-left 50 120)"),
+    (MouseAction::WheelRight, "wh_right", "(mwheel-right 50 120)"),
+    (MouseAction::Accel0, "ms_slow", "(movemouse-speed 50)"),
+    (MouseAction::Accel1, "ms_normal", "(movemouse-speed 100)"),
+    (MouseAction::Accel2, "ms_fast", "(movemouse-speed 200)"),
+];
+
+/// The Kanata key name of a usage, e.g. `esc`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    usage.alias(NamingScheme::Kanata)
+}
+
+/// Name of the downloaded file, e.g. `dactyl_manuform_5x7.kbd`
+pub fn file_name(keyboard: &KeyboardModel) -> String {
+    format!("{}.kbd", keyboard.id)
+}
+
+/// Write the keymap as a Kanata `.kbd` configuration
+pub fn export_config(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let names: Vec<String> = layer_identifiers(layers).iter().map(|name| name.to_lowercase()).collect();
+    let mut warnings = Vec::new();
+    let sources: Vec<Vec<((usize, usize), &str)>> = source_keys(keyboard, layers, key_name, &mut warnings)
+        .into_iter()
+        .map(|row| row.into_iter().filter_map(|(position, usage)| Some((position, key_name(usage)?))).collect())
+        .collect();
+
+    // Every layer lists an action per source key, in the order of `defsrc`
+    let mut aliases: Vec<(String, String)> = Vec::new();
+    let layer_rows: Vec<Vec<Vec<String>>> = (0..layers.len())
+        .map(|index| {
+            sources.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&(position, _)| {
+                            let (action, warning) = match layers[index].unmapped.get(&position) {
+                                Some(unmapped) => ("XX".to_string(), Some(format!(
+                                    "{} keycode '{}' has no Kanata equivalent, exported as XX", unmapped.format, unmapped.keycode
+                                ))),
+                                None => layer_action(action_at(layers, index, position), &names, &mut aliases),
+                            };
+                            if let Some(warning) = warning {
+                                warnings.push(format!(
+                                    "Layer '{}' [{}, {}]: {}", layers[index].name, position.0, position.1, warning
+                                ));
+                            }
+                            action
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    // Cells are padded to a common width so `defsrc` and the layers line up as a grid
+    let width = sources.iter().flatten().map(|(_, source)| source.len())
+        .chain(layer_rows.iter().flatten().flatten().map(String::len))
+        .max()
+        .unwrap_or(0);
+    let grid = |rows: Vec<Vec<String>>| -> String {
+        rows.iter()
+            .map(|row| {
+                let cells: Vec<String> = row.iter().map(|cell| format!("{:<width$}", cell, width = width)).collect();
+                format!("  {}", cells.join(" ")).trim_end().to_string() + "\n"
+            })
+            .collect()
+    };
+
+    let mut output = format!(";; {} keymap exported by layout-rs\n\n", keyboard.name);
+    output.push_str("(defcfg\n  process-unmapped-keys yes\n)\n\n");
+    let source_rows = sources.iter()
+        .map(|row| row.iter().map(|(_, source)| source.to_string()).collect())
+        .collect();
+    output.push_str(&format!("(defsrc\n{})\n", grid(source_rows)));
+
+    if !aliases.is_empty() {
+        output.push_str("\n(defalias\n");
+        for (name, action) in &aliases {
+            output.push_str(&format!("  {} {}\n", name, action));
+        }
+        output.push_str(")\n");
+    }
+
+    for (index, rows) in layer_rows.into_iter().enumerate() {
+        output.push('\n');
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(&format!(";; {}\n", line));
+        }
+        output.push_str(&format!("(deflayer {}\n{})\n", names[index], grid(rows)));
+    }
+
+    Conversion { output, warnings }
+}
+
+/// Kanata action for a key, and a warning if it had to be approximated. Actions that are
+/// not a single key are written as aliases, added to `aliases` the first time they are used.
+fn layer_action(action: KeyAction, layers: &[String], aliases: &mut Vec<(String, String)>) -> (String, Option<String>) {
+    let layer_name = |layer: usize| layers.get(layer).cloned().unwrap_or_else(|| format!("layer{}", layer));
+    let name = |usage: KeyboardUsage| key_name(usage)
+        .ok_or_else(|| format!("Kanata has no key name for {:?}, exported as XX", usage));
+    let mut alias = |name: String, action: String| {
+        if !aliases.iter().any(|(existing, _)| *existing == name) {
+            aliases.push((name.clone(), action));
+        }
+        format!("@{}", name)
+    };
+    let held = |modifiers: Modifiers| {
+        let keys: Vec<&str> = KANATA_MODIFIERS.iter()
+            .filter(|&&(modifier, _, _)| modifiers.contains(modifier))
+            .map(|&(_, _, key)| key)
+            .collect();
+        match keys.as_slice() {
+            [key] => key.to_string(),
+            keys => format!("(multi {})", keys.join(" ")),
+        }
+    };
+
+    let mut warning = None;
+    let output = match action {
+        KeyAction::Key(usage) => name(usage).map(str::to_string),
+        KeyAction::Consumer(usage) => usage.alias(NamingScheme::Kanata)
+            .map(str::to_string)
+            .ok_or_else(|| format!("Kanata has no key name for {:?}, exported as XX", usage)),
+        KeyAction::Mouse(mouse) => match KANATA_MOUSE_BUTTONS.iter().find(|&&(other, _)| other == mouse) {
+            Some(&(_, name)) => Ok(name.to_string()),
+            None => KANATA_MOUSE_ALIASES.iter()
+                .find(|&&(other, _, _)| other == mouse)
+                .map(|&(_, name, definition)| alias(name.to_string(), definition.to_string()))
+                .ok_or_else(|| format!("Kanata has no mouse action for {:?}, exported as XX", mouse)),
+        },
+        KeyAction::Modified { modifiers, key } => name(key).map(|key| {
+            let prefixes: String = KANATA_MODIFIERS.iter()
+                .filter(|&&(modifier, _, _)| modifiers.contains(modifier))
+                .map(|&(_, prefix, _)| format!("{}-", prefix))
+                .collect();
+            format!("{}{}", prefixes, key)
+        }),
+        KeyAction::ModTap { modifiers, tap } => name(tap).map(|tap| {
+            let mods: Vec<&str> = KANATA_MODIFIERS.iter()
+                .filter(|&&(modifier, _, _)| modifiers.contains(modifier))
+                .map(|&(_, _, key)| key)
+                .collect();
+            alias(
+                format!("{}_{}", mods.join("_"), tap),
+                format!("(tap-hold {t} {t} {} {})", tap, held(modifiers), t = TAP_HOLD_TIMEOUT),
+            )
+        }),
+        KeyAction::LayerTap { layer, tap } => name(tap).map(|tap| {
+            let layer = layer_name(layer);
+            alias(
+                format!("{}_{}", layer, tap),
+                format!("(tap-hold {t} {t} {} (layer-while-held {}))", tap, layer, t = TAP_HOLD_TIMEOUT),
+            )
+        }),
+        KeyAction::MomentaryLayer(layer) => {
+            let layer = layer_name(layer);
+            Ok(alias(layer.clone(), format!("(layer-while-held {})", layer)))
+        }
+        KeyAction::ToggleLayer(index) => {
+            // Kanata has no layer toggle; switching the base layer is the closest
+            warning = Some(format!("Kanata has no layer toggle, TG({}) exported as layer-switch", index));
+            let layer = layer_name(index);
+            Ok(alias(format!("to_{}", layer), format!("(layer-switch {})", layer)))
+        }
+        KeyAction::OneShotLayer(layer) => {
+            let layer = layer_name(layer);
+            Ok(alias(
+                format!("os_{}", layer),
+                format!("(one-shot {} (layer-while-held {}))", ONE_SHOT_TIMEOUT, layer),
+            ))
+        }
+        KeyAction::Transparent => Ok("_".to_string()),
+        KeyAction::NoOp => Ok("XX".to_string()),
+    };
+    match output {
+        Ok(output) => (output, warning),
+        Err(warning) => ("XX".to_string(), Some(warning)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::keycodes::ConsumerUsage;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_layer_actions() {
+        let layers = ["base".to_string(), "lower".to_string()];
+        let mut aliases = Vec::new();
+        let mut action = |action| layer_action(action, &layers, &mut aliases).0;
+
+        assert_eq!(action(KeyAction::Key(KeyboardSpacebar)), "spc");
+        assert_eq!(action(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::RIGHT_ALT, key: KeyboardZz }), "C-RA-z");
+        assert_eq!(action(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), "@lctl_esc");
+        assert_eq!(action(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa }), "@lctl_lsft_a");
+        assert_eq!(action(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), "@lower_spc");
+        assert_eq!(action(KeyAction::MomentaryLayer(1)), "@lower");
+        assert_eq!(action(KeyAction::MomentaryLayer(1)), "@lower");
+        assert_eq!(action(KeyAction::ToggleLayer(5)), "@to_layer5");
+        assert_eq!(action(KeyAction::OneShotLayer(0)), "@os_base");
+        assert_eq!(action(KeyAction::Transparent), "_");
+        assert_eq!(action(KeyAction::NoOp), "XX");
+        assert_eq!(action(KeyAction::Consumer(ConsumerUsage::PlayPause)), "pp");
+        assert_eq!(action(KeyAction::Mouse(MouseAction::Button2)), "mrgt");
+        assert_eq!(action(KeyAction::Mouse(MouseAction::WheelUp)), "@wh_up");
+
+        assert_eq!(aliases, [
+            ("lctl_esc".to_string(), "(tap-hold 200 200 esc lctl)".to_string()),
+            ("lctl_lsft_a".to_string(), "(tap-hold 200 200 a (multi lctl lsft))".to_string()),
+            ("lower_spc".to_string(), "(tap-hold 200 200 spc (layer-while-held lower))".to_string()),
+            ("lower".to_string(), "(layer-while-held lower)".to_string()),
+            ("to_layer5".to_string(), "(layer-switch layer5)".to_string()),
+            ("os_base".to_string(), "(one-shot 2000 (layer-while-held base))".to_string()),
+            ("wh_up".to_string(), "(mwheel-up 50 120)".to_string()),
+        ]);
+
+        let (output, warning) = layer_action(KeyAction::Key(Keypad00), &layers, &mut aliases);
+        assert_eq!(output, "XX");
+        assert_eq!(warning.as_deref(), Some("Kanata has no key name for Keypad00, exported as XX"));
+        let (output, warning) = layer_action(KeyAction::Consumer(ConsumerUsage::ACHome), &layers, &mut aliases);
+        assert_eq!(output, "XX");
+        assert_eq!(warning.as_deref(), Some("Kanata has no key name for ACHome, exported as XX"));
+    }
+
+    #[test]
+    fn test_export_config() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_config(test_keyboard(), &layers);
+        let config = &export.output;
+
+        // Lower, Raise, the unused key and the second Backspace and Enter have no key of their own
+        assert_eq!(export.warnings, [
+            "[2, 13]: the empty key is read from the menu key",
+            "[4, 3]: 'MO(1)' is read from the lsgt key",
+            "[4, 10]: 'MO(2)' is read from the grv key",
+            "[7, 6]: 'BKSP' is read from the prnt key",
+            "[7, 8]: 'Enter' is read from the slck key",
+        ]);
+        assert!(config.starts_with(";; Dactyl Manuform 5x7 keymap exported by layout-rs\n"));
+        assert!(config.contains("(defsrc\n  esc    1      2      3"));
+        assert!(config.contains("(defalias\n  lower (layer-while-held lower)\n  raise (layer-while-held raise)\n)"));
+        assert!(config.contains("(deflayer base\n  esc    1      2      3"));
+        assert!(config.contains("  lctl   lalt   lmet   @lower @raise rmet"));
+        assert!(config.contains(";; | Esc   | 1     |"));
+        assert!(config.contains("(deflayer lower\n  _      f1     _"));
+        assert_eq!(config.matches("(deflayer ").count(), 3);
+        assert_eq!(file_name(test_keyboard()), "dactyl_manuform_5x7.kbd");
+    }
+}
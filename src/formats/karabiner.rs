@@ -0,0 +1,473 @@
+//! # Karabiner-Elements
+//!
+//! Complex-modification rule export for macOS, so a keymap can be emulated on a Mac's
+//! built-in keyboard. Each matrix position is read from a physical key (see [`source_keys`]),
+//! and every layer above the base layer is a `layout_rs_<layer>` variable that its layer keys
+//! set while held. A layer's manipulators only apply while its variable is set, and come before
+//! those of the layers below it, so transparent keys fall through to the next active layer.
+//!
+//! The output is a complete rule file: copy it to `~/.config/karabiner/assets/complex_modifications/`
+//! and enable it under Complex Modifications → Add rule.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::Layer;
+use super::{action_at, layer_identifiers, source_keys, Conversion};
+
+/// Key code that sends nothing
+const NONE_KEY: &str = "vk_none";
+
+/// Modifier bits with their Karabiner key code
+const KARABINER_MODIFIERS: [(Modifiers, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "left_control"),
+    (Modifiers::LEFT_SHIFT, "left_shift"),
+    (Modifiers::LEFT_ALT, "left_option"),
+    (Modifiers::LEFT_GUI, "left_command"),
+    (Modifiers::RIGHT_CTRL, "right_control"),
+    (Modifiers::RIGHT_SHIFT, "right_shift"),
+    (Modifiers::RIGHT_ALT, "right_option"),
+    (Modifiers::RIGHT_GUI, "right_command"),
+];
+
+/// Karabiner `key_code` names
+static KARABINER_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "a"),
+        (KeyboardBb, "b"),
+        (KeyboardCc, "c"),
+        (KeyboardDd, "d"),
+        (KeyboardEe, "e"),
+        (KeyboardFf, "f"),
+        (KeyboardGg, "g"),
+        (KeyboardHh, "h"),
+        (KeyboardIi, "i"),
+        (KeyboardJj, "j"),
+        (KeyboardKk, "k"),
+        (KeyboardLl, "l"),
+        (KeyboardMm, "m"),
+        (KeyboardNn, "n"),
+        (KeyboardOo, "o"),
+        (KeyboardPp, "p"),
+        (KeyboardQq, "q"),
+        (KeyboardRr, "r"),
+        (KeyboardSs, "s"),
+        (KeyboardTt, "t"),
+        (KeyboardUu, "u"),
+        (KeyboardVv, "v"),
+        (KeyboardWw, "w"),
+        (KeyboardXx, "x"),
+        (KeyboardYy, "y"),
+        (KeyboardZz, "z"),
+        (Keyboard1Exclamation, "1"),
+        (Keyboard2At, "2"),
+        (Keyboard3Hash, "3"),
+        (Keyboard4Dollar, "4"),
+        (Keyboard5Percent, "5"),
+        (Keyboard6Caret, "6"),
+        (Keyboard7Ampersand, "7"),
+        (Keyboard8Asterisk, "8"),
+        (Keyboard9OpenParens, "9"),
+        (Keyboard0CloseParens, "0"),
+        (KeyboardEnter, "return_or_enter"),
+        (KeyboardEscape, "escape"),
+        (KeyboardBackspace, "delete_or_backspace"),
+        (KeyboardTab, "tab"),
+        (KeyboardSpacebar, "spacebar"),
+        (KeyboardDashUnderscore, "hyphen"),
+        (KeyboardEqualPlus, "equal_sign"),
+        (KeyboardOpenBracketBrace, "open_bracket"),
+        (KeyboardCloseBracketBrace, "close_bracket"),
+        (KeyboardBackslashBar, "backslash"),
+        (KeyboardNonUSHash, "non_us_pound"),
+        (KeyboardSemiColon, "semicolon"),
+        (KeyboardSingleDoubleQuote, "quote"),
+        (KeyboardBacktickTilde, "grave_accent_and_tilde"),
+        (KeyboardCommaLess, "comma"),
+        (KeyboardPeriodGreater, "period"),
+        (KeyboardSlashQuestion, "slash"),
+        (KeyboardCapsLock, "caps_lock"),
+        (KeyboardF1, "f1"),
+        (KeyboardF2, "f2"),
+        (KeyboardF3, "f3"),
+        (KeyboardF4, "f4"),
+        (KeyboardF5, "f5"),
+        (KeyboardF6, "f6"),
+        (KeyboardF7, "f7"),
+        (KeyboardF8, "f8"),
+        (KeyboardF9, "f9"),
+        (KeyboardF10, "f10"),
+        (KeyboardF11, "f11"),
+        (KeyboardF12, "f12"),
+        (KeyboardF13, "f13"),
+        (KeyboardF14, "f14"),
+        (KeyboardF15, "f15"),
+        (KeyboardF16, "f16"),
+        (KeyboardF17, "f17"),
+        (KeyboardF18, "f18"),
+        (KeyboardF19, "f19"),
+        (KeyboardF20, "f20"),
+        (KeyboardF21, "f21"),
+        (KeyboardF22, "f22"),
+        (KeyboardF23, "f23"),
+        (KeyboardF24, "f24"),
+        (KeyboardPrintScreen, "print_screen"),
+        (KeyboardScrollLock, "scroll_lock"),
+        (KeyboardPause, "pause"),
+        (KeyboardInsert, "insert"),
+        (KeyboardHome, "home"),
+        (KeyboardPageUp, "page_up"),
+        (KeyboardDelete, "delete_forward"),
+        (KeyboardEnd, "end"),
+        (KeyboardPageDown, "page_down"),
+        (KeyboardRightArrow, "right_arrow"),
+        (KeyboardLeftArrow, "left_arrow"),
+        (KeyboardDownArrow, "down_arrow"),
+        (KeyboardUpArrow, "up_arrow"),
+        (KeypadNumLock, "keypad_num_lock"),
+        (KeypadDivide, "keypad_slash"),
+        (KeypadMultiply, "keypad_asterisk"),
+        (KeypadMinus, "keypad_hyphen"),
+        (KeypadPlus, "keypad_plus"),
+        (KeypadEnter, "keypad_enter"),
+        (Keypad1End, "keypad_1"),
+        (Keypad2DownArrow, "keypad_2"),
+        (Keypad3PageDown, "keypad_3"),
+        (Keypad4LeftArrow, "keypad_4"),
+        (Keypad5, "keypad_5"),
+        (Keypad6RightArrow, "keypad_6"),
+        (Keypad7Home, "keypad_7"),
+        (Keypad8UpArrow, "keypad_8"),
+        (Keypad9PageUp, "keypad_9"),
+        (Keypad0Insert, "keypad_0"),
+        (KeypadPeriodDelete, "keypad_period"),
+        (KeyboardNonUSSlash, "non_us_backslash"),
+        (KeyboardApplication, "application"),
+        (KeyboardPower, "power"),
+        (KeypadEqual, "keypad_equal_sign"),
+        (KeyboardExecute, "execute"),
+        (KeyboardHelp, "help"),
+        (KeyboardMenu, "menu"),
+        (KeyboardSelect, "select"),
+        (KeyboardStop, "stop"),
+        (KeyboardAgain, "again"),
+        (KeyboardUndo, "undo"),
+        (KeyboardCut, "cut"),
+        (KeyboardCopy, "copy"),
+        (KeyboardPaste, "paste"),
+        (KeyboardFind, "find"),
+        (KeyboardMute, "mute"),
+        (KeyboardVolumeUp, "volume_increment"),
+        (KeyboardVolumeDown, "volume_decrement"),
+        (KeypadComma, "keypad_comma"),
+        (KeypadEqualSign, "keypad_equal_sign_as400"),
+        (KeyboardInternational1, "international1"),
+        (KeyboardInternational2, "international2"),
+        (KeyboardInternational3, "international3"),
+        (KeyboardInternational4, "international4"),
+        (KeyboardInternational5, "international5"),
+        (KeyboardInternational6, "international6"),
+        (KeyboardInternational7, "international7"),
+        (KeyboardInternational8, "international8"),
+        (KeyboardInternational9, "international9"),
+        (KeyboardLANG1, "lang1"),
+        (KeyboardLANG2, "lang2"),
+        (KeyboardLANG3, "lang3"),
+        (KeyboardLANG4, "lang4"),
+        (KeyboardLANG5, "lang5"),
+        (KeyboardLANG6, "lang6"),
+        (KeyboardLANG7, "lang7"),
+        (KeyboardLANG8, "lang8"),
+        (KeyboardLANG9, "lang9"),
+        (KeyboardLeftControl, "left_control"),
+        (KeyboardLeftShift, "left_shift"),
+        (KeyboardLeftAlt, "left_option"),
+        (KeyboardLeftGUI, "left_command"),
+        (KeyboardRightControl, "right_control"),
+        (KeyboardRightShift, "right_shift"),
+        (KeyboardRightAlt, "right_option"),
+        (KeyboardRightGUI, "right_command"),
+    ])
+});
+
+/// Karabiner `consumer_key_code` of each consumer control it can send
+const KARABINER_CONSUMER_KEYS: [(ConsumerUsage, &str); 19] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, "display_brightness_increment"),
+    (ConsumerUsage::DisplayBrightnessDecrement, "display_brightness_decrement"),
+    (ConsumerUsage::FastForward, "fastforward"),
+    (ConsumerUsage::Rewind, "rewind"),
+    (ConsumerUsage::ScanNextTrack, "scan_next_track"),
+    (ConsumerUsage::ScanPreviousTrack, "scan_previous_track"),
+    (ConsumerUsage::Eject, "eject"),
+    (ConsumerUsage::PlayPause, "play_or_pause"),
+    (ConsumerUsage::Mute, "mute"),
+    (ConsumerUsage::VolumeIncrement, "volume_increment"),
+    (ConsumerUsage::VolumeDecrement, "volume_decrement"),
+    (ConsumerUsage::ALCalculator, "al_calculator"),
+    (ConsumerUsage::ALLocalMachineBrowser, "al_local_machine_browser"),
+    (ConsumerUsage::ACSearch, "ac_search"),
+    (ConsumerUsage::ACHome, "ac_home"),
+    (ConsumerUsage::ACBack, "ac_back"),
+    (ConsumerUsage::ACForward, "ac_forward"),
+    (ConsumerUsage::ACRefresh, "ac_refresh"),
+    (ConsumerUsage::ACBookmarks, "ac_bookmarks"),
+];
+
+/// Karabiner event of a mouse key: a `pointing_button`, or a `mouse_key` movement, wheel
+/// or speed multiplier with the magnitudes Karabiner's own mouse keys rules use
+fn mouse_event(action: MouseAction) -> Value {
+    match action {
+        MouseAction::Button1 => json!({ "pointing_button": "button1" }),
+        MouseAction::Button2 => json!({ "pointing_button": "button2" }),
+        MouseAction::Button3 => json!({ "pointing_button": "button3" }),
+        MouseAction::Button4 => json!({ "pointing_button": "button4" }),
+        MouseAction::Button5 => json!({ "pointing_button": "button5" }),
+        MouseAction::MoveUp => json!({ "mouse_key": { "y": -1536 } }),
+        MouseAction::MoveDown => json!({ "mouse_key": { "y": 1536 } }),
+        MouseAction::MoveLeft => json!({ "mouse_key": { "x": -1536 } }),
+        MouseAction::MoveRight => json!({ "mouse_key": { "x": 1536 } }),
+        MouseAction::WheelUp => json!({ "mouse_key": { "vertical_wheel": -32 } }),
+        MouseAction::WheelDown => json!({ "mouse_key": { "vertical_wheel": 32 } }),
+        MouseAction::WheelLeft => json!({ "mouse_key": { "horizontal_wheel": 32 } }),
+        MouseAction::WheelRight => json!({ "mouse_key": { "horizontal_wheel": -32 } }),
+        MouseAction::Accel0 => json!({ "mouse_key": { "speed_multiplier": 0.5 } }),
+        MouseAction::Accel1 => json!({ "mouse_key": { "speed_multiplier": 1.0 } }),
+        MouseAction::Accel2 => json!({ "mouse_key": { "speed_multiplier": 2.0 } }),
+    }
+}
+
+/// Karabiner `key_code` of a usage, e.g. `return_or_enter`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    KARABINER_KEYS.get(&usage).copied()
+}
+
+/// Whether a MacBook's built-in keyboard has the key: the main block, F1-F12, the arrows and
+/// the modifiers, less the Non-US # key and Right Control
+fn is_built_in(usage: KeyboardUsage) -> bool {
+    use KeyboardUsage::*;
+
+    let code = usage as u8;
+    let main_block = (KeyboardAa as u8..=KeyboardF12 as u8).contains(&code) && usage != KeyboardNonUSHash;
+    let arrows = (KeyboardRightArrow as u8..=KeyboardUpArrow as u8).contains(&code);
+    let modifiers = (KeyboardLeftControl as u8..=KeyboardRightGUI as u8).contains(&code) && usage != KeyboardRightControl;
+    main_block || arrows || modifiers || usage == KeyboardNonUSSlash
+}
+
+/// Name of the downloaded rule file
+pub fn file_name(keyboard: &KeyboardModel) -> String {
+    format!("{}_karabiner.json", keyboard.id)
+}
+
+/// Write the keymap as a Karabiner-Elements complex-modification rule file
+pub fn export_rules(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let mut warnings = Vec::new();
+    let sources = source_keys(keyboard, layers, |usage| key_name(usage).filter(|_| is_built_in(usage)), &mut warnings);
+    let variables: Vec<String> = layer_identifiers(layers).iter()
+        .map(|identifier| format!("layout_rs_{}", identifier.to_lowercase()))
+        .collect();
+
+    // Higher layers first, as Karabiner applies the first manipulator that matches
+    let mut manipulators = Vec::new();
+    for (index, layer) in layers.iter().enumerate().rev() {
+        for &(position, source) in sources.iter().flatten() {
+            let action = action_at(layers, index, position);
+            if action == KeyAction::Transparent || (index == 0 && action == KeyAction::Key(source)) {
+                continue;
+            }
+            let from = json!({ "key_code": key_name(source), "modifiers": { "optional": ["any"] } });
+            let (events, warning) = match layer.unmapped.get(&position) {
+                Some(unmapped) => (vec![json!({ "to": [{ "key_code": NONE_KEY }] })], Some(format!(
+                    "{} keycode '{}' has no Karabiner equivalent, exported as vk_none", unmapped.format, unmapped.keycode
+                ))),
+                None => action_events(action, &variables),
+            };
+            if let Some(warning) = warning {
+                warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+            }
+
+            for mut manipulator in events {
+                manipulator["type"] = json!("basic");
+                manipulator["from"] = from.clone();
+                if index > 0 {
+                    let conditions = manipulator["conditions"].as_array_mut().map(std::mem::take).unwrap_or_default();
+                    let active = json!({ "type": "variable_if", "name": variables[index], "value": 1 });
+                    manipulator["conditions"] = json!([vec![active], conditions].concat());
+                }
+                manipulators.push(manipulator);
+            }
+        }
+    }
+
+    let layer_names: Vec<&str> = layers.iter().map(|layer| layer.name.as_str()).collect();
+    let file = json!({
+        "title": format!("layout-rs: {}", keyboard.name),
+        "rules": [{
+            "description": format!("{} keymap ({})", keyboard.name, layer_names.join(", ")),
+            "manipulators": manipulators,
+        }],
+    });
+    let output = serde_json::to_string_pretty(&file).expect("rules serialize to JSON");
+
+    Conversion { output, warnings }
+}
+
+/// `to` event of a key with modifiers held
+fn key_event(modifiers: Modifiers, usage: KeyboardUsage) -> (Value, Option<String>) {
+    let modifiers: Vec<&str> = KARABINER_MODIFIERS.iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .map(|&(_, name)| name)
+        .collect();
+    match key_name(usage) {
+        Some(name) if modifiers.is_empty() => (json!({ "key_code": name }), None),
+        Some(name) => (json!({ "key_code": name, "modifiers": modifiers }), None),
+        None => (json!({ "key_code": NONE_KEY }), Some(format!("Karabiner has no key code for {:?}, exported as vk_none", usage))),
+    }
+}
+
+/// Manipulators, less `type`, `from` and the layer condition, for an action. Toggles take two:
+/// one to clear the layer while it is set and one to set it.
+fn action_events(action: KeyAction, variables: &[String]) -> (Vec<Value>, Option<String>) {
+    let set = |layer: usize, value: u8| json!({ "set_variable": { "name": variables[layer], "value": value } });
+    if let Some(layer) = action.layer().filter(|&layer| layer == 0 || layer >= variables.len()) {
+        let warning = format!("{} targets layer {}, which has no Karabiner variable, exported as vk_none", action, layer);
+        return (vec![json!({ "to": [{ "key_code": NONE_KEY }] })], Some(warning));
+    }
+
+    match action {
+        KeyAction::Key(usage) => {
+            let (event, warning) = key_event(Modifiers::NONE, usage);
+            (vec![json!({ "to": [event] })], warning)
+        }
+        KeyAction::Consumer(usage) => match KARABINER_CONSUMER_KEYS.iter().find(|&&(other, _)| other == usage) {
+            Some(&(_, name)) => (vec![json!({ "to": [{ "consumer_key_code": name }] })], None),
+            None => {
+                let warning = format!("Karabiner has no consumer key code for {:?}, exported as vk_none", usage);
+                (vec![json!({ "to": [{ "key_code": NONE_KEY }] })], Some(warning))
+            }
+        },
+        KeyAction::Mouse(mouse) => (vec![json!({ "to": [mouse_event(mouse)] })], None),
+        KeyAction::Modified { modifiers, key } => {
+            let (event, warning) = key_event(modifiers, key);
+            (vec![json!({ "to": [event] })], warning)
+        }
+        KeyAction::ModTap { modifiers, tap } => {
+            let (tap, warning) = key_event(Modifiers::NONE, tap);
+            let mut held: Vec<&str> = KARABINER_MODIFIERS.iter()
+                .filter(|(modifier, _)| modifiers.contains(*modifier))
+                .map(|&(_, name)| name)
+                .collect();
+            let first = if held.is_empty() { NONE_KEY } else { held.remove(0) };
+            let hold = if held.is_empty() {
+                json!({ "key_code": first, "lazy": true })
+            } else {
+                json!({ "key_code": first, "modifiers": held, "lazy": true })
+            };
+            (vec![json!({ "to": [hold], "to_if_alone": [tap] })], warning)
+        }
+        KeyAction::LayerTap { layer, tap } => {
+            let (tap, warning) = key_event(Modifiers::NONE, tap);
+            (vec![json!({ "to": [set(layer, 1)], "to_after_key_up": [set(layer, 0)], "to_if_alone": [tap] })], warning)
+        }
+        KeyAction::MomentaryLayer(layer) => {
+            (vec![json!({ "to": [set(layer, 1)], "to_after_key_up": [set(layer, 0)] })], None)
+        }
+        KeyAction::OneShotLayer(layer) => {
+            let warning = format!("Karabiner has no one-shot layers, {} exported as MO({})", action, layer);
+            (vec![json!({ "to": [set(layer, 1)], "to_after_key_up": [set(layer, 0)] })], Some(warning))
+        }
+        KeyAction::ToggleLayer(layer) => (vec![
+            json!({
+                "conditions": [{ "type": "variable_if", "name": variables[layer], "value": 1 }],
+                "to": [set(layer, 0)],
+            }),
+            json!({ "to": [set(layer, 1)] }),
+        ], None),
+        KeyAction::Transparent | KeyAction::NoOp => (vec![json!({ "to": [{ "key_code": NONE_KEY }] })], None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use KeyboardUsage::*;
+
+    fn variables() -> Vec<String> {
+        ["layout_rs_base", "layout_rs_lower", "layout_rs_raise"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!(key_name(KeyboardEnter), Some("return_or_enter"));
+        assert_eq!(key_name(KeyboardLeftGUI), Some("left_command"));
+        assert!(is_built_in(KeyboardSlashQuestion));
+        assert!(is_built_in(KeyboardRightAlt));
+        assert!(!is_built_in(KeyboardHome));
+        assert!(!is_built_in(KeyboardRightControl));
+    }
+
+    #[test]
+    fn test_action_events() {
+        let events = |action| action_events(action, &variables()).0;
+
+        assert_eq!(events(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), [
+            json!({ "to": [{ "key_code": "z", "modifiers": ["left_control", "left_shift"] }] }),
+        ]);
+        assert_eq!(events(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), [
+            json!({ "to": [{ "key_code": "left_control", "lazy": true }], "to_if_alone": [{ "key_code": "escape" }] }),
+        ]);
+        assert_eq!(events(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), [json!({
+            "to": [{ "set_variable": { "name": "layout_rs_lower", "value": 1 } }],
+            "to_after_key_up": [{ "set_variable": { "name": "layout_rs_lower", "value": 0 } }],
+            "to_if_alone": [{ "key_code": "spacebar" }],
+        })]);
+        assert_eq!(events(KeyAction::ToggleLayer(2)), [
+            json!({
+                "conditions": [{ "type": "variable_if", "name": "layout_rs_raise", "value": 1 }],
+                "to": [{ "set_variable": { "name": "layout_rs_raise", "value": 0 } }],
+            }),
+            json!({ "to": [{ "set_variable": { "name": "layout_rs_raise", "value": 1 } }] }),
+        ]);
+        assert_eq!(events(KeyAction::Consumer(ConsumerUsage::PlayPause)), [json!({ "to": [{ "consumer_key_code": "play_or_pause" }] })]);
+        assert_eq!(events(KeyAction::Mouse(MouseAction::Button2)), [json!({ "to": [{ "pointing_button": "button2" }] })]);
+        assert_eq!(events(KeyAction::Mouse(MouseAction::WheelDown)), [json!({ "to": [{ "mouse_key": { "vertical_wheel": 32 } }] })]);
+        assert_eq!(events(KeyAction::NoOp), [json!({ "to": [{ "key_code": "vk_none" }] })]);
+
+        let (_, warning) = action_events(KeyAction::MomentaryLayer(3), &variables());
+        assert_eq!(warning.as_deref(), Some("MO(3) targets layer 3, which has no Karabiner variable, exported as vk_none"));
+    }
+
+    #[test]
+    fn test_export_rules() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_rules(test_keyboard(), &layers);
+        let file: Value = serde_json::from_str(&export.output).unwrap();
+
+        assert_eq!(file["title"], "layout-rs: Dactyl Manuform 5x7");
+        assert_eq!(file["rules"][0]["description"], "Dactyl Manuform 5x7 keymap (Base, Lower, Raise)");
+        let manipulators = file["rules"][0]["manipulators"].as_array().unwrap();
+        for manipulator in manipulators {
+            assert_eq!(manipulator["type"], "basic");
+            assert_eq!(manipulator["from"]["modifiers"], json!({ "optional": ["any"] }));
+        }
+
+        // The Lower key is read from a spare key and the layer's F1 only applies while it is held
+        let lower = manipulators.iter()
+            .find(|manipulator| manipulator["to"][0]["set_variable"]["name"] == "layout_rs_lower")
+            .unwrap();
+        assert!(manipulators.iter().any(|manipulator| *manipulator == json!({
+            "type": "basic",
+            "from": { "key_code": "1", "modifiers": { "optional": ["any"] } },
+            "conditions": [{ "type": "variable_if", "name": "layout_rs_lower", "value": 1 }],
+            "to": [{ "key_code": "f1" }],
+        })));
+        assert!(export.warnings.contains(&format!("[4, 3]: 'MO(1)' is read from the {} key", lower["from"]["key_code"].as_str().unwrap())));
+        assert!(!manipulators.iter().any(|manipulator| manipulator["from"]["key_code"] == "a"));
+    }
+}
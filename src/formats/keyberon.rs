@@ -0,0 +1,445 @@
+//! # Keyberon
+//!
+//! Rust `layout!` export for firmware built on the Keyberon crate. Every layer becomes a
+//! `{ ... }` block of the macro with one `[...]` per matrix row, using `KeyCode` variants as
+//! key names, `(1)` for layer keys, `t` for transparent and `n` for empty keys.
+//!
+//! Modifier-wrapped keys are written as keys pressed together (`[LCtrl C]`). Mod-taps and
+//! layer-taps need a `HoldTapAction`, so they are declared as constants above the layout.
+
+use std::collections::{BTreeSet, HashMap};
+use once_cell::sync::Lazy;
+use crate::actions::{KeyAction, Modifiers};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::Layer;
+use super::{action_at, ascii_art, layer_identifiers, matrix_positions, Conversion};
+
+/// Milliseconds a hold-tap key waits before it counts as held
+const HOLD_TAP_TIMEOUT: u16 = 200;
+
+/// Modifier bits with their Keyberon key name
+const KEYBERON_MODIFIERS: [(Modifiers, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "LCtrl"),
+    (Modifiers::LEFT_SHIFT, "LShift"),
+    (Modifiers::LEFT_ALT, "LAlt"),
+    (Modifiers::LEFT_GUI, "LGui"),
+    (Modifiers::RIGHT_CTRL, "RCtrl"),
+    (Modifiers::RIGHT_SHIFT, "RShift"),
+    (Modifiers::RIGHT_ALT, "RAlt"),
+    (Modifiers::RIGHT_GUI, "RGui"),
+];
+
+/// Keyberon key names, the variants of its `KeyCode` enum
+static KEYBERON_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "A"),
+        (KeyboardBb, "B"),
+        (KeyboardCc, "C"),
+        (KeyboardDd, "D"),
+        (KeyboardEe, "E"),
+        (KeyboardFf, "F"),
+        (KeyboardGg, "G"),
+        (KeyboardHh, "H"),
+        (KeyboardIi, "I"),
+        (KeyboardJj, "J"),
+        (KeyboardKk, "K"),
+        (KeyboardLl, "L"),
+        (KeyboardMm, "M"),
+        (KeyboardNn, "N"),
+        (KeyboardOo, "O"),
+        (KeyboardPp, "P"),
+        (KeyboardQq, "Q"),
+        (KeyboardRr, "R"),
+        (KeyboardSs, "S"),
+        (KeyboardTt, "T"),
+        (KeyboardUu, "U"),
+        (KeyboardVv, "V"),
+        (KeyboardWw, "W"),
+        (KeyboardXx, "X"),
+        (KeyboardYy, "Y"),
+        (KeyboardZz, "Z"),
+        (Keyboard1Exclamation, "Kb1"),
+        (Keyboard2At, "Kb2"),
+        (Keyboard3Hash, "Kb3"),
+        (Keyboard4Dollar, "Kb4"),
+        (Keyboard5Percent, "Kb5"),
+        (Keyboard6Caret, "Kb6"),
+        (Keyboard7Ampersand, "Kb7"),
+        (Keyboard8Asterisk, "Kb8"),
+        (Keyboard9OpenParens, "Kb9"),
+        (Keyboard0CloseParens, "Kb0"),
+        (KeyboardEnter, "Enter"),
+        (KeyboardEscape, "Escape"),
+        (KeyboardBackspace, "BSpace"),
+        (KeyboardTab, "Tab"),
+        (KeyboardSpacebar, "Space"),
+        (KeyboardDashUnderscore, "Minus"),
+        (KeyboardEqualPlus, "Equal"),
+        (KeyboardOpenBracketBrace, "LBracket"),
+        (KeyboardCloseBracketBrace, "RBracket"),
+        (KeyboardBackslashBar, "Bslash"),
+        (KeyboardNonUSHash, "NonUsHash"),
+        (KeyboardSemiColon, "SColon"),
+        (KeyboardSingleDoubleQuote, "Quote"),
+        (KeyboardBacktickTilde, "Grave"),
+        (KeyboardCommaLess, "Comma"),
+        (KeyboardPeriodGreater, "Dot"),
+        (KeyboardSlashQuestion, "Slash"),
+        (KeyboardCapsLock, "CapsLock"),
+        (KeyboardF1, "F1"),
+        (KeyboardF2, "F2"),
+        (KeyboardF3, "F3"),
+        (KeyboardF4, "F4"),
+        (KeyboardF5, "F5"),
+        (KeyboardF6, "F6"),
+        (KeyboardF7, "F7"),
+        (KeyboardF8, "F8"),
+        (KeyboardF9, "F9"),
+        (KeyboardF10, "F10"),
+        (KeyboardF11, "F11"),
+        (KeyboardF12, "F12"),
+        (KeyboardPrintScreen, "PScreen"),
+        (KeyboardScrollLock, "ScrollLock"),
+        (KeyboardPause, "Pause"),
+        (KeyboardInsert, "Insert"),
+        (KeyboardHome, "Home"),
+        (KeyboardPageUp, "PgUp"),
+        (KeyboardDelete, "Delete"),
+        (KeyboardEnd, "End"),
+        (KeyboardPageDown, "PgDown"),
+        (KeyboardRightArrow, "Right"),
+        (KeyboardLeftArrow, "Left"),
+        (KeyboardDownArrow, "Down"),
+        (KeyboardUpArrow, "Up"),
+        (KeypadNumLock, "NumLock"),
+        (KeypadDivide, "KpSlash"),
+        (KeypadMultiply, "KpAsterisk"),
+        (KeypadMinus, "KpMinus"),
+        (KeypadPlus, "KpPlus"),
+        (KeypadEnter, "KpEnter"),
+        (Keypad1End, "Kp1"),
+        (Keypad2DownArrow, "Kp2"),
+        (Keypad3PageDown, "Kp3"),
+        (Keypad4LeftArrow, "Kp4"),
+        (Keypad5, "Kp5"),
+        (Keypad6RightArrow, "Kp6"),
+        (Keypad7Home, "Kp7"),
+        (Keypad8UpArrow, "Kp8"),
+        (Keypad9PageUp, "Kp9"),
+        (Keypad0Insert, "Kp0"),
+        (KeypadPeriodDelete, "KpDot"),
+        (KeyboardNonUSSlash, "NonUsBslash"),
+        (KeyboardApplication, "Application"),
+        (KeyboardPower, "Power"),
+        (KeypadEqual, "KpEqual"),
+        (KeyboardF13, "F13"),
+        (KeyboardF14, "F14"),
+        (KeyboardF15, "F15"),
+        (KeyboardF16, "F16"),
+        (KeyboardF17, "F17"),
+        (KeyboardF18, "F18"),
+        (KeyboardF19, "F19"),
+        (KeyboardF20, "F20"),
+        (KeyboardF21, "F21"),
+        (KeyboardF22, "F22"),
+        (KeyboardF23, "F23"),
+        (KeyboardF24, "F24"),
+        (KeyboardExecute, "Execute"),
+        (KeyboardHelp, "Help"),
+        (KeyboardMenu, "Menu"),
+        (KeyboardSelect, "Select"),
+        (KeyboardStop, "Stop"),
+        (KeyboardAgain, "Again"),
+        (KeyboardUndo, "Undo"),
+        (KeyboardCut, "Cut"),
+        (KeyboardCopy, "Copy"),
+        (KeyboardPaste, "Paste"),
+        (KeyboardFind, "Find"),
+        (KeyboardMute, "Mute"),
+        (KeyboardVolumeUp, "VolUp"),
+        (KeyboardVolumeDown, "VolDown"),
+        (KeyboardLockingCapsLock, "LockingCapsLock"),
+        (KeyboardLockingNumLock, "LockingNumLock"),
+        (KeyboardLockingScrollLock, "LockingScrollLock"),
+        (KeypadComma, "KpComma"),
+        (KeypadEqualSign, "KpEqualSign"),
+        (KeyboardInternational1, "Intl1"),
+        (KeyboardInternational2, "Intl2"),
+        (KeyboardInternational3, "Intl3"),
+        (KeyboardInternational4, "Intl4"),
+        (KeyboardInternational5, "Intl5"),
+        (KeyboardInternational6, "Intl6"),
+        (KeyboardInternational7, "Intl7"),
+        (KeyboardInternational8, "Intl8"),
+        (KeyboardInternational9, "Intl9"),
+        (KeyboardLANG1, "Lang1"),
+        (KeyboardLANG2, "Lang2"),
+        (KeyboardLANG3, "Lang3"),
+        (KeyboardLANG4, "Lang4"),
+        (KeyboardLANG5, "Lang5"),
+        (KeyboardLANG6, "Lang6"),
+        (KeyboardLANG7, "Lang7"),
+        (KeyboardLANG8, "Lang8"),
+        (KeyboardLANG9, "Lang9"),
+        (KeyboardAlternateErase, "AltErase"),
+        (KeyboardSysReqAttention, "SysReq"),
+        (KeyboardCancel, "Cancel"),
+        (KeyboardClear, "Clear"),
+        (KeyboardPrior, "Prior"),
+        (KeyboardReturn, "Return"),
+        (KeyboardSeparator, "Separator"),
+        (KeyboardOut, "Out"),
+        (KeyboardOper, "Oper"),
+        (KeyboardClearAgain, "ClearAgain"),
+        (KeyboardCrSelProps, "CrSel"),
+        (KeyboardExSel, "ExSel"),
+        (KeyboardLeftControl, "LCtrl"),
+        (KeyboardLeftShift, "LShift"),
+        (KeyboardLeftAlt, "LAlt"),
+        (KeyboardLeftGUI, "LGui"),
+        (KeyboardRightControl, "RCtrl"),
+        (KeyboardRightShift, "RShift"),
+        (KeyboardRightAlt, "RAlt"),
+        (KeyboardRightGUI, "RGui"),
+    ])
+});
+
+/// Keyberon's media key codes. They sit in the Keyboard page's reserved range, where only
+/// Linux reads them, as Keyberon has no consumer report.
+const KEYBERON_CONSUMER_KEYS: [(ConsumerUsage, &str); 14] = [
+    (ConsumerUsage::ScanNextTrack, "MediaNextSong"),
+    (ConsumerUsage::ScanPreviousTrack, "MediaPreviousSong"),
+    (ConsumerUsage::Stop, "MediaStopCD"),
+    (ConsumerUsage::Eject, "MediaEjectCD"),
+    (ConsumerUsage::PlayPause, "MediaPlayPause"),
+    (ConsumerUsage::Mute, "MediaMute"),
+    (ConsumerUsage::VolumeIncrement, "MediaVolUp"),
+    (ConsumerUsage::VolumeDecrement, "MediaVolDown"),
+    (ConsumerUsage::ALCalculator, "MediaCalc"),
+    (ConsumerUsage::ACSearch, "MediaFind"),
+    (ConsumerUsage::ACHome, "MediaWWW"),
+    (ConsumerUsage::ACBack, "MediaBack"),
+    (ConsumerUsage::ACForward, "MediaForward"),
+    (ConsumerUsage::ACRefresh, "MediaRefresh"),
+];
+
+/// The Keyberon key name of a usage, e.g. `Escape`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    KEYBERON_KEYS.get(&usage).copied()
+}
+
+/// Hold-tap constants and the imports they need, collected while writing the layers
+#[derive(Default)]
+struct Declarations {
+    /// `(name, hold, tap)` of every hold-tap constant, in order of first use
+    hold_taps: Vec<(String, String, String)>,
+    /// Names used from `keyberon::action`
+    imports: BTreeSet<&'static str>,
+}
+
+impl Declarations {
+    /// Declare a hold-tap constant unless it already is, returning the key that uses it
+    fn hold_tap(&mut self, name: String, hold: String, tap: &str) -> String {
+        self.imports.extend(["k", "Action", "HoldTapAction", "HoldTapConfig"]);
+        if !self.hold_taps.iter().any(|(existing, _, _)| *existing == name) {
+            self.hold_taps.push((name.clone(), hold, format!("k({})", tap)));
+        }
+        format!("{{{}}}", name)
+    }
+}
+
+/// Write the keymap as a Rust module with a Keyberon `layout!`
+pub fn export_layout(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let identifiers = layer_identifiers(layers);
+    let positions = matrix_positions(keyboard);
+    let rows = positions.iter().map(|&(row, _)| row).max().map_or(0, |row| row + 1);
+    let cols = positions.iter().map(|&(_, col)| col).max().map_or(0, |col| col + 1);
+    let mut declarations = Declarations::default();
+    let mut warnings = Vec::new();
+
+    let layer_rows: Vec<Vec<Vec<String>>> = layers.iter().enumerate()
+        .map(|(index, layer)| {
+            (0..rows)
+                .map(|row| {
+                    (0..cols)
+                        .map(|col| {
+                            let position = (row, col);
+                            if !keyboard.geometry.contains(position) {
+                                return "n".to_string();
+                            }
+                            let (action, warning) = match layer.unmapped.get(&position) {
+                                Some(unmapped) => ("n".to_string(), Some(format!(
+                                    "{} keycode '{}' has no Keyberon equivalent, exported as n", unmapped.format, unmapped.keycode
+                                ))),
+                                None => layout_action(action_at(layers, index, position), &identifiers, &mut declarations),
+                            };
+                            if let Some(warning) = warning {
+                                warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, row, col, warning));
+                            }
+                            action
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut output = format!("//! {} keymap exported by layout-rs\n\n", keyboard.name);
+    if !declarations.imports.is_empty() {
+        let imports: Vec<&str> = declarations.imports.iter().copied().collect();
+        output.push_str(&format!("use keyberon::action::{{{}}};\n", imports.join(", ")));
+        if !declarations.hold_taps.is_empty() {
+            output.push_str("use keyberon::key_code::KeyCode::*;\n");
+        }
+        output.push('\n');
+    }
+    for (name, hold, tap) in &declarations.hold_taps {
+        output.push_str(&format!(
+            "const {}: Action = Action::HoldTap(&HoldTapAction {{\n    timeout: {},\n    hold: {},\n    tap: {},\n    config: HoldTapConfig::Default,\n    tap_hold_interval: 0,\n}});\n\n",
+            name, HOLD_TAP_TIMEOUT, hold, tap
+        ));
+    }
+
+    output.push_str(&format!("pub const COLS: usize = {};\npub const ROWS: usize = {};\npub const N_LAYERS: usize = {};\n\n", cols, rows, layers.len()));
+    output.push_str("#[rustfmt::skip]\npub static LAYERS: keyberon::layout::Layers<COLS, ROWS, N_LAYERS> = keyberon::layout::layout! {\n");
+
+    // Keys are padded to a common width so the rows line up as a grid
+    let width = layer_rows.iter().flatten().flatten().map(String::len).max().unwrap_or(0);
+    for (index, rows) in layer_rows.iter().enumerate() {
+        output.push_str(&format!("    // {}\n", layers[index].name));
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(&format!("    // {}\n", line));
+        }
+        output.push_str("    {\n");
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|cell| format!("{:<width$}", cell, width = width)).collect();
+            output.push_str(&format!("        [{}]\n", cells.join(" ").trim_end()));
+        }
+        output.push_str("    }\n");
+    }
+    output.push_str("};\n");
+
+    Conversion { output, warnings }
+}
+
+/// Key of the `layout!` macro for an action, and a warning if it had to be approximated
+fn layout_action(action: KeyAction, layers: &[String], declarations: &mut Declarations) -> (String, Option<String>) {
+    let layer_name = |layer: usize| layers.get(layer).cloned().unwrap_or_else(|| format!("LAYER{}", layer));
+    let name = |usage: KeyboardUsage| key_name(usage)
+        .ok_or_else(|| format!("Keyberon has no key name for {:?}, exported as n", usage));
+    let modifier_names = |modifiers: Modifiers| KEYBERON_MODIFIERS.iter()
+        .filter(|&&(modifier, _)| modifiers.contains(modifier))
+        .map(|&(_, name)| name)
+        .collect::<Vec<_>>();
+    let mut warning = None;
+    let output = match action {
+        KeyAction::Key(usage) => name(usage).map(str::to_string),
+        KeyAction::Consumer(usage) => match KEYBERON_CONSUMER_KEYS.iter().find(|&&(other, _)| other == usage) {
+            Some(&(_, name)) => {
+                warning = Some(format!("Keyberon sends {} as the {} key code, which only Linux reads", action, name));
+                Ok(name.to_string())
+            }
+            None => Err(format!("Keyberon has no key code for {:?}, exported as n", usage)),
+        },
+        KeyAction::Mouse(_) => Err(format!("Keyberon has no mouse keys, {} exported as n", action)),
+        KeyAction::Modified { modifiers, key } => name(key).map(|key| {
+            format!("[{} {}]", modifier_names(modifiers).join(" "), key)
+        }),
+        KeyAction::ModTap { modifiers, tap } => name(tap).map(|tap| {
+            let held = modifier_names(modifiers);
+            let hold = match held.as_slice() {
+                [modifier] => format!("k({})", modifier),
+                held => format!("Action::MultipleKeyCodes(&[{}].as_slice())", held.join(", ")),
+            };
+            declarations.hold_tap(format!("{}_{}", held.join("_"), tap).to_uppercase(), hold, tap)
+        }),
+        KeyAction::LayerTap { layer, tap } => name(tap).map(|tap| {
+            declarations.imports.insert("l");
+            declarations.hold_tap(format!("{}_{}", layer_name(layer), tap).to_uppercase(), format!("l({})", layer), tap)
+        }),
+        KeyAction::MomentaryLayer(layer) => Ok(format!("({})", layer)),
+        KeyAction::ToggleLayer(layer) => {
+            // Keyberon has no layer toggle; switching the default layer is the closest
+            warning = Some(format!("Keyberon has no layer toggle, TG({}) exported as a default layer switch", layer));
+            declarations.imports.insert("Action");
+            Ok(format!("{{Action::DefaultLayer({})}}", layer))
+        }
+        KeyAction::OneShotLayer(layer) => {
+            warning = Some(format!("Keyberon has no one-shot layers, OSL({}) exported as a momentary layer", layer));
+            Ok(format!("({})", layer))
+        }
+        KeyAction::Transparent => Ok("t".to_string()),
+        KeyAction::NoOp => Ok("n".to_string()),
+    };
+    match output {
+        Ok(output) => (output, warning),
+        Err(warning) => ("n".to_string(), Some(warning)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::actions::MouseAction;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_layout_actions() {
+        let layers = ["BASE".to_string(), "LOWER".to_string()];
+        let mut declarations = Declarations::default();
+        let mut action = |action| layout_action(action, &layers, &mut declarations).0;
+
+        assert_eq!(action(KeyAction::Key(Keyboard1Exclamation)), "Kb1");
+        assert_eq!(action(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), "[LCtrl LShift Z]");
+        assert_eq!(action(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), "{LCTRL_ESCAPE}");
+        assert_eq!(action(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_ALT, tap: KeyboardAa }), "{LCTRL_LALT_A}");
+        assert_eq!(action(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), "{LOWER_SPACE}");
+        assert_eq!(action(KeyAction::MomentaryLayer(2)), "(2)");
+        assert_eq!(action(KeyAction::ToggleLayer(1)), "{Action::DefaultLayer(1)}");
+        assert_eq!(action(KeyAction::OneShotLayer(1)), "(1)");
+        assert_eq!(action(KeyAction::Transparent), "t");
+        assert_eq!(action(KeyAction::NoOp), "n");
+
+        assert_eq!(declarations.hold_taps, [
+            ("LCTRL_ESCAPE".to_string(), "k(LCtrl)".to_string(), "k(Escape)".to_string()),
+            ("LCTRL_LALT_A".to_string(), "Action::MultipleKeyCodes(&[LCtrl, LAlt].as_slice())".to_string(), "k(A)".to_string()),
+            ("LOWER_SPACE".to_string(), "l(1)".to_string(), "k(Space)".to_string()),
+        ]);
+        assert_eq!(declarations.imports.iter().copied().collect::<Vec<_>>(), ["Action", "HoldTapAction", "HoldTapConfig", "k", "l"]);
+
+        let (output, warning) = layout_action(KeyAction::Key(Keypad00), &layers, &mut declarations);
+        assert_eq!(output, "n");
+        assert_eq!(warning.as_deref(), Some("Keyberon has no key name for Keypad00, exported as n"));
+        let (output, warning) = layout_action(KeyAction::Consumer(ConsumerUsage::PlayPause), &layers, &mut declarations);
+        assert_eq!(output, "MediaPlayPause");
+        assert_eq!(warning.as_deref(), Some("Keyberon sends Play as the MediaPlayPause key code, which only Linux reads"));
+        let (output, _) = layout_action(KeyAction::Consumer(ConsumerUsage::ALEmailReader), &layers, &mut declarations);
+        assert_eq!(output, "n");
+        let (output, warning) = layout_action(KeyAction::Mouse(MouseAction::Button1), &layers, &mut declarations);
+        assert_eq!(output, "n");
+        assert_eq!(warning.as_deref(), Some("Keyberon has no mouse keys, Btn1 exported as n"));
+    }
+
+    #[test]
+    fn test_export_layout() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].keys.insert((2, 0), KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape });
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_layout(test_keyboard(), &layers);
+        let layout = &export.output;
+
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        assert!(layout.starts_with("//! Dactyl Manuform 5x7 keymap exported by layout-rs\n\nuse keyberon::action::{Action, HoldTapAction, HoldTapConfig, k};\n"));
+        assert!(layout.contains("const LCTRL_ESCAPE: Action = Action::HoldTap(&HoldTapAction {\n    timeout: 200,\n    hold: k(LCtrl),\n"));
+        assert!(layout.contains("pub const COLS: usize = 14;\npub const ROWS: usize = 8;\npub const N_LAYERS: usize = 3;\n"));
+        assert!(layout.contains("    // Base\n    // | Esc   | 1     |"));
+        assert!(layout.contains("        [{LCTRL_ESCAPE} A"));
+        assert!(layout.contains("LGui           (1)            n"));
+        assert!(layout.contains("    {\n        [t              F1             t"));
+        assert!(layout.ends_with("    }\n};\n"));
+    }
+}
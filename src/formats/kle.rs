@@ -0,0 +1,283 @@
+//! # Keyboard Layout Editor
+//!
+//! Import of [keyboard-layout-editor.com](http://www.keyboard-layout-editor.com) (KLE) raw
+//! data as a new keyboard model. KLE files are a JSON list of rows; each row lists its keys as
+//! legend strings, preceded by objects that change the position (`x`, `y`), size (`w`, `h`)
+//! and rotation (`r`, `rx`, `ry`) of the keys that follow. An optional leading object holds
+//! metadata such as the board's `name`.
+//!
+//! KLE has no wiring information. Keys whose legend is a `row,col` pair, as in the layouts
+//! VIA and QMK publish, use that matrix position; otherwise each key's position is its KLE
+//! row and its index in that row. The remaining legends are matched to key labels so the
+//! base layer starts out with the printed keys.
+
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::actions::KeyAction;
+use crate::geometry::{KeyGeometry, KeyboardGeometry};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::KeyboardUsage;
+use super::Conversion;
+
+/// Prefix of the ids of models imported from KLE
+pub const ID_PREFIX: &str = "kle_";
+
+/// Common KLE legends that differ from the editor's key labels
+const LEGEND_LABELS: [(&str, &str); 22] = [
+    ("Backspace", "BKSP"),
+    ("Back Space", "BKSP"),
+    ("Caps Lock", "Caps"),
+    ("Escape", "Esc"),
+    ("Return", "Enter"),
+    ("Shift", "L Shift"),
+    ("Ctrl", "L Ctrl"),
+    ("Control", "L Ctrl"),
+    ("Alt", "L Alt"),
+    ("AltGr", "R Alt"),
+    ("Alt Gr", "R Alt"),
+    ("Win", "L GUI"),
+    ("Super", "L GUI"),
+    ("Cmd", "L GUI"),
+    ("Meta", "L GUI"),
+    ("Menu", "App"),
+    ("Delete", "Del"),
+    ("Insert", "Ins"),
+    ("Page Up", "PgUp"),
+    ("Page Down", "PgDn"),
+    ("Print Screen", "PrtSc"),
+    ("Num Lock", "NumLk"),
+];
+
+/// Whether the contents look like KLE raw data: a JSON list whose entries are rows (lists)
+/// or property objects
+pub fn is_kle(contents: &str) -> bool {
+    match serde_json::from_str::<Value>(contents) {
+        Ok(Value::Array(rows)) => !rows.is_empty() && rows.iter().all(|row| row.is_array() || row.is_object()),
+        _ => false,
+    }
+}
+
+/// Build a keyboard model from KLE raw data. `fallback_name` names the model when the file
+/// has no `name` of its own, e.g. the file name.
+pub fn import_kle(fallback_name: &str, json: &str) -> Result<Conversion<KeyboardModel>, String> {
+    let rows: Vec<Value> = serde_json::from_str(json)
+        .map_err(|e| format!("Not a KLE layout: {}", e))?;
+
+    let mut name = fallback_name.to_string();
+    let mut keys = Vec::new();
+    // Position, size and rotation of the next key, updated as KLE's properties are read
+    let (mut x, mut y) = (0.0f32, 0.0f32);
+    let (mut width, mut height) = (1.0f32, 1.0f32);
+    let (mut rotation, mut rx, mut ry) = (0.0f32, 0.0f32, 0.0f32);
+    let mut decal = false;
+    // Rows of keys, not counting the metadata object
+    let mut key_rows = 0;
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let items = match row {
+            Value::Object(metadata) if row_index == 0 => {
+                if let Some(board) = metadata.get("name").and_then(Value::as_str).filter(|board| !board.trim().is_empty()) {
+                    name = board.trim().to_string();
+                }
+                continue;
+            }
+            Value::Array(items) => items,
+            _ => return Err(format!("Row {} is not a list of keys", row_index)),
+        };
+
+        let mut key_index = 0;
+        for (item_index, item) in items.iter().enumerate() {
+            match item {
+                Value::String(legends) => {
+                    if !decal {
+                        let geometry = KeyGeometry { width, height, ..KeyGeometry::new(0, 0, x, y) };
+                        keys.push((geometry.rotated_about((rx, ry), rotation), key_rows, key_index, legends.clone()));
+                        key_index += 1;
+                    }
+                    x += width;
+                    (width, height, decal) = (1.0, 1.0, false);
+                }
+                Value::Object(properties) => {
+                    let number = |key: &str| properties.get(key).and_then(Value::as_f64).map(|value| value as f32);
+                    let (r, new_rx, new_ry) = (number("r"), number("rx"), number("ry"));
+                    if item_index > 0 && (r.is_some() || new_rx.is_some() || new_ry.is_some()) {
+                        return Err(format!("Row {}: rotation can only be set before the first key of a row", row_index));
+                    }
+                    if let Some(r) = r {
+                        rotation = r;
+                    }
+                    if new_rx.is_some() || new_ry.is_some() {
+                        // A new rotation origin starts a cluster of keys at that origin
+                        rx = new_rx.unwrap_or(rx);
+                        ry = new_ry.unwrap_or(ry);
+                        (x, y) = (rx, ry);
+                    }
+                    x += number("x").unwrap_or(0.0);
+                    y += number("y").unwrap_or(0.0);
+                    width = number("w").unwrap_or(width);
+                    height = number("h").unwrap_or(height);
+                    decal = properties.get("d").and_then(Value::as_bool).unwrap_or(decal);
+                }
+                other => return Err(format!("Row {}: unexpected {} in the row", row_index, other)),
+            }
+        }
+        y += 1.0;
+        x = rx;
+        key_rows += 1;
+    }
+
+    if keys.is_empty() {
+        return Err("KLE layout has no keys".to_string());
+    }
+
+    // Matrix positions come from `row,col` legends only if every key has one
+    let matrix: Vec<Option<(usize, usize)>> = keys.iter().map(|(_, _, _, legends)| matrix_legend(legends)).collect();
+    let use_matrix = matrix.iter().all(Option::is_some);
+
+    let mut warnings = Vec::new();
+    let mut base = HashMap::new();
+    let geometry_keys = keys.into_iter().zip(matrix)
+        .map(|((geometry, row, col, legends), matrix)| {
+            let (row, col) = if use_matrix { matrix.unwrap_or((row, col)) } else { (row, col) };
+            let labels: Vec<String> = split_legends(&legends).into_iter()
+                .filter(|legend| !legend.is_empty() && matrix_legend(legend).is_none())
+                .collect();
+            match labels.iter().find_map(|legend| legend_usage(legend)) {
+                Some(usage) => {
+                    base.insert((row, col), KeyAction::Key(usage));
+                }
+                None if !labels.is_empty() => {
+                    warnings.push(format!("Key [{}, {}]: no key matches the legend '{}'", row, col, labels.join(" / ")));
+                }
+                None => {}
+            }
+            KeyGeometry { row, col, ..geometry }
+        })
+        .collect();
+
+    let geometry = KeyboardGeometry::new(name.clone(), geometry_keys)
+        .map_err(|e| format!("KLE layout has overlapping matrix legends: {}", e))?;
+    let model = KeyboardModel::new(&model_id(&name), &name, geometry, base);
+    Ok(Conversion { output: model, warnings })
+}
+
+/// Id of an imported model, e.g. `kle_my_board` for "My Board"
+fn model_id(name: &str) -> String {
+    let mut id = String::from(ID_PREFIX);
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_end_matches('_').to_string()
+}
+
+/// Legends of a key, in KLE's order, with HTML markup removed
+fn split_legends(legends: &str) -> Vec<String> {
+    legends.split('\n').map(plain_text).collect()
+}
+
+/// Text of a legend without HTML tags and entities, e.g. `Back Space` for `Back<br>Space`
+fn plain_text(legend: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in legend.replace("<br>", " ").chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&").trim().to_string()
+}
+
+/// A `row,col` matrix legend
+fn matrix_legend(legend: &str) -> Option<(usize, usize)> {
+    let (row, col) = legend.split('\n').next()?.trim().split_once(',')?;
+    Some((row.trim().parse().ok()?, col.trim().parse().ok()?))
+}
+
+/// The key printed on a legend, by its editor label or a common KLE spelling
+fn legend_usage(legend: &str) -> Option<KeyboardUsage> {
+    let label = LEGEND_LABELS.iter()
+        .find(|(kle, _)| kle.eq_ignore_ascii_case(legend))
+        .map_or(legend, |&(_, label)| label);
+    [label.to_string(), label.to_ascii_uppercase()].iter()
+        .map(|label| KeyboardUsage::from(label.as_str()))
+        .find(|&usage| usage != KeyboardUsage::Reserved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_import_kle() {
+        let kle = r#"[
+            {"name": "Tiny Board"},
+            ["Esc", {"w": 1.5}, "Tab", "!\n1", "Fn"],
+            [{"x": 0.25, "h": 2}, "Backspace", {"d": true}, "decal", "q"]
+        ]"#;
+        let import = import_kle("tiny.json", kle).unwrap();
+        let model = import.output;
+        assert_eq!(model.id, "kle_tiny_board");
+        assert_eq!(model.name, "Tiny Board");
+        assert_eq!(import.warnings, ["Key [0, 3]: no key matches the legend 'Fn'"]);
+
+        let keys = model.geometry.keys();
+        assert_eq!(keys.len(), 6);
+        let tab = &keys[1];
+        assert_eq!((tab.position(), tab.x, tab.y, tab.width), ((0, 1), 1.0, 0.0, 1.5));
+        assert_eq!((keys[2].x, keys[3].x), (2.5, 3.5));
+        let backspace = &keys[4];
+        assert_eq!((backspace.position(), backspace.x, backspace.y, backspace.height), ((1, 0), 0.25, 1.0, 2.0));
+        // The decal takes up space but is not a key
+        assert_eq!((keys[5].position(), keys[5].x), ((1, 1), 2.25));
+
+        let base = &model.default_layers[0].keys;
+        assert_eq!(base[&(0, 0)], KeyAction::Key(KeyboardEscape));
+        assert_eq!(base[&(0, 2)], KeyAction::Key(Keyboard1Exclamation));
+        assert_eq!(base[&(1, 0)], KeyAction::Key(KeyboardBackspace));
+        assert_eq!(base[&(1, 1)], KeyAction::Key(KeyboardQq));
+        assert!(!base.contains_key(&(0, 3)));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let kle = r#"[[{"r": 90, "rx": 2, "ry": 1}, "A", "B"], ["C"]]"#;
+        let keys = import_kle("Rotated", kle).unwrap().output.geometry.keys().to_vec();
+        // Keys are laid out from the rotation origin, then turned around it
+        let close = |(x, y): (f32, f32), (ex, ey): (f32, f32)| (x - ex).abs() < 1e-4 && (y - ey).abs() < 1e-4;
+        assert!(close(keys[0].center(), (1.5, 1.5)), "{:?}", keys[0].center());
+        assert!(close(keys[1].center(), (1.5, 2.5)), "{:?}", keys[1].center());
+        assert!(close(keys[2].center(), (0.5, 1.5)), "{:?}", keys[2].center());
+        assert!(keys.iter().all(|key| key.rotation == 90.0));
+    }
+
+    #[test]
+    fn test_matrix_legends() {
+        let kle = r#"[["0,0\n\n\nEsc", "0,1"], ["1,1"]]"#;
+        let model = import_kle("via", kle).unwrap().output;
+        let positions: Vec<(usize, usize)> = model.geometry.keys().iter().map(KeyGeometry::position).collect();
+        assert_eq!(positions, [(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(model.default_layers[0].keys.get(&(0, 0)), Some(&KeyAction::Key(KeyboardEscape)));
+
+        let overlapping = r#"[["0,0", "0,0"]]"#;
+        assert!(import_kle("via", overlapping).unwrap_err().contains("Duplicate matrix position"));
+    }
+
+    #[test]
+    fn test_invalid_kle() {
+        assert!(is_kle(r#"[{"name": "x"}, ["A"]]"#));
+        assert!(!is_kle(r#"{"layers": []}"#));
+        assert!(!is_kle("[]"));
+        assert!(import_kle("x", "[]").is_err());
+        assert!(import_kle("x", r#"[["A", {"r": 15}, "B"]]"#).unwrap_err().contains("rotation"));
+        assert_eq!(plain_text("Back<br>Space"), "Back Space");
+        assert_eq!(plain_text("<i class='kb kb-Arrows-Up'></i>&amp;"), "&");
+    }
+}
@@ -0,0 +1,517 @@
+//! # KMK
+//!
+//! CircuitPython `main.py` export for KMK firmware. `keyboard.keymap` holds one list of keys
+//! per layer in matrix order (row by row), e.g. `KC.ESC`, `KC.LCTL(KC.C)` or `KC.MO(1)`, and
+//! `keyboard.coord_mapping` gives the matrix index of each of those keys.
+//!
+//! On split keyboards each half scans its own matrix. KMK numbers the right half's rows after
+//! the left half's, and the right half's columns are counted from its outer edge, the mirror
+//! of the left half.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::Layer;
+use super::{action_at, ascii_art, matrix_positions, Conversion};
+
+/// Modifier bits with their KMK key, which also wraps other keys: `KC.LCTL(KC.C)`
+const KMK_MODIFIERS: [(Modifiers, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "LCTL"),
+    (Modifiers::LEFT_SHIFT, "LSFT"),
+    (Modifiers::LEFT_ALT, "LALT"),
+    (Modifiers::LEFT_GUI, "LGUI"),
+    (Modifiers::RIGHT_CTRL, "RCTL"),
+    (Modifiers::RIGHT_SHIFT, "RSFT"),
+    (Modifiers::RIGHT_ALT, "RALT"),
+    (Modifiers::RIGHT_GUI, "RGUI"),
+];
+
+/// KMK key names, the attributes of `KC`
+static KMK_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "A"),
+        (KeyboardBb, "B"),
+        (KeyboardCc, "C"),
+        (KeyboardDd, "D"),
+        (KeyboardEe, "E"),
+        (KeyboardFf, "F"),
+        (KeyboardGg, "G"),
+        (KeyboardHh, "H"),
+        (KeyboardIi, "I"),
+        (KeyboardJj, "J"),
+        (KeyboardKk, "K"),
+        (KeyboardLl, "L"),
+        (KeyboardMm, "M"),
+        (KeyboardNn, "N"),
+        (KeyboardOo, "O"),
+        (KeyboardPp, "P"),
+        (KeyboardQq, "Q"),
+        (KeyboardRr, "R"),
+        (KeyboardSs, "S"),
+        (KeyboardTt, "T"),
+        (KeyboardUu, "U"),
+        (KeyboardVv, "V"),
+        (KeyboardWw, "W"),
+        (KeyboardXx, "X"),
+        (KeyboardYy, "Y"),
+        (KeyboardZz, "Z"),
+        (Keyboard1Exclamation, "N1"),
+        (Keyboard2At, "N2"),
+        (Keyboard3Hash, "N3"),
+        (Keyboard4Dollar, "N4"),
+        (Keyboard5Percent, "N5"),
+        (Keyboard6Caret, "N6"),
+        (Keyboard7Ampersand, "N7"),
+        (Keyboard8Asterisk, "N8"),
+        (Keyboard9OpenParens, "N9"),
+        (Keyboard0CloseParens, "N0"),
+        (KeyboardEnter, "ENT"),
+        (KeyboardEscape, "ESC"),
+        (KeyboardBackspace, "BSPC"),
+        (KeyboardTab, "TAB"),
+        (KeyboardSpacebar, "SPC"),
+        (KeyboardDashUnderscore, "MINS"),
+        (KeyboardEqualPlus, "EQL"),
+        (KeyboardOpenBracketBrace, "LBRC"),
+        (KeyboardCloseBracketBrace, "RBRC"),
+        (KeyboardBackslashBar, "BSLS"),
+        (KeyboardNonUSHash, "NUHS"),
+        (KeyboardSemiColon, "SCLN"),
+        (KeyboardSingleDoubleQuote, "QUOT"),
+        (KeyboardBacktickTilde, "GRV"),
+        (KeyboardCommaLess, "COMM"),
+        (KeyboardPeriodGreater, "DOT"),
+        (KeyboardSlashQuestion, "SLSH"),
+        (KeyboardCapsLock, "CAPS"),
+        (KeyboardF1, "F1"),
+        (KeyboardF2, "F2"),
+        (KeyboardF3, "F3"),
+        (KeyboardF4, "F4"),
+        (KeyboardF5, "F5"),
+        (KeyboardF6, "F6"),
+        (KeyboardF7, "F7"),
+        (KeyboardF8, "F8"),
+        (KeyboardF9, "F9"),
+        (KeyboardF10, "F10"),
+        (KeyboardF11, "F11"),
+        (KeyboardF12, "F12"),
+        (KeyboardPrintScreen, "PSCR"),
+        (KeyboardScrollLock, "SLCK"),
+        (KeyboardPause, "PAUS"),
+        (KeyboardInsert, "INS"),
+        (KeyboardHome, "HOME"),
+        (KeyboardPageUp, "PGUP"),
+        (KeyboardDelete, "DEL"),
+        (KeyboardEnd, "END"),
+        (KeyboardPageDown, "PGDN"),
+        (KeyboardRightArrow, "RGHT"),
+        (KeyboardLeftArrow, "LEFT"),
+        (KeyboardDownArrow, "DOWN"),
+        (KeyboardUpArrow, "UP"),
+        (KeypadNumLock, "NLCK"),
+        (KeypadDivide, "PSLS"),
+        (KeypadMultiply, "PAST"),
+        (KeypadMinus, "PMNS"),
+        (KeypadPlus, "PPLS"),
+        (KeypadEnter, "PENT"),
+        (Keypad1End, "P1"),
+        (Keypad2DownArrow, "P2"),
+        (Keypad3PageDown, "P3"),
+        (Keypad4LeftArrow, "P4"),
+        (Keypad5, "P5"),
+        (Keypad6RightArrow, "P6"),
+        (Keypad7Home, "P7"),
+        (Keypad8UpArrow, "P8"),
+        (Keypad9PageUp, "P9"),
+        (Keypad0Insert, "P0"),
+        (KeypadPeriodDelete, "PDOT"),
+        (KeyboardNonUSSlash, "NUBS"),
+        (KeyboardApplication, "APP"),
+        (KeyboardPower, "POWER"),
+        (KeypadEqual, "PEQL"),
+        (KeyboardF13, "F13"),
+        (KeyboardF14, "F14"),
+        (KeyboardF15, "F15"),
+        (KeyboardF16, "F16"),
+        (KeyboardF17, "F17"),
+        (KeyboardF18, "F18"),
+        (KeyboardF19, "F19"),
+        (KeyboardF20, "F20"),
+        (KeyboardF21, "F21"),
+        (KeyboardF22, "F22"),
+        (KeyboardF23, "F23"),
+        (KeyboardF24, "F24"),
+        (KeyboardExecute, "EXEC"),
+        (KeyboardHelp, "HELP"),
+        (KeyboardMenu, "MENU"),
+        (KeyboardSelect, "SLCT"),
+        (KeyboardStop, "STOP"),
+        (KeyboardAgain, "AGIN"),
+        (KeyboardUndo, "UNDO"),
+        (KeyboardCut, "CUT"),
+        (KeyboardCopy, "COPY"),
+        (KeyboardPaste, "PSTE"),
+        (KeyboardFind, "FIND"),
+        (KeyboardMute, "MUTE"),
+        (KeyboardVolumeUp, "VOLU"),
+        (KeyboardVolumeDown, "VOLD"),
+        (KeypadComma, "PCMM"),
+        (KeyboardInternational1, "INT1"),
+        (KeyboardInternational2, "INT2"),
+        (KeyboardInternational3, "INT3"),
+        (KeyboardInternational4, "INT4"),
+        (KeyboardInternational5, "INT5"),
+        (KeyboardInternational6, "INT6"),
+        (KeyboardInternational7, "INT7"),
+        (KeyboardInternational8, "INT8"),
+        (KeyboardInternational9, "INT9"),
+        (KeyboardLANG1, "LANG1"),
+        (KeyboardLANG2, "LANG2"),
+        (KeyboardLANG3, "LANG3"),
+        (KeyboardLANG4, "LANG4"),
+        (KeyboardLANG5, "LANG5"),
+        (KeyboardLANG6, "LANG6"),
+        (KeyboardLANG7, "LANG7"),
+        (KeyboardLANG8, "LANG8"),
+        (KeyboardLANG9, "LANG9"),
+        (KeyboardAlternateErase, "ERAS"),
+        (KeyboardSysReqAttention, "SYSREQ"),
+        (KeyboardCancel, "CANCEL"),
+        (KeyboardClear, "CLR"),
+        (KeyboardPrior, "PRIOR"),
+        (KeyboardSeparator, "SEPARATOR"),
+        (KeyboardOut, "OUT"),
+        (KeyboardOper, "OPER"),
+        (KeyboardClearAgain, "CLEAR_AGAIN"),
+        (KeyboardCrSelProps, "CRSEL"),
+        (KeyboardExSel, "EXSEL"),
+        (KeyboardLeftControl, "LCTL"),
+        (KeyboardLeftShift, "LSFT"),
+        (KeyboardLeftAlt, "LALT"),
+        (KeyboardLeftGUI, "LGUI"),
+        (KeyboardRightControl, "RCTL"),
+        (KeyboardRightShift, "RSFT"),
+        (KeyboardRightAlt, "RALT"),
+        (KeyboardRightGUI, "RGUI"),
+    ])
+});
+
+/// KMK names for consumer controls, which need the `MediaKeys` extension
+const KMK_CONSUMER_KEYS: [(ConsumerUsage, &str); 12] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, "BRIU"),
+    (ConsumerUsage::DisplayBrightnessDecrement, "BRID"),
+    (ConsumerUsage::FastForward, "MFFD"),
+    (ConsumerUsage::Rewind, "MRWD"),
+    (ConsumerUsage::ScanNextTrack, "MNXT"),
+    (ConsumerUsage::ScanPreviousTrack, "MPRV"),
+    (ConsumerUsage::Stop, "MSTP"),
+    (ConsumerUsage::Eject, "EJCT"),
+    (ConsumerUsage::PlayPause, "MPLY"),
+    (ConsumerUsage::Mute, "MUTE"),
+    (ConsumerUsage::VolumeIncrement, "VOLU"),
+    (ConsumerUsage::VolumeDecrement, "VOLD"),
+];
+
+/// KMK names for mouse keys, which need the `MouseKeys` module. KMK has no speed keys.
+const KMK_MOUSE_KEYS: [(MouseAction, &str); 13] = [
+    (MouseAction::Button1, "MB_LMB"),
+    (MouseAction::Button2, "MB_RMB"),
+    (MouseAction::Button3, "MB_MMB"),
+    (MouseAction::Button4, "MB_BTN4"),
+    (MouseAction::Button5, "MB_BTN5"),
+    (MouseAction::MoveUp, "MS_UP"),
+    (MouseAction::MoveDown, "MS_DOWN"),
+    (MouseAction::MoveLeft, "MS_LEFT"),
+    (MouseAction::MoveRight, "MS_RIGHT"),
+    (MouseAction::WheelUp, "MW_UP"),
+    (MouseAction::WheelDown, "MW_DOWN"),
+    (MouseAction::WheelLeft, "MW_LEFT"),
+    (MouseAction::WheelRight, "MW_RIGHT"),
+];
+
+/// The KMK key name of a usage, e.g. `ESC` for `KC.ESC`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    KMK_KEYS.get(&usage).copied()
+}
+
+/// Whether the model is built as two halves with a controller each
+fn is_split(keyboard: &KeyboardModel) -> bool {
+    matches!(
+        keyboard.id.as_str(),
+        "dactyl_manuform_5x7" | "dactyl_manuform_5x6" | "dactyl_manuform_4x6" | "corne" | "ferris_sweep"
+    )
+}
+
+/// KMK matrix index of every key, in keymap order. Split keyboards number the right half's
+/// rows after the left half's, with its columns counted from the outer edge.
+pub fn coord_mapping(keyboard: &KeyboardModel) -> Vec<usize> {
+    let positions = matrix_positions(keyboard);
+    let rows = positions.iter().map(|&(row, _)| row).max().map_or(0, |row| row + 1);
+    let cols = positions.iter().map(|&(_, col)| col).max().map_or(0, |col| col + 1);
+    let split = is_split(keyboard);
+    let half = cols.div_ceil(2);
+
+    positions.iter()
+        .map(|&(row, col)| match split {
+            true if col >= half => (rows + row) * half + (cols - 1 - col),
+            true => row * half + col,
+            false => row * cols + col,
+        })
+        .collect()
+}
+
+/// Write the keymap as a KMK `main.py`
+pub fn export_main_py(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let positions = matrix_positions(keyboard);
+    let rows = positions.iter().map(|&(row, _)| row).max().map_or(0, |row| row + 1);
+    let cols = positions.iter().map(|&(_, col)| col).max().map_or(0, |col| col + 1);
+    let split = is_split(keyboard);
+    let mut warnings = Vec::new();
+
+    let layer_rows: Vec<Vec<Vec<String>>> = layers.iter().enumerate()
+        .map(|(index, layer)| {
+            positions
+                .chunk_by(|a, b| a.0 == b.0)
+                .map(|row| {
+                    row.iter()
+                        .map(|&position| {
+                            let (key, warning) = match layer.unmapped.get(&position) {
+                                Some(unmapped) => ("KC.NO".to_string(), Some(format!(
+                                    "{} keycode '{}' has no KMK equivalent, exported as KC.NO", unmapped.format, unmapped.keycode
+                                ))),
+                                None => action_key(action_at(layers, index, position)),
+                            };
+                            if let Some(warning) = warning {
+                                warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+                            }
+                            key
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    // KMK modules the keys need, besides layers
+    let keys: Vec<&String> = layer_rows.iter().flatten().flatten().collect();
+    let hold_tap = keys.iter().any(|key| key.starts_with("KC.HT("));
+    let one_shot = keys.iter().any(|key| key.starts_with("KC.OS("));
+    let mouse_keys = keys.iter().any(|key| KMK_MOUSE_KEYS.iter().any(|&(_, name)| key.contains(&format!("KC.{}", name))));
+    let media_keys = keys.iter().any(|key| KMK_CONSUMER_KEYS.iter().any(|&(_, name)| key.contains(&format!("KC.{}", name))));
+
+    let mut output = format!("# {} keymap exported by layout-rs\n", keyboard.name);
+    output.push_str("#\n# Set the pins to your wiring, then copy this file to the CIRCUITPY drive");
+    output.push_str(if split { " of both halves.\n\n" } else { ".\n\n" });
+    output.push_str("import board\n\n");
+    if media_keys {
+        output.push_str("from kmk.extensions.media_keys import MediaKeys\n");
+    }
+    output.push_str("from kmk.keys import KC\nfrom kmk.kmk_keyboard import KMKKeyboard\n");
+    if hold_tap {
+        output.push_str("from kmk.modules.holdtap import HoldTap\n");
+    }
+    output.push_str("from kmk.modules.layers import Layers\n");
+    if mouse_keys {
+        output.push_str("from kmk.modules.mouse_keys import MouseKeys\n");
+    }
+    if one_shot {
+        output.push_str("from kmk.modules.oneshot import OneShot\n");
+    }
+    if split {
+        output.push_str("from kmk.modules.split import Split\n");
+    }
+    output.push_str("from kmk.scanners import DiodeOrientation\n\nkeyboard = KMKKeyboard()\n");
+    if media_keys {
+        output.push_str("keyboard.extensions.append(MediaKeys())\n");
+    }
+    if hold_tap {
+        output.push_str("keyboard.modules.append(HoldTap())\n");
+    }
+    output.push_str("keyboard.modules.append(Layers())\n");
+    if mouse_keys {
+        output.push_str("keyboard.modules.append(MouseKeys())\n");
+    }
+    if one_shot {
+        output.push_str("keyboard.modules.append(OneShot())\n");
+    }
+    if split {
+        output.push_str("keyboard.modules.append(Split())\n");
+    }
+
+    let (col_pins, mapping_comment) = match split {
+        true => (cols.div_ceil(2), format!(
+            "# Matrix index of each key below: left half rows 0-{}, right half rows {}-{},\n# with the right half's columns counted from its outer edge\n",
+            rows - 1, rows, 2 * rows - 1
+        )),
+        false => (cols, "# Matrix index of each key below\n".to_string()),
+    };
+    output.push_str(&format!(
+        "\nkeyboard.col_pins = ()  # {} pins{}, e.g. (board.GP2, board.GP3)\nkeyboard.row_pins = ()  # {} pins\n",
+        col_pins, if split { " per half" } else { "" }, rows
+    ));
+    output.push_str("keyboard.diode_orientation = DiodeOrientation.COL2ROW\n\n");
+
+    // Indices are right-aligned and keys left-aligned, both grouped by matrix row
+    output.push_str(&mapping_comment);
+    output.push_str("keyboard.coord_mapping = [\n");
+    let mapping = coord_mapping(keyboard);
+    let index_width = mapping.iter().max().map_or(1, |index| index.to_string().len());
+    let mut indices = mapping.iter();
+    for row in positions.chunk_by(|a, b| a.0 == b.0) {
+        let cells: Vec<String> = indices.by_ref().take(row.len())
+            .map(|index| format!("{:>width$},", index, width = index_width))
+            .collect();
+        output.push_str(&format!("    {}\n", cells.join(" ")));
+    }
+    output.push_str("]\n\nkeyboard.keymap = [\n");
+
+    let width = keys.iter().map(|key| key.len() + 1).max().unwrap_or(0);
+    for (index, rows) in layer_rows.iter().enumerate() {
+        output.push_str(&format!("    # {}\n", layers[index].name));
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(&format!("    # {}\n", line));
+        }
+        output.push_str("    [\n");
+        for row in rows {
+            let cells: Vec<String> = row.iter()
+                .map(|key| format!("{:<width$}", format!("{},", key), width = width))
+                .collect();
+            output.push_str(&format!("        {}\n", cells.join(" ").trim_end()));
+        }
+        output.push_str("    ],\n");
+    }
+    output.push_str("]\n\nif __name__ == '__main__':\n    keyboard.go()\n");
+
+    Conversion { output, warnings }
+}
+
+/// KMK key for an action, and a warning if it had to be approximated
+fn action_key(action: KeyAction) -> (String, Option<String>) {
+    let name = |usage: KeyboardUsage| key_name(usage)
+        .map(|name| format!("KC.{}", name))
+        .ok_or_else(|| format!("KMK has no key name for {:?}, exported as KC.NO", usage));
+    // Wrap a key in every modifier of the set, e.g. `KC.LCTL(KC.LSFT(KC.Z))`
+    let wrap = |modifiers: Modifiers, key: String| KMK_MODIFIERS.iter().rev()
+        .filter(|&&(modifier, _)| modifiers.contains(modifier))
+        .fold(key, |key, &(_, function)| format!("KC.{}({})", function, key));
+
+    let key = match action {
+        KeyAction::Key(usage) => name(usage),
+        KeyAction::Consumer(usage) => KMK_CONSUMER_KEYS.iter()
+            .find(|&&(other, _)| other == usage)
+            .map(|&(_, name)| format!("KC.{}", name))
+            .ok_or_else(|| format!("KMK has no key name for {:?}, exported as KC.NO", usage)),
+        KeyAction::Mouse(mouse) => KMK_MOUSE_KEYS.iter()
+            .find(|&&(other, _)| other == mouse)
+            .map(|&(_, name)| format!("KC.{}", name))
+            .ok_or_else(|| format!("KMK has no mouse key for {:?}, exported as KC.NO", mouse)),
+        KeyAction::Modified { modifiers, key } => name(key).map(|key| wrap(modifiers, key)),
+        KeyAction::ModTap { modifiers, tap } => name(tap).map(|tap| {
+            // The hold key is the first modifier, wrapped in any others
+            match KMK_MODIFIERS.iter().find(|&&(modifier, _)| modifiers.contains(modifier)) {
+                Some(&(first, hold)) => {
+                    let others = KMK_MODIFIERS.iter()
+                        .filter(|&&(modifier, _)| modifier != first && modifiers.contains(modifier))
+                        .fold(Modifiers::NONE, |others, &(modifier, _)| others | modifier);
+                    format!("KC.HT({}, {})", tap, wrap(others, format!("KC.{}", hold)))
+                }
+                None => tap,
+            }
+        }),
+        KeyAction::LayerTap { layer, tap } => name(tap).map(|tap| format!("KC.LT({}, {})", layer, tap)),
+        KeyAction::MomentaryLayer(layer) => Ok(format!("KC.MO({})", layer)),
+        KeyAction::ToggleLayer(layer) => Ok(format!("KC.TG({})", layer)),
+        KeyAction::OneShotLayer(layer) => Ok(format!("KC.OS(KC.MO({}))", layer)),
+        KeyAction::Transparent => Ok("KC.TRNS".to_string()),
+        KeyAction::NoOp => Ok("KC.NO".to_string()),
+    };
+    match key {
+        Ok(key) => (key, None),
+        Err(warning) => ("KC.NO".to_string(), Some(warning)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::keyboards;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_action_keys() {
+        let key = |action| action_key(action).0;
+
+        assert_eq!(key(KeyAction::Key(Keyboard1Exclamation)), "KC.N1");
+        assert_eq!(key(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), "KC.LCTL(KC.LSFT(KC.Z))");
+        assert_eq!(key(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), "KC.HT(KC.ESC, KC.LCTL)");
+        assert_eq!(key(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa }), "KC.HT(KC.A, KC.LSFT(KC.LCTL))");
+        assert_eq!(key(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), "KC.LT(1, KC.SPC)");
+        assert_eq!(key(KeyAction::MomentaryLayer(2)), "KC.MO(2)");
+        assert_eq!(key(KeyAction::ToggleLayer(1)), "KC.TG(1)");
+        assert_eq!(key(KeyAction::OneShotLayer(1)), "KC.OS(KC.MO(1))");
+        assert_eq!(key(KeyAction::Transparent), "KC.TRNS");
+        assert_eq!(key(KeyAction::NoOp), "KC.NO");
+        assert_eq!(key(KeyAction::Consumer(ConsumerUsage::ScanNextTrack)), "KC.MNXT");
+        assert_eq!(key(KeyAction::Mouse(MouseAction::Button1)), "KC.MB_LMB");
+        assert_eq!(action_key(KeyAction::Mouse(MouseAction::Accel0)).1.as_deref(), Some("KMK has no mouse key for Accel0, exported as KC.NO"));
+
+        let (output, warning) = action_key(KeyAction::Key(Keypad00));
+        assert_eq!(output, "KC.NO");
+        assert_eq!(warning.as_deref(), Some("KMK has no key name for Keypad00, exported as KC.NO"));
+    }
+
+    #[test]
+    fn test_coord_mapping() {
+        let mapping = coord_mapping(test_keyboard());
+        assert_eq!(mapping.len(), 74);
+        // Left half row 0, then the right half's row 0 from its inner column outwards
+        assert_eq!(mapping[..14], [0, 1, 2, 3, 4, 5, 6, 62, 61, 60, 59, 58, 57, 56]);
+        let mut sorted = mapping.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), mapping.len());
+
+        // Unibody boards use the plain matrix index
+        let kinesis = keyboards::find_keyboard("kinesis_advantage").unwrap();
+        assert_eq!(coord_mapping(kinesis)[..3], [0, 1, 2]);
+        assert_eq!(coord_mapping(kinesis)[12], 12);
+    }
+
+    #[test]
+    fn test_export_main_py() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_main_py(test_keyboard(), &layers);
+        let main = &export.output;
+
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        assert!(main.contains("from kmk.modules.layers import Layers\nfrom kmk.modules.split import Split\n"));
+        assert!(!main.contains("HoldTap"));
+        assert!(!main.contains("MediaKeys"));
+        assert!(!main.contains("MouseKeys"));
+        assert!(main.contains("keyboard.col_pins = ()  # 7 pins per half"));
+        assert!(main.contains("keyboard.coord_mapping = [\n      0,   1,   2,"));
+        assert!(main.contains("    # Base\n    # | Esc   | 1     |"));
+        assert!(main.contains("        KC.ESC,   KC.N1,    KC.N2,"));
+        assert!(main.contains("KC.LGUI,  KC.MO(1), KC.MO(2), KC.RGUI,"));
+        assert!(main.contains("        KC.TRNS,  KC.F1,    KC.TRNS,"));
+        assert!(main.ends_with("if __name__ == '__main__':\n    keyboard.go()\n"));
+        assert_eq!(main.matches("    [\n").count(), 3);
+
+        layers[1].keys.insert((0, 2), KeyAction::Consumer(ConsumerUsage::PlayPause));
+        let main = export_main_py(test_keyboard(), &layers).output;
+        assert!(main.contains("import board\n\nfrom kmk.extensions.media_keys import MediaKeys\nfrom kmk.keys import KC\n"));
+        assert!(main.contains("keyboard.extensions.append(MediaKeys())\n"));
+        assert!(main.contains("        KC.TRNS,  KC.F1,    KC.MPLY,"));
+
+        layers[2].keys.insert((0, 1), KeyAction::Mouse(MouseAction::WheelUp));
+        let main = export_main_py(test_keyboard(), &layers).output;
+        assert!(main.contains("from kmk.modules.layers import Layers\nfrom kmk.modules.mouse_keys import MouseKeys\n"));
+        assert!(main.contains("keyboard.modules.append(Layers())\nkeyboard.modules.append(MouseKeys())\n"));
+    }
+}
@@ -0,0 +1,534 @@
+//! # Firmware Formats
+//!
+//! Converters between keymap [`Layer`]s and the files keyboard firmware and configurator
+//! tools work with. Keys that a format cannot represent are still converted as closely as
+//! possible, and reported as warnings alongside the output.
+//!
+//! | Format                   | Module        | Export | Import |
+//! |--------------------------|---------------|--------|--------|
+//! | QMK `keymap.c`           | [`qmk`]       | yes    | yes    |
+//! | QMK `keymap.json`        | [`qmk`]       | yes    | yes    |
+//! | ZMK `.keymap`            | [`zmk`]       | yes    | yes    |
+//! | VIA layout `.json`       | [`via`]       | yes    | yes    |
+//! | Vial `.vil`              | [`via`]       | yes    | yes    |
+//! | Kanata `.kbd`            | [`kanata`]    | yes    |        |
+//! | RMK `keyboard.toml`      | [`rmk`]       | yes    |        |
+//! | Keyberon `layout!`       | [`keyberon`]  | yes    |        |
+//! | KMK `main.py`            | [`kmk`]       | yes    |        |
+//! | XKB symbols              | [`xkb`]       | yes    |        |
+//! | Karabiner-Elements rules | [`karabiner`] | yes    |        |
+//! | KLE raw data (geometry)  | [`kle`]       |        | yes    |
+
+use std::collections::HashMap;
+use crate::actions::KeyAction;
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::KeyboardUsage;
+use crate::keymap::Layer;
+
+pub mod kanata;
+pub mod karabiner;
+pub mod keyberon;
+pub mod kle;
+pub mod kmk;
+pub mod qmk;
+pub mod rmk;
+pub mod via;
+pub mod xkb;
+pub mod zmk;
+
+/// Converted output together with everything that could not be converted exactly
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Conversion<T> {
+    pub output: T,
+    pub warnings: Vec<String>,
+}
+
+/// File formats the editor can download
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ExportFormat {
+    QmkKeymapC,
+    QmkKeymapJson,
+    ZmkKeymap,
+    ViaJson,
+    VialVil,
+    KanataKbd,
+    RmkKeyboardToml,
+    KeyberonLayout,
+    KmkMainPy,
+    XkbSymbols,
+    KarabinerJson,
+}
+
+impl ExportFormat {
+    /// All formats, in picker order
+    pub const ALL: [Self; 11] = [
+        Self::QmkKeymapC,
+        Self::QmkKeymapJson,
+        Self::ZmkKeymap,
+        Self::ViaJson,
+        Self::VialVil,
+        Self::KanataKbd,
+        Self::RmkKeyboardToml,
+        Self::KeyberonLayout,
+        Self::KmkMainPy,
+        Self::XkbSymbols,
+        Self::KarabinerJson,
+    ];
+
+    /// Stable identifier, e.g. for a `<select>` value
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::QmkKeymapC => "qmk_keymap_c",
+            Self::QmkKeymapJson => "qmk_keymap_json",
+            Self::ZmkKeymap => "zmk_keymap",
+            Self::ViaJson => "via_json",
+            Self::VialVil => "vial_vil",
+            Self::KanataKbd => "kanata_kbd",
+            Self::RmkKeyboardToml => "rmk_keyboard_toml",
+            Self::KeyberonLayout => "keyberon_layout",
+            Self::KmkMainPy => "kmk_main_py",
+            Self::XkbSymbols => "xkb_symbols",
+            Self::KarabinerJson => "karabiner_json",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::QmkKeymapC => "QMK keymap.c",
+            Self::QmkKeymapJson => "QMK keymap.json",
+            Self::ZmkKeymap => "ZMK .keymap",
+            Self::ViaJson => "VIA layout .json",
+            Self::VialVil => "Vial .vil",
+            Self::KanataKbd => "Kanata .kbd",
+            Self::RmkKeyboardToml => "RMK keyboard.toml",
+            Self::KeyberonLayout => "Keyberon layout.rs",
+            Self::KmkMainPy => "KMK main.py",
+            Self::XkbSymbols => "Linux XKB symbols",
+            Self::KarabinerJson => "macOS Karabiner-Elements rules",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.id() == id)
+    }
+
+    /// Name of the downloaded file
+    pub fn file_name(self, keyboard: &KeyboardModel) -> String {
+        match self {
+            Self::QmkKeymapC => "keymap.c".to_string(),
+            Self::QmkKeymapJson => "keymap.json".to_string(),
+            Self::ZmkKeymap => zmk::file_name(keyboard),
+            Self::ViaJson => format!("{}_via.json", keyboard.id),
+            Self::VialVil => format!("{}.vil", keyboard.id),
+            Self::KanataKbd => kanata::file_name(keyboard),
+            Self::RmkKeyboardToml => "keyboard.toml".to_string(),
+            Self::KeyberonLayout => "layout.rs".to_string(),
+            Self::KmkMainPy => "main.py".to_string(),
+            Self::XkbSymbols => xkb::FILE_NAME.to_string(),
+            Self::KarabinerJson => karabiner::file_name(keyboard),
+        }
+    }
+
+    pub fn export(self, keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+        match self {
+            Self::QmkKeymapC => qmk::export_keymap_c(keyboard, layers),
+            Self::QmkKeymapJson => qmk::export_keymap_json(keyboard, layers),
+            Self::ZmkKeymap => zmk::export_keymap(keyboard, layers),
+            Self::ViaJson => via::export_via(keyboard, layers),
+            Self::VialVil => via::export_vial(keyboard, layers),
+            Self::KanataKbd => kanata::export_config(keyboard, layers),
+            Self::RmkKeyboardToml => rmk::export_keyboard_toml(keyboard, layers),
+            Self::KeyberonLayout => keyberon::export_layout(keyboard, layers),
+            Self::KmkMainPy => kmk::export_main_py(keyboard, layers),
+            Self::XkbSymbols => xkb::export_symbols(keyboard, layers),
+            Self::KarabinerJson => karabiner::export_rules(keyboard, layers),
+        }
+    }
+}
+
+/// Firmware files the editor can import, besides its own JSON export
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ImportFormat {
+    QmkKeymapC,
+    QmkKeymapJson,
+    ZmkKeymap,
+    ViaJson,
+    VialVil,
+}
+
+impl ImportFormat {
+    /// Recognise a file from its name and contents. `None` means the editor's own format.
+    pub fn detect(file_name: &str, contents: &str) -> Option<Self> {
+        if file_name.ends_with(".keymap") || contents.contains("\"zmk,keymap\"") {
+            return Some(Self::ZmkKeymap);
+        }
+        if file_name.ends_with(".c") || contents.contains("[MATRIX_ROWS][MATRIX_COLS]") {
+            return Some(Self::QmkKeymapC);
+        }
+        let value: serde_json::Value = serde_json::from_str(contents).ok()?;
+        if file_name.ends_with(".vil") || value.get("vial_protocol").is_some() || value.get("uid").is_some() {
+            return value.get("layout").is_some_and(|layout| layout.is_array()).then_some(Self::VialVil);
+        }
+        if value.get("vendorProductId").is_some() {
+            return Some(Self::ViaJson);
+        }
+        let is_qmk = value.get("metadata").is_none()
+            && value.get("layers").and_then(|layers| layers.get(0)).is_some_and(|layer| layer.is_array());
+        is_qmk.then_some(Self::QmkKeymapJson)
+    }
+
+    pub fn import(self, keyboard: &KeyboardModel, contents: &str) -> Result<Conversion<Vec<Layer>>, String> {
+        match self {
+            Self::QmkKeymapC => qmk::import_keymap_c(keyboard, contents),
+            Self::QmkKeymapJson => qmk::import_keymap_json(keyboard, contents),
+            Self::ZmkKeymap => zmk::import_keymap(keyboard, contents),
+            Self::ViaJson => via::import_via(keyboard, contents),
+            Self::VialVil => via::import_vial(keyboard, contents),
+        }
+    }
+}
+
+/// Matrix positions of the keyboard in row-major order, the order firmware lists keys in
+pub fn matrix_positions(keyboard: &KeyboardModel) -> Vec<(usize, usize)> {
+    let mut positions: Vec<(usize, usize)> = keyboard.geometry.keys().iter()
+        .map(|key| key.position())
+        .collect();
+    positions.sort();
+    positions
+}
+
+/// Keys that rarely carry a layout of their own, in the order they stand in for positions
+/// without a key on the base layer in [`source_keys`]
+const SPARE_KEYS: [KeyboardUsage; 20] = {
+    use KeyboardUsage::*;
+
+    [
+        KeyboardApplication, KeyboardNonUSSlash, KeyboardBacktickTilde, KeyboardPrintScreen, KeyboardScrollLock,
+        KeyboardPause, KeyboardInsert, KeypadNumLock, KeyboardF1, KeyboardF2, KeyboardF3, KeyboardF4, KeyboardF5,
+        KeyboardF6, KeyboardF7, KeyboardF8, KeyboardF9, KeyboardF10, KeyboardF11, KeyboardF12,
+    ]
+};
+
+/// The physical keys of an ordinary keyboard that stand in for the matrix positions when a
+/// keymap is emulated in software, grouped by matrix row. Each position is read from the key
+/// it sends on the base layer, so the letters stay where they are printed. Positions without
+/// a key of their own there, such as Lower and Raise or a second Backspace, take the next
+/// spare key and are reported as warnings, or are left out when none is left.
+///
+/// `name` gives the format's name of a key; keys without one are never used.
+pub fn source_keys(
+    keyboard: &KeyboardModel,
+    layers: &[Layer],
+    name: impl Fn(KeyboardUsage) -> Option<&'static str>,
+    warnings: &mut Vec<String>,
+) -> Vec<Vec<((usize, usize), KeyboardUsage)>> {
+    let positions = matrix_positions(keyboard);
+    let base_key = |position| match action_at(layers, 0, position) {
+        KeyAction::Key(usage) | KeyAction::Modified { key: usage, .. } => Some(usage),
+        KeyAction::ModTap { tap, .. } | KeyAction::LayerTap { tap, .. } => Some(tap),
+        _ => None,
+    };
+
+    // Keys of the base layer come first, so a spare key never takes a key that is in use
+    let mut sources: HashMap<(usize, usize), KeyboardUsage> = HashMap::new();
+    for &position in &positions {
+        let key = base_key(position).filter(|&key| name(key).is_some() && !sources.values().any(|&used| used == key));
+        if let Some(key) = key {
+            sources.insert(position, key);
+        }
+    }
+    let used: Vec<KeyboardUsage> = sources.values().copied().collect();
+    let mut spare = SPARE_KEYS.into_iter().filter(|&key| name(key).is_some() && !used.contains(&key));
+    for &position in &positions {
+        if sources.contains_key(&position) {
+            continue;
+        }
+        let label = action_at(layers, 0, position).to_string();
+        let label = if label.is_empty() { "the empty key".to_string() } else { format!("'{}'", label) };
+        match spare.next() {
+            Some(key) => {
+                let key_name = name(key).unwrap_or_default();
+                warnings.push(format!("[{}, {}]: {} is read from the {} key", position.0, position.1, label, key_name));
+                sources.insert(position, key);
+            }
+            None => warnings.push(format!("[{}, {}]: no spare key is left for {}, left out", position.0, position.1, label)),
+        }
+    }
+
+    positions
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|row| row.iter().filter_map(|position| Some((*position, *sources.get(position)?))).collect::<Vec<_>>())
+        .filter(|row| !row.is_empty())
+        .collect()
+}
+
+/// The action of a key on the given layer. Keys that were never assigned do nothing on
+/// the base layer and fall through on the layers above it.
+pub fn action_at(layers: &[Layer], layer: usize, position: (usize, usize)) -> KeyAction {
+    match layers[layer].keys.get(&position) {
+        Some(&action) => action,
+        None if layer == 0 => KeyAction::NoOp,
+        None => KeyAction::Transparent,
+    }
+}
+
+/// Name for an imported layer: the model's default name if it has one, else `Layer n`
+pub fn layer_name(keyboard: &KeyboardModel, index: usize) -> String {
+    keyboard.default_layers.get(index)
+        .map(|layer| layer.name.clone())
+        .unwrap_or_else(|| format!("Layer {}", index))
+}
+
+/// Upper-case identifiers for the layers, e.g. `BASE` and `LOWER`, unique within the keymap
+pub fn layer_identifiers(layers: &[Layer]) -> Vec<String> {
+    let mut identifiers: Vec<String> = Vec::with_capacity(layers.len());
+    for (index, layer) in layers.iter().enumerate() {
+        let mut identifier = String::new();
+        for c in layer.name.chars() {
+            if c.is_ascii_alphanumeric() {
+                identifier.push(c.to_ascii_uppercase());
+            } else if !identifier.is_empty() && !identifier.ends_with('_') {
+                identifier.push('_');
+            }
+        }
+        let mut identifier = identifier.trim_end_matches('_').to_string();
+        if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+            identifier = format!("LAYER{}", index);
+        }
+        if identifiers.contains(&identifier) {
+            identifier = format!("{}_{}", identifier, index);
+        }
+        identifiers.push(identifier);
+    }
+    identifiers
+}
+
+/// One comment line per matrix row showing the key labels, e.g. `| Esc   | 1     |`.
+/// Transparent keys are left blank.
+pub fn ascii_art(keyboard: &KeyboardModel, layers: &[Layer], layer: usize) -> Vec<String> {
+    /// Width of a label; every cell is one wider for its left border and padding
+    const LABEL_WIDTH: usize = 6;
+
+    let positions = matrix_positions(keyboard);
+    let rows = positions.iter().map(|&(row, _)| row).max().map_or(0, |row| row + 1);
+    let cols = positions.iter().map(|&(_, col)| col).max().map_or(0, |col| col + 1);
+
+    (0..rows)
+        .map(|row| {
+            let mut line = String::new();
+            let mut previous = false;
+            for col in 0..cols {
+                let present = keyboard.geometry.contains((row, col));
+                if present {
+                    let label = match (layers[layer].unmapped.get(&(row, col)), action_at(layers, layer, (row, col))) {
+                        (Some(unmapped), _) => unmapped.keycode.clone(),
+                        (None, KeyAction::Transparent) => String::new(),
+                        (None, action) => action.to_string(),
+                    };
+                    let label: String = label.chars().take(LABEL_WIDTH).collect();
+                    line.push_str(&format!("| {:<width$}", label, width = LABEL_WIDTH));
+                } else {
+                    line.push_str(if previous { "|" } else { " " });
+                    line.push_str(&" ".repeat(LABEL_WIDTH + 1));
+                }
+                previous = present;
+            }
+            if previous {
+                line.push('|');
+            }
+            // Labels such as `*/` must not end the surrounding comment
+            line.trim_end().replace("*/", "* /")
+        })
+        .collect()
+}
+
+// Helpers for the C-like source formats (QMK keymap.c, ZMK devicetree)
+
+/// Replace comments with spaces, keeping line breaks and string literals, so offsets and
+/// line numbers still match the original source
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                code.push(' ');
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    blank(&mut code, next);
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                code.push_str("  ");
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        code.push('\n');
+                    } else {
+                        blank(&mut code, next);
+                    }
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            ('"' | '\'', _) => {
+                code.push(c);
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    code.push(next);
+                    if next == c && !escaped {
+                        break;
+                    }
+                    escaped = next == '\\' && !escaped;
+                }
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+/// Spaces covering the same number of bytes as `c`
+fn blank(code: &mut String, c: char) {
+    code.push_str(&" ".repeat(c.len_utf8()));
+}
+
+/// Numbers given to names by `#define NAME 2` lines
+fn define_constants(code: &str) -> HashMap<String, usize> {
+    let mut constants = HashMap::new();
+    for line in code.lines() {
+        let mut words = line.split_whitespace();
+        if let (Some("#define"), Some(name), Some(value), None) = (words.next(), words.next(), words.next(), words.next()) {
+            if let Ok(value) = value.parse() {
+                constants.insert(name.to_string(), value);
+            }
+        }
+    }
+    constants
+}
+
+/// 1-based line number of a byte offset
+fn line_number(code: &str, offset: usize) -> usize {
+    code[..offset].matches('\n').count() + 1
+}
+
+/// Split on top-level commas, returning each trimmed part with its offset in the source.
+/// `base` is the offset of `text` itself.
+fn split_arguments(text: &str, base: usize) -> Vec<(usize, &str)> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut push = |start: usize, end: usize| {
+        let part = &text[start..end];
+        let trimmed = part.trim_start();
+        let offset = base + start + part.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        if !trimmed.is_empty() {
+            parts.push((offset, trimmed));
+        }
+    };
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                push(start, index);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    push(start, text.len());
+    parts
+}
+
+/// Offset of `word` in `text` where it is not part of a longer identifier
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    text.match_indices(word)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            !text[..index].ends_with(is_identifier) && !text[index + word.len()..].starts_with(is_identifier)
+        })
+}
+
+/// Layer name for an identifier, e.g. `Nav Media` for `_NAV_MEDIA` or `nav_media`
+fn identifier_layer_name(identifier: &str) -> String {
+    identifier.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Keyboard the format tests convert layouts for
+#[cfg(test)]
+fn test_keyboard() -> &'static KeyboardModel {
+    crate::keyboards::default_keyboard()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboards;
+
+    #[test]
+    fn test_layer_identifiers() {
+        let layers: Vec<Layer> = ["Base", "lower", "Nav & Media", "", "Base", "2nd"]
+            .into_iter()
+            .map(Layer::new)
+            .collect();
+        assert_eq!(layer_identifiers(&layers), ["BASE", "LOWER", "NAV_MEDIA", "LAYER3", "BASE_4", "LAYER5"]);
+    }
+
+    #[test]
+    fn test_missing_keys() {
+        let keyboard = keyboards::find_keyboard("corne").unwrap();
+        let mut layers = keyboard.default_layers.clone();
+        layers[0].keys.remove(&(0, 0));
+        assert_eq!(action_at(&layers, 0, (0, 0)), KeyAction::NoOp);
+        assert_eq!(action_at(&layers, 1, (0, 0)), KeyAction::Transparent);
+    }
+
+    #[test]
+    fn test_ascii_art() {
+        let keyboard = keyboards::find_keyboard("ferris_sweep").unwrap();
+        let art = ascii_art(keyboard, &keyboard.default_layers, 0);
+        assert_eq!(art.len(), 4);
+        assert_eq!(art[0], "| Q     | W     | E     | R     | T     | Y     | U     | I     | O     | P     |");
+        assert_eq!(art[3], "                        | MO(1) | Space | BKSP  | MO(2) |");
+    }
+
+    #[test]
+    fn test_detect_import_format() {
+        let qmk = r#"{"keyboard": "crkbd/rev1", "layout": "LAYOUT_split_3x6_3", "layers": [["KC_A"]]}"#;
+        assert_eq!(ImportFormat::detect("keymap.json", qmk), Some(ImportFormat::QmkKeymapJson));
+
+        let export = r#"{"metadata": {"version": "3.0"}, "layers": [{"name": "Base", "keys": []}]}"#;
+        assert_eq!(ImportFormat::detect("dactyl_keymap.json", export), None);
+        assert_eq!(ImportFormat::detect("broken.json", "{"), None);
+
+        assert_eq!(ImportFormat::detect("keymap.c", "#include QMK_KEYBOARD_H"), Some(ImportFormat::QmkKeymapC));
+        let pasted = "const uint16_t PROGMEM keymaps[][MATRIX_ROWS][MATRIX_COLS] = {};";
+        assert_eq!(ImportFormat::detect("keymap.txt", pasted), Some(ImportFormat::QmkKeymapC));
+
+        assert_eq!(ImportFormat::detect("corne.keymap", "/ { };"), Some(ImportFormat::ZmkKeymap));
+        let pasted = "keymap { compatible = \"zmk,keymap\"; };";
+        assert_eq!(ImportFormat::detect("corne.dtsi", pasted), Some(ImportFormat::ZmkKeymap));
+
+        let via = r#"{"name": "Corne", "vendorProductId": 1179975681, "layers": [[4, 5]]}"#;
+        assert_eq!(ImportFormat::detect("corne.json", via), Some(ImportFormat::ViaJson));
+        let vial = r#"{"version": 1, "uid": 123, "layout": [[[4, -1]]]}"#;
+        assert_eq!(ImportFormat::detect("corne.vil", vial), Some(ImportFormat::VialVil));
+    }
+}
@@ -0,0 +1,918 @@
+//! # QMK
+//!
+//! - `keymap.c` import and export: every layer becomes a call of the board's layout macro
+//!   (`LAYOUT_5x7(...)`) in the `keymaps` array, with the keys in the order the macro takes
+//!   them and an ASCII-art comment showing the layer. The Dactyl Manuform 5x7 and Kinesis
+//!   macros take the whole left half, thumb cluster included, before the right half; the
+//!   others go row by row across both halves. Layers are named by an `enum layers` so layer
+//!   keys read as `MO(_LOWER)`. Imports resolve layer names from the file's `enum`s and `#define`s.
+//! - `keymap.json` import and export, the QMK Configurator format read by `qmk compile`.
+//!   Its `layout` names the same macro, and its `layers` list the keycodes of each layer in
+//!   the same order.
+//!
+//! Keycodes are parsed from their short (`KC_ESC`) and long (`KC_ESCAPE`) names, media keys
+//! (`KC_MPLY`), mouse keys (`MS_BTN1`, or the older `KC_BTN1`), shifted symbols (`KC_EXLM`), modifier wrappers (`LCTL(KC_C)`), mod-taps
+//! (`MT(MOD_LCTL, KC_A)`, `LCTL_T(KC_A)`) and layer keys (`MO`, `TG`, `OSL`, `LT`).
+//! Keymaps with mouse keys need `MOUSEKEY_ENABLE = yes` in the keymap's `rules.mk`.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage, NamingScheme};
+use crate::keymap::{Layer, UnmappedKeycode};
+use super::{
+    action_at, ascii_art, define_constants, find_word, identifier_layer_name, layer_identifiers, layer_name,
+    line_number, matrix_positions, split_arguments, strip_comments, Conversion,
+};
+
+/// Identifies keycodes kept from QMK files in [`UnmappedKeycode::format`]
+pub const FORMAT: &str = "qmk";
+
+/// Modifier bits with their QMK key wrapper (`LCTL(kc)`) and mod-tap mask (`MOD_LCTL`)
+const QMK_MODIFIERS: [(Modifiers, &str, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "LCTL", "MOD_LCTL"),
+    (Modifiers::LEFT_SHIFT, "LSFT", "MOD_LSFT"),
+    (Modifiers::LEFT_ALT, "LALT", "MOD_LALT"),
+    (Modifiers::LEFT_GUI, "LGUI", "MOD_LGUI"),
+    (Modifiers::RIGHT_CTRL, "RCTL", "MOD_RCTL"),
+    (Modifiers::RIGHT_SHIFT, "RSFT", "MOD_RSFT"),
+    (Modifiers::RIGHT_ALT, "RALT", "MOD_RALT"),
+    (Modifiers::RIGHT_GUI, "RGUI", "MOD_RGUI"),
+];
+
+/// Mouse keys with their QMK keycode and the names older QMK versions used
+const QMK_MOUSE_KEYCODES: [(MouseAction, &str, &[&str]); 16] = [
+    (MouseAction::Button1, "MS_BTN1", &["KC_MS_BTN1", "KC_BTN1"]),
+    (MouseAction::Button2, "MS_BTN2", &["KC_MS_BTN2", "KC_BTN2"]),
+    (MouseAction::Button3, "MS_BTN3", &["KC_MS_BTN3", "KC_BTN3"]),
+    (MouseAction::Button4, "MS_BTN4", &["KC_MS_BTN4", "KC_BTN4"]),
+    (MouseAction::Button5, "MS_BTN5", &["KC_MS_BTN5", "KC_BTN5"]),
+    (MouseAction::MoveUp, "MS_UP", &["KC_MS_UP", "KC_MS_U"]),
+    (MouseAction::MoveDown, "MS_DOWN", &["KC_MS_DOWN", "KC_MS_D"]),
+    (MouseAction::MoveLeft, "MS_LEFT", &["KC_MS_LEFT", "KC_MS_L"]),
+    (MouseAction::MoveRight, "MS_RGHT", &["KC_MS_RIGHT", "KC_MS_R"]),
+    (MouseAction::WheelUp, "MS_WHLU", &["KC_MS_WH_UP", "KC_WH_U"]),
+    (MouseAction::WheelDown, "MS_WHLD", &["KC_MS_WH_DOWN", "KC_WH_D"]),
+    (MouseAction::WheelLeft, "MS_WHLL", &["KC_MS_WH_LEFT", "KC_WH_L"]),
+    (MouseAction::WheelRight, "MS_WHLR", &["KC_MS_WH_RIGHT", "KC_WH_R"]),
+    (MouseAction::Accel0, "MS_ACL0", &["KC_MS_ACCEL0", "KC_ACL0"]),
+    (MouseAction::Accel1, "MS_ACL1", &["KC_MS_ACCEL1", "KC_ACL1"]),
+    (MouseAction::Accel2, "MS_ACL2", &["KC_MS_ACCEL2", "KC_ACL2"]),
+];
+
+/// Highest layer QMK's `LT()` can address
+const MAX_LAYER_TAP_LAYER: usize = 15;
+
+/// QMK names for shifted symbols, e.g. `KC_EXLM` for Shift+1
+static QMK_SHIFTED_KEYCODES: Lazy<HashMap<&'static str, KeyboardUsage>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        ("KC_TILD", KeyboardBacktickTilde),
+        ("KC_TILDE", KeyboardBacktickTilde),
+        ("KC_EXLM", Keyboard1Exclamation),
+        ("KC_EXCLAIM", Keyboard1Exclamation),
+        ("KC_AT", Keyboard2At),
+        ("KC_HASH", Keyboard3Hash),
+        ("KC_DLR", Keyboard4Dollar),
+        ("KC_DOLLAR", Keyboard4Dollar),
+        ("KC_PERC", Keyboard5Percent),
+        ("KC_PERCENT", Keyboard5Percent),
+        ("KC_CIRC", Keyboard6Caret),
+        ("KC_CIRCUMFLEX", Keyboard6Caret),
+        ("KC_AMPR", Keyboard7Ampersand),
+        ("KC_AMPERSAND", Keyboard7Ampersand),
+        ("KC_ASTR", Keyboard8Asterisk),
+        ("KC_ASTERISK", Keyboard8Asterisk),
+        ("KC_LPRN", Keyboard9OpenParens),
+        ("KC_LEFT_PAREN", Keyboard9OpenParens),
+        ("KC_RPRN", Keyboard0CloseParens),
+        ("KC_RIGHT_PAREN", Keyboard0CloseParens),
+        ("KC_UNDS", KeyboardDashUnderscore),
+        ("KC_UNDERSCORE", KeyboardDashUnderscore),
+        ("KC_PLUS", KeyboardEqualPlus),
+        ("KC_LCBR", KeyboardOpenBracketBrace),
+        ("KC_LEFT_CURLY_BRACE", KeyboardOpenBracketBrace),
+        ("KC_RCBR", KeyboardCloseBracketBrace),
+        ("KC_RIGHT_CURLY_BRACE", KeyboardCloseBracketBrace),
+        ("KC_PIPE", KeyboardBackslashBar),
+        ("KC_COLN", KeyboardSemiColon),
+        ("KC_COLON", KeyboardSemiColon),
+        ("KC_DQUO", KeyboardSingleDoubleQuote),
+        ("KC_DQT", KeyboardSingleDoubleQuote),
+        ("KC_DOUBLE_QUOTE", KeyboardSingleDoubleQuote),
+        ("KC_LABK", KeyboardCommaLess),
+        ("KC_LT", KeyboardCommaLess),
+        ("KC_LEFT_ANGLE_BRACKET", KeyboardCommaLess),
+        ("KC_RABK", KeyboardPeriodGreater),
+        ("KC_GT", KeyboardPeriodGreater),
+        ("KC_RIGHT_ANGLE_BRACKET", KeyboardPeriodGreater),
+        ("KC_QUES", KeyboardSlashQuestion),
+        ("KC_QUESTION", KeyboardSlashQuestion),
+    ])
+});
+
+/// QMK functions that wrap a keycode with modifiers, e.g. `LCTL(KC_C)` or `C(KC_C)`
+fn modifier_function(name: &str) -> Option<Modifiers> {
+    let modifiers = match name {
+        "LCTL" | "C" => Modifiers::LEFT_CTRL,
+        "LSFT" | "S" => Modifiers::LEFT_SHIFT,
+        "LALT" | "A" | "LOPT" => Modifiers::LEFT_ALT,
+        "LGUI" | "G" | "LCMD" | "LWIN" => Modifiers::LEFT_GUI,
+        "RCTL" => Modifiers::RIGHT_CTRL,
+        "RSFT" => Modifiers::RIGHT_SHIFT,
+        "RALT" | "ROPT" | "ALGR" => Modifiers::RIGHT_ALT,
+        "RGUI" | "RCMD" | "RWIN" => Modifiers::RIGHT_GUI,
+        "C_S" | "LCS" => Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT,
+        "MEH" => Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT | Modifiers::LEFT_ALT,
+        "HYPR" => Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT | Modifiers::LEFT_ALT | Modifiers::LEFT_GUI,
+        _ => return None,
+    };
+    Some(modifiers)
+}
+
+/// Mod-tap shorthands, e.g. `LCTL_T(KC_ESC)` or `SFT_T(KC_A)`
+fn mod_tap_function(name: &str) -> Option<Modifiers> {
+    let modifiers = name.strip_suffix("_T")?;
+    match modifiers {
+        "CTL" => Some(Modifiers::LEFT_CTRL),
+        "SFT" => Some(Modifiers::LEFT_SHIFT),
+        "ALT" | "OPT" => Some(Modifiers::LEFT_ALT),
+        "GUI" | "CMD" | "WIN" => Some(Modifiers::LEFT_GUI),
+        "ALL" => modifier_function("HYPR"),
+        _ => modifier_function(modifiers),
+    }
+}
+
+/// Mod-tap modifier masks such as `MOD_LCTL | MOD_LSFT`
+fn parse_mod_mask(mask: &str) -> Option<Modifiers> {
+    let mut modifiers = Modifiers::NONE;
+    for name in mask.split('|') {
+        let name = name.trim();
+        modifiers |= match name {
+            "MOD_MEH" => modifier_function("MEH")?,
+            "MOD_HYPR" => modifier_function("HYPR")?,
+            _ => QMK_MODIFIERS.iter()
+                .find(|&&(_, _, mask)| mask == name)
+                .map(|&(modifier, _, _)| modifier)?,
+        };
+    }
+    Some(modifiers)
+}
+
+/// The QMK keycode name of a usage, e.g. `KC_ESC`
+pub fn keycode_name(usage: KeyboardUsage) -> Option<&'static str> {
+    usage.alias(NamingScheme::Qmk)
+}
+
+/// The usage of a QMK basic keycode, by its short or long name
+pub fn keycode_usage(name: &str) -> Option<KeyboardUsage> {
+    KeyboardUsage::from_alias(NamingScheme::Qmk, name)
+}
+
+/// Parse a QMK keycode expression such as `KC_A`, `LCTL(KC_C)` or `LT(_LOWER, KC_SPC)`.
+/// Layer arguments may be numbers or names that `layer` resolves, e.g. enum constants.
+pub fn parse_keycode(expression: &str, layer: &dyn Fn(&str) -> Option<usize>) -> Option<KeyAction> {
+    let expression = expression.trim();
+    match expression {
+        "KC_TRNS" | "KC_TRANSPARENT" | "_______" => return Some(KeyAction::Transparent),
+        "KC_NO" | "XXXXXXX" => return Some(KeyAction::NoOp),
+        _ => {}
+    }
+    if let Some(usage) = keycode_usage(expression) {
+        return Some(KeyAction::Key(usage));
+    }
+    if let Some(usage) = ConsumerUsage::from_alias(NamingScheme::Qmk, expression) {
+        return Some(KeyAction::Consumer(usage));
+    }
+    if let Some(&(action, _, _)) = QMK_MOUSE_KEYCODES.iter()
+        .find(|&&(_, name, legacy)| name == expression || legacy.contains(&expression))
+    {
+        return Some(KeyAction::Mouse(action));
+    }
+    if let Some(&key) = QMK_SHIFTED_KEYCODES.get(expression) {
+        return Some(KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key });
+    }
+
+    let (function, args) = split_call(expression)?;
+    let parse_layer = |arg: &str| arg.parse::<usize>().ok().or_else(|| layer(arg));
+    match (function, args.as_slice()) {
+        ("MO", [arg]) => Some(KeyAction::MomentaryLayer(parse_layer(arg)?)),
+        ("TG", [arg]) => Some(KeyAction::ToggleLayer(parse_layer(arg)?)),
+        ("OSL", [arg]) => Some(KeyAction::OneShotLayer(parse_layer(arg)?)),
+        ("LT", [arg, tap]) => Some(KeyAction::LayerTap { layer: parse_layer(arg)?, tap: keycode_usage(tap)? }),
+        ("MT", [mask, tap]) => Some(KeyAction::ModTap { modifiers: parse_mod_mask(mask)?, tap: keycode_usage(tap)? }),
+        (function, [tap]) if mod_tap_function(function).is_some() => {
+            Some(KeyAction::ModTap { modifiers: mod_tap_function(function)?, tap: keycode_usage(tap)? })
+        }
+        (function, [inner]) => {
+            let modifiers = modifier_function(function)?;
+            match parse_keycode(inner, layer)? {
+                KeyAction::Key(key) => Some(KeyAction::Modified { modifiers, key }),
+                KeyAction::Modified { modifiers: inner, key } => Some(KeyAction::Modified { modifiers: modifiers | inner, key }),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Split `NAME(a, b)` into its name and top-level arguments
+fn split_call(expression: &str) -> Option<(&str, Vec<&str>)> {
+    let (function, rest) = expression.split_once('(')?;
+    let inner = rest.strip_suffix(')')?;
+
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(inner[start..].trim());
+    Some((function.trim(), args))
+}
+
+/// QMK keyboard name of a model, as used by `qmk compile -kb`
+fn qmk_keyboard(keyboard: &KeyboardModel) -> &str {
+    match keyboard.id.as_str() {
+        "dactyl_manuform_5x7" => "handwired/dactyl_manuform/5x7",
+        "dactyl_manuform_5x6" => "handwired/dactyl_manuform/5x6",
+        "dactyl_manuform_4x6" => "handwired/dactyl_manuform/4x6",
+        "corne" => "crkbd/rev1",
+        "ferris_sweep" => "ferris/sweep",
+        "kinesis_advantage" => "kinesis/kint36",
+        id => id,
+    }
+}
+
+/// QMK layout macro of a model, and the column its right half starts at if the macro takes
+/// the whole left half, thumb cluster included, before the right half. Other macros take
+/// both halves row by row.
+fn qmk_layout(keyboard: &KeyboardModel) -> (&'static str, Option<usize>) {
+    match keyboard.id.as_str() {
+        "dactyl_manuform_5x7" => ("LAYOUT_5x7", Some(7)),
+        "dactyl_manuform_5x6" => ("LAYOUT_5x6", None),
+        "dactyl_manuform_4x6" => ("LAYOUT_4x6", None),
+        "corne" => ("LAYOUT_split_3x6_3", None),
+        "ferris_sweep" => ("LAYOUT_split_3x5_2", None),
+        "kinesis_advantage" => ("LAYOUT", Some(6)),
+        _ => ("LAYOUT", None),
+    }
+}
+
+/// Key positions in the order the model's layout macro takes them, one group per line of
+/// the macro (a row, or a row of one half)
+fn layout_rows(keyboard: &KeyboardModel) -> Vec<Vec<(usize, usize)>> {
+    let (_, right_half) = qmk_layout(keyboard);
+    let half = |&(_, col): &(usize, usize)| right_half.is_some_and(|right_half| col >= right_half);
+
+    let mut positions = matrix_positions(keyboard);
+    positions.sort_by_key(|position| (half(position), *position));
+    positions.chunk_by(|a, b| (half(a), a.0) == (half(b), b.0))
+        .map(<[_]>::to_vec)
+        .collect()
+}
+
+/// Key positions in the order the model's layout macro takes them
+fn layout_positions(keyboard: &KeyboardModel) -> Vec<(usize, usize)> {
+    layout_rows(keyboard).concat()
+}
+
+/// Write the keymap as a QMK `keymap.c`
+pub fn export_keymap_c(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let identifiers: Vec<String> = layer_identifiers(layers).iter()
+        .map(|identifier| format!("_{}", identifier))
+        .collect();
+    let mut warnings = Vec::new();
+
+    let mut output = format!("// {} keymap exported by layout-rs\n\n#include QMK_KEYBOARD_H\n\n", keyboard.name);
+    output.push_str("enum layers {\n");
+    for identifier in &identifiers {
+        output.push_str(&format!("    {},\n", identifier));
+    }
+    output.push_str("};\n\n");
+
+    let (layout, _) = qmk_layout(keyboard);
+    let positions = layout_rows(keyboard);
+    output.push_str("const uint16_t PROGMEM keymaps[][MATRIX_ROWS][MATRIX_COLS] = {\n");
+    for (index, layer) in layers.iter().enumerate() {
+        output.push_str(&format!("    /* {}\n", layer.name.replace("*/", "* /")));
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(format!("     * {}", line).trim_end());
+            output.push('\n');
+        }
+        output.push_str("     */\n");
+
+        output.push_str(&format!("    [{}] = {}(\n", identifiers[index], layout));
+        let rows: Vec<String> = layer_rows(&positions, layers, index, &identifiers, &mut warnings).iter()
+            .map(|row| format!("        {}", row.join(", ")))
+            .collect();
+        output.push_str(&rows.join(",\n"));
+        output.push_str("\n    ),\n");
+    }
+    output.push_str("};\n");
+
+    Conversion { output, warnings }
+}
+
+/// QMK Configurator `keymap.json`, as read by `qmk compile` and `qmk json2c`
+#[derive(Deserialize)]
+struct KeymapJson {
+    #[serde(default)]
+    keyboard: String,
+    #[serde(default)]
+    layout: String,
+    layers: Vec<Vec<String>>,
+}
+
+/// Write the keymap as a QMK Configurator `keymap.json`, one line of the layout macro per line
+pub fn export_keymap_json(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let mut warnings = Vec::new();
+    let quote = |value: &str| serde_json::Value::from(value).to_string();
+    let (layout, _) = qmk_layout(keyboard);
+    let positions = layout_rows(keyboard);
+
+    let layer_arrays: Vec<String> = (0..layers.len())
+        .map(|index| {
+            // keymap.json has no layer names, so layer keys use numbers
+            let rows: Vec<String> = layer_rows(&positions, layers, index, &[], &mut warnings).iter()
+                .map(|row| {
+                    let keycodes: Vec<String> = row.iter().map(|keycode| quote(keycode)).collect();
+                    format!("      {}", keycodes.join(", "))
+                })
+                .collect();
+            format!("    [\n{}\n    ]", rows.join(",\n"))
+        })
+        .collect();
+
+    let output = format!(
+        "{{\n  \"version\": 1,\n  \"notes\": {},\n  \"keyboard\": {},\n  \"keymap\": \"layout-rs\",\n  \"layout\": {},\n  \"layers\": [\n{}\n  ]\n}}\n",
+        quote(&format!("{} keymap exported by layout-rs", keyboard.name)),
+        quote(qmk_keyboard(keyboard)),
+        quote(layout),
+        layer_arrays.join(",\n"),
+    );
+    Conversion { output, warnings }
+}
+
+/// Read a QMK Configurator `keymap.json`. Keycodes that no [`KeyAction`] can represent are
+/// kept in [`Layer::unmapped`] so they are written back on export.
+pub fn import_keymap_json(keyboard: &KeyboardModel, json: &str) -> Result<Conversion<Vec<Layer>>, String> {
+    let file: KeymapJson = serde_json::from_str(json)
+        .map_err(|e| format!("Not a QMK keymap.json: {}", e))?;
+    if file.layers.is_empty() {
+        return Err("QMK keymap.json has no layers".to_string());
+    }
+
+    let mut warnings = Vec::new();
+    if !file.keyboard.is_empty() && file.keyboard != qmk_keyboard(keyboard) {
+        warnings.push(format!("File is for QMK keyboard '{}', loaded onto {}", file.keyboard, keyboard.name));
+    }
+    let (layout, _) = qmk_layout(keyboard);
+    if !file.layout.is_empty() && file.layout != layout {
+        warnings.push(format!("File uses layout {}, read in the key order of {}", file.layout, layout));
+    }
+
+    let positions = layout_positions(keyboard);
+    let layers = file.layers.iter().enumerate()
+        .map(|(index, keycodes)| {
+            let mut layer = Layer::new(layer_name(keyboard, index));
+            if keycodes.len() != positions.len() {
+                warnings.push(format!(
+                    "Layer {} has {} keycodes, but {} has {} keys", index, keycodes.len(), keyboard.name, positions.len()
+                ));
+            }
+            for (&position, keycode) in positions.iter().zip(keycodes) {
+                if !set_keycode(&mut layer, position, keycode, &|_| None) {
+                    warnings.push(format!(
+                        "Layer {} [{}, {}]: '{}' has no equivalent in the editor and is kept as is",
+                        index, position.0, position.1, keycode
+                    ));
+                }
+            }
+            layer
+        })
+        .collect();
+
+    Ok(Conversion { output: layers, warnings })
+}
+
+/// Read the `keymaps` array of a QMK `keymap.c`. Layers are placed by their designators
+/// (`[_LOWER] = LAYOUT_5x7(...)`), resolved through the file's `enum`s and `#define`s.
+/// Keycodes that cannot be represented are kept in [`Layer::unmapped`] and reported with
+/// their line number.
+pub fn import_keymap_c(keyboard: &KeyboardModel, source: &str) -> Result<Conversion<Vec<Layer>>, String> {
+    let code = strip_comments(source);
+    let line = |offset: usize| line_number(&code, offset);
+    let constants = layer_constants(&code);
+    let resolve = |name: &str| constants.get(name).copied();
+
+    let (start, end) = keymaps_body(&code)?;
+    let positions = layout_positions(keyboard);
+    let mut warnings = Vec::new();
+    let mut layers: Vec<Option<Layer>> = Vec::new();
+
+    for (offset, entry) in split_arguments(&code[start..end], start) {
+        let (designator, call) = match entry.strip_prefix('[') {
+            Some(rest) => {
+                let (designator, call) = rest.split_once(']')
+                    .ok_or_else(|| format!("Line {}: unclosed '[' in keymaps", line(offset)))?;
+                let call = call.trim_start().strip_prefix('=')
+                    .ok_or_else(|| format!("Line {}: expected '=' after [{}]", line(offset), designator))?;
+                (Some(designator.trim()), call.trim())
+            }
+            None => (None, entry),
+        };
+
+        let index = match designator {
+            Some(designator) => designator.parse().ok().or_else(|| resolve(designator))
+                .ok_or_else(|| format!("Line {}: unknown layer '{}'", line(offset), designator))?,
+            None => layers.len(),
+        };
+        let call_offset = offset + entry.len() - call.len();
+        let (function, arguments) = match call.split_once('(') {
+            Some((function, rest)) if rest.ends_with(')') => {
+                let arguments_offset = call_offset + function.len() + 1;
+                (function.trim(), split_arguments(&rest[..rest.len() - 1], arguments_offset))
+            }
+            _ => return Err(format!("Line {}: expected a LAYOUT(...) call", line(call_offset))),
+        };
+
+        let name = match designator {
+            Some(designator) if designator.parse::<usize>().is_err() => identifier_layer_name(designator),
+            _ => layer_name(keyboard, index),
+        };
+        let mut layer = Layer::new(name);
+        if arguments.len() != positions.len() {
+            warnings.push(format!(
+                "Line {}: {}(...) has {} keycodes, but {} has {} keys",
+                line(call_offset), function, arguments.len(), keyboard.name, positions.len()
+            ));
+        }
+        for (&position, (offset, keycode)) in positions.iter().zip(arguments) {
+            if !set_keycode(&mut layer, position, keycode, &resolve) {
+                warnings.push(format!("Line {}: '{}' has no equivalent in the editor and is kept as is", line(offset), keycode));
+            }
+        }
+
+        if layers.len() <= index {
+            layers.resize(index + 1, None);
+        }
+        if layers[index].replace(layer).is_some() {
+            warnings.push(format!("Line {}: layer {} is defined twice, the last definition is used", line(offset), index));
+        }
+    }
+
+    if layers.is_empty() {
+        return Err("The keymaps array has no layers".to_string());
+    }
+    let layers = layers.into_iter().enumerate()
+        .map(|(index, layer)| layer.unwrap_or_else(|| Layer::new(layer_name(keyboard, index))))
+        .collect();
+    Ok(Conversion { output: layers, warnings })
+}
+
+/// Set a key from a QMK keycode. Keycodes no [`KeyAction`] can represent are kept in
+/// [`Layer::unmapped`] instead, and `false` is returned.
+fn set_keycode(layer: &mut Layer, position: (usize, usize), keycode: &str, layers: &dyn Fn(&str) -> Option<usize>) -> bool {
+    match parse_keycode(keycode, layers) {
+        Some(action) => {
+            layer.keys.insert(position, action);
+            true
+        }
+        None => {
+            layer.unmapped.insert(position, UnmappedKeycode { format: FORMAT.to_string(), keycode: keycode.to_string() });
+            false
+        }
+    }
+}
+
+/// Layer numbers of the identifiers declared in `enum`s and `#define`s
+fn layer_constants(code: &str) -> HashMap<String, usize> {
+    let mut constants = define_constants(code);
+
+    let mut rest = code;
+    while let Some(start) = find_word(rest, "enum") {
+        rest = &rest[start + "enum".len()..];
+        let Some(open) = rest.find(['{', ';']).filter(|&open| rest[open..].starts_with('{')) else {
+            continue;
+        };
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let mut value = 0;
+        for item in rest[open + 1..open + close].split(',') {
+            let (name, initializer) = match item.split_once('=') {
+                Some((name, initializer)) => (name.trim(), Some(initializer.trim())),
+                None => (item.trim(), None),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            if let Some(initializer) = initializer {
+                // Values such as `SAFE_RANGE` belong to custom keycodes, not layers
+                match initializer.parse() {
+                    Ok(initializer) => value = initializer,
+                    Err(_) => break,
+                }
+            }
+            constants.insert(name.to_string(), value);
+            value += 1;
+        }
+        rest = &rest[open + close..];
+    }
+
+    constants
+}
+
+/// Byte range of the contents of the `keymaps` array initializer, between its braces
+fn keymaps_body(code: &str) -> Result<(usize, usize), String> {
+    const MISSING: &str = "No keymaps[][MATRIX_ROWS][MATRIX_COLS] array found";
+
+    let mut search = 0;
+    let start = loop {
+        let found = search + find_word(&code[search..], "keymaps").ok_or(MISSING)?;
+        let after = found + "keymaps".len();
+        let declaration = &code[after..];
+        if let Some(equals) = declaration.find(['=', ';']).filter(|&equals| declaration[equals..].starts_with('=')) {
+            if declaration[..equals].trim_start().starts_with('[') && declaration[equals + 1..].trim_start().starts_with('{') {
+                break after + equals + 1 + declaration[equals + 1..].find('{').unwrap_or_default() + 1;
+            }
+        }
+        search = after;
+    };
+
+    let mut depth = 0;
+    for (index, c) in code[start..].char_indices() {
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' if depth == 0 => return Ok((start, start + index)),
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    Err("The keymaps array is not closed".to_string())
+}
+
+/// QMK keycodes of one layer, grouped like the given key positions
+fn layer_rows(
+    rows: &[Vec<(usize, usize)>],
+    layers: &[Layer],
+    index: usize,
+    identifiers: &[String],
+    warnings: &mut Vec<String>,
+) -> Vec<Vec<String>> {
+    let layer = &layers[index];
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|&position| {
+                    let (keycode, warning) = match layer.unmapped.get(&position) {
+                        Some(unmapped) if unmapped.format == FORMAT => (unmapped.keycode.clone(), None),
+                        Some(unmapped) => ("KC_NO".to_string(), Some(format!(
+                            "{} keycode '{}' has no QMK equivalent, exported as KC_NO", unmapped.format, unmapped.keycode
+                        ))),
+                        None => action_keycode(action_at(layers, index, position), identifiers),
+                    };
+                    if let Some(warning) = warning {
+                        warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+                    }
+                    keycode
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// QMK keycode expression for an action, and a warning if it had to be approximated
+fn action_keycode(action: KeyAction, layers: &[String]) -> (String, Option<String>) {
+    let layer_name = |layer: usize| layers.get(layer).cloned().unwrap_or_else(|| layer.to_string());
+
+    match action {
+        KeyAction::Key(usage) => usage_keycode(usage),
+        KeyAction::Consumer(usage) => match usage.alias(NamingScheme::Qmk) {
+            Some(name) => (name.to_string(), None),
+            None => ("KC_NO".to_string(), Some(format!("QMK has no keycode for {:?}, exported as KC_NO", usage))),
+        },
+        KeyAction::Mouse(mouse) => {
+            let &(_, name, _) = QMK_MOUSE_KEYCODES.iter()
+                .find(|&&(other, _, _)| other == mouse)
+                .expect("every mouse action has a QMK keycode");
+            (name.to_string(), None)
+        }
+        KeyAction::Modified { modifiers, key } => {
+            let (mut keycode, warning) = usage_keycode(key);
+            for &(modifier, wrapper, _) in QMK_MODIFIERS.iter().rev() {
+                if modifiers.contains(modifier) {
+                    keycode = format!("{}({})", wrapper, keycode);
+                }
+            }
+            (keycode, warning)
+        }
+        KeyAction::ModTap { modifiers, tap } => {
+            let (keycode, mut warning) = usage_keycode(tap);
+            let masks: Vec<&str> = QMK_MODIFIERS.iter()
+                .filter(|&&(modifier, _, _)| modifiers.contains(modifier))
+                .map(|&(_, _, mask)| mask)
+                .collect();
+            // The first four entries are the left-hand modifiers
+            let (left, right) = QMK_MODIFIERS.split_at(4);
+            let uses = |side: &[(Modifiers, &str, &str)]| side.iter().any(|&(modifier, _, _)| modifiers.contains(modifier));
+            if uses(left) && uses(right) {
+                warning.get_or_insert_with(|| format!("QMK mod-taps cannot mix left and right modifiers ({}); all act as right modifiers", modifiers));
+            }
+            (format!("MT({}, {})", masks.join(" | "), keycode), warning)
+        }
+        KeyAction::LayerTap { layer, tap } => {
+            let (keycode, mut warning) = usage_keycode(tap);
+            if layer > MAX_LAYER_TAP_LAYER {
+                warning.get_or_insert_with(|| format!("QMK layer-taps only reach layers 0-{}", MAX_LAYER_TAP_LAYER));
+            }
+            (format!("LT({}, {})", layer_name(layer), keycode), warning)
+        }
+        KeyAction::MomentaryLayer(layer) => (format!("MO({})", layer_name(layer)), None),
+        KeyAction::ToggleLayer(layer) => (format!("TG({})", layer_name(layer)), None),
+        KeyAction::OneShotLayer(layer) => (format!("OSL({})", layer_name(layer)), None),
+        KeyAction::Transparent => ("KC_TRNS".to_string(), None),
+        KeyAction::NoOp => ("KC_NO".to_string(), None),
+    }
+}
+
+fn usage_keycode(usage: KeyboardUsage) -> (String, Option<String>) {
+    match keycode_name(usage) {
+        Some(name) => (name.to_string(), None),
+        None => ("KC_NO".to_string(), Some(format!("QMK has no keycode for {:?}, exported as KC_NO", usage))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::keyboards;
+
+    #[test]
+    fn test_keycode_names() {
+        assert_eq!(keycode_name(KeyboardUsage::KeyboardEscape), Some("KC_ESC"));
+        assert_eq!(keycode_name(KeyboardUsage::KeyboardRightGUI), Some("KC_RGUI"));
+        assert_eq!(keycode_name(KeyboardUsage::KeypadHexadecimal), None);
+
+        // Every keyboard usage QMK supports has a name: 0x04-0xA4 and the modifiers
+        for code in (0x04..=0xA4).chain(0xE0..=0xE7) {
+            assert!(keycode_name(KeyboardUsage::from(code)).is_some(), "no QMK name for {:#04x}", code);
+        }
+    }
+
+    #[test]
+    fn test_action_keycodes() {
+        let layers = ["_BASE".to_string(), "_LOWER".to_string()];
+        let keycode = |action: KeyAction| action_keycode(action, &layers);
+        use KeyboardUsage::*;
+
+        assert_eq!(keycode(KeyAction::Key(KeyboardAa)), ("KC_A".to_string(), None));
+        assert_eq!(keycode(KeyAction::Consumer(ConsumerUsage::PlayPause)), ("KC_MPLY".to_string(), None));
+        assert_eq!(keycode(KeyAction::Mouse(MouseAction::WheelRight)), ("MS_WHLR".to_string(), None));
+        assert_eq!(
+            keycode(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }).0,
+            "LCTL(LSFT(KC_Z))"
+        );
+        assert_eq!(
+            keycode(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_GUI, tap: KeyboardEscape }).0,
+            "MT(MOD_LCTL | MOD_LGUI, KC_ESC)"
+        );
+        assert_eq!(keycode(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }).0, "LT(_LOWER, KC_SPC)");
+        assert_eq!(keycode(KeyAction::MomentaryLayer(1)).0, "MO(_LOWER)");
+        assert_eq!(keycode(KeyAction::ToggleLayer(5)).0, "TG(5)");
+        assert_eq!(keycode(KeyAction::OneShotLayer(0)).0, "OSL(_BASE)");
+        assert_eq!(keycode(KeyAction::Transparent).0, "KC_TRNS");
+        assert_eq!(keycode(KeyAction::NoOp).0, "KC_NO");
+
+        let (exported, warning) = keycode(KeyAction::Key(KeypadHexadecimal));
+        assert_eq!(exported, "KC_NO");
+        assert!(warning.is_some());
+        assert!(keycode(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::RIGHT_ALT, tap: KeyboardAa }).1.is_some());
+        assert!(keycode(KeyAction::LayerTap { layer: 16, tap: KeyboardAa }).1.is_some());
+    }
+
+    #[test]
+    fn test_export_keymap_c() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardUsage::KeyboardF1));
+        layers[1].keys.insert((0, 2), KeyAction::Key(KeyboardUsage::Keypad00));
+
+        let export = export_keymap_c(test_keyboard(), &layers);
+        let c = &export.output;
+        assert!(c.contains("#include QMK_KEYBOARD_H"));
+        assert!(c.contains("enum layers {\n    _BASE,\n    _LOWER,\n    _RAISE,\n};"));
+        // The whole left half, thumb cluster included, comes before the right half
+        assert!(c.contains(
+            "    [_BASE] = LAYOUT_5x7(\n        KC_ESC, KC_1, KC_2, KC_3, KC_4, KC_5, KC_6,\n        KC_TAB, KC_Q, KC_W,"
+        ));
+        assert!(c.contains("        KC_LCTL, KC_LALT, KC_LGUI, MO(_LOWER),\n        KC_HOME, KC_END,\n"));
+        assert!(c.contains("        KC_SPC, KC_BSPC,\n        KC_7, KC_8, KC_9, KC_0, KC_MINS, KC_EQL, KC_BSPC,\n"));
+        assert!(c.contains("        KC_DEL, KC_ENT\n    ),\n"));
+        assert!(c.contains("    [_LOWER] = LAYOUT_5x7(\n        KC_TRNS, KC_F1, KC_NO, KC_TRNS,"));
+        assert!(c.contains("     * | Esc   | 1     | 2     |"));
+        assert_eq!(c.matches("LAYOUT_5x7(").count(), 3);
+        assert!(c.trim_end().ends_with("    ),\n};"));
+
+        // One keycode per key on every layer
+        let base = &c[c.find("[_BASE] = LAYOUT_5x7(").unwrap()..c.find("/* Lower").unwrap()];
+        assert_eq!(base.matches(',').count(), test_keyboard().geometry.keys().len());
+
+        assert_eq!(export.warnings, ["Layer 'Lower' [0, 2]: QMK has no keycode for Keypad00, exported as KC_NO"]);
+    }
+
+    #[test]
+    fn test_layout_positions() {
+        // LAYOUT_5x7 takes the 37 keys of the left half first
+        let positions = layout_positions(test_keyboard());
+        assert_eq!(positions.len(), test_keyboard().geometry.keys().len());
+        assert_eq!(positions[7], (1, 0));
+        assert_eq!(positions[31..37], [(5, 5), (5, 6), (6, 5), (6, 6), (7, 5), (7, 6)]);
+        assert_eq!(positions[37], (0, 7));
+
+        // LAYOUT_split_3x6_3 goes row by row across both halves
+        let corne = keyboards::find_keyboard("corne").unwrap();
+        assert_eq!(qmk_layout(corne).0, "LAYOUT_split_3x6_3");
+        assert_eq!(layout_positions(corne), matrix_positions(corne));
+    }
+
+    #[test]
+    fn test_parse_keycode() {
+        let layers = |name: &str| ["_BASE", "_LOWER"].iter().position(|&layer| layer == name);
+        let parse = |expression: &str| parse_keycode(expression, &layers);
+        use KeyboardUsage::*;
+
+        assert_eq!(parse("KC_ESC"), Some(KeyAction::Key(KeyboardEscape)));
+        assert_eq!(parse("KC_ESCAPE"), Some(KeyAction::Key(KeyboardEscape)));
+        assert_eq!(parse("KC_AUDIO_VOL_UP"), Some(KeyAction::Consumer(ConsumerUsage::VolumeIncrement)));
+        assert_eq!(parse("KC_KB_VOLUME_UP"), Some(KeyAction::Key(KeyboardVolumeUp)));
+        assert_eq!(parse("MS_BTN2"), Some(KeyAction::Mouse(MouseAction::Button2)));
+        assert_eq!(parse("KC_MS_R"), Some(KeyAction::Mouse(MouseAction::MoveRight)));
+        assert_eq!(parse("KC_MS_ACCEL1"), Some(KeyAction::Mouse(MouseAction::Accel1)));
+        assert_eq!(parse("_______"), Some(KeyAction::Transparent));
+        assert_eq!(parse("XXXXXXX"), Some(KeyAction::NoOp));
+        assert_eq!(parse("KC_EXLM"), Some(KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard1Exclamation }));
+        assert_eq!(
+            parse("LCTL(LSFT(KC_Z))"),
+            Some(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz })
+        );
+        assert_eq!(parse("C(KC_C)"), Some(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeyboardCc }));
+        assert_eq!(
+            parse("MT(MOD_LCTL | MOD_LSFT, KC_A)"),
+            Some(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa })
+        );
+        assert_eq!(parse("LGUI_T(KC_A)"), Some(KeyAction::ModTap { modifiers: Modifiers::LEFT_GUI, tap: KeyboardAa }));
+        assert_eq!(parse("SFT_T(KC_F)"), Some(KeyAction::ModTap { modifiers: Modifiers::LEFT_SHIFT, tap: KeyboardFf }));
+        assert_eq!(parse("LT(_LOWER, KC_SPC)"), Some(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }));
+        assert_eq!(parse("MO(2)"), Some(KeyAction::MomentaryLayer(2)));
+        assert_eq!(parse(" TG(_BASE) "), Some(KeyAction::ToggleLayer(0)));
+        assert_eq!(parse("OSL(1)"), Some(KeyAction::OneShotLayer(1)));
+
+        assert_eq!(parse("RGB_TOG"), None);
+        assert_eq!(parse("MO(_UNKNOWN)"), None);
+        assert_eq!(parse("LCTL(MO(1))"), None);
+        assert_eq!(parse("LT(1, LCTL(KC_A))"), None);
+    }
+
+    #[test]
+    fn test_keymap_json_round_trip() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].keys.insert((0, 0), KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardUsage::KeyboardEscape });
+        layers[1].keys.insert((0, 1), KeyAction::Modified { modifiers: Modifiers::RIGHT_ALT, key: KeyboardUsage::KeyboardEe });
+        layers[2].unmapped.insert((0, 2), UnmappedKeycode { format: FORMAT.to_string(), keycode: "RGB_TOG".to_string() });
+
+        let export = export_keymap_json(test_keyboard(), &layers);
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        let json: serde_json::Value = serde_json::from_str(&export.output).unwrap();
+        assert_eq!(json["keyboard"], "handwired/dactyl_manuform/5x7");
+        assert_eq!(json["layout"], "LAYOUT_5x7");
+        assert_eq!(json["layers"][0][0], "MT(MOD_LCTL, KC_ESC)");
+        // The right half follows the 37 keys of the left half
+        assert_eq!(json["layers"][0][36], "KC_BSPC");
+        assert_eq!(json["layers"][0][37], "KC_7");
+        let lower_key = layout_positions(test_keyboard()).iter().position(|&position| position == (4, 3)).unwrap();
+        assert_eq!(json["layers"][0][lower_key], "MO(1)");
+        assert_eq!(json["layers"][1][1], "RALT(KC_E)");
+        assert_eq!(json["layers"][2][2], "RGB_TOG");
+        assert_eq!(json["layers"][2].as_array().unwrap().len(), test_keyboard().geometry.keys().len());
+
+        let import = import_keymap_json(test_keyboard(), &export.output).unwrap();
+        assert_eq!(import.warnings, ["Layer 2 [0, 2]: 'RGB_TOG' has no equivalent in the editor and is kept as is"]);
+        let imported = import.output;
+        assert_eq!(imported.len(), 3);
+        assert_eq!(imported[0].name, "Base");
+        assert_eq!(imported[0].keys, layers[0].keys.iter()
+            .map(|(&position, &action)| (position, action))
+            .chain(matrix_positions(test_keyboard()).into_iter()
+                .filter(|position| !layers[0].keys.contains_key(position))
+                .map(|position| (position, KeyAction::NoOp)))
+            .collect());
+        assert_eq!(imported[1].keys.get(&(0, 1)), layers[1].keys.get(&(0, 1)));
+        assert_eq!(imported[2].unmapped, layers[2].unmapped);
+
+        // The kept keycode is written back unchanged
+        assert_eq!(export_keymap_json(test_keyboard(), &imported).output, export.output);
+    }
+
+    #[test]
+    fn test_import_keymap_json_mismatches() {
+        let json = r#"{"keyboard": "crkbd/rev1", "layout": "LAYOUT_split_3x6_3", "layers": [["KC_A", "KC_B"]]}"#;
+        let import = import_keymap_json(test_keyboard(), json).unwrap();
+        assert_eq!(import.warnings, [
+            "File is for QMK keyboard 'crkbd/rev1', loaded onto Dactyl Manuform 5x7",
+            "File uses layout LAYOUT_split_3x6_3, read in the key order of LAYOUT_5x7",
+            "Layer 0 has 2 keycodes, but Dactyl Manuform 5x7 has 74 keys",
+        ]);
+        assert_eq!(import.output[0].keys.get(&(0, 1)), Some(&KeyAction::Key(KeyboardUsage::KeyboardBb)));
+
+        assert!(import_keymap_json(test_keyboard(), r#"{"layers": []}"#).is_err());
+        assert!(import_keymap_json(test_keyboard(), "{").is_err());
+    }
+
+    #[test]
+    fn test_import_keymap_c() {
+        let source = r#"#include QMK_KEYBOARD_H
+#define _FN 3 // function layer
+
+enum layers {
+    _BASE,
+    _LOWER, /* symbols */
+};
+
+const uint16_t PROGMEM keymaps[][MATRIX_ROWS][MATRIX_COLS] = {
+    /* Base: [_LOWER] = LAYOUT(KC_NO), */
+    [_BASE] = LAYOUT(
+        LT(_LOWER, KC_ESC), MT(MOD_LCTL | MOD_LSFT, KC_A),   // "home row"
+        LSFT(KC_1), RGB_TOG
+    ),
+    [_FN] = LAYOUT(_______, MO(_BASE))
+};
+"#;
+        let import = import_keymap_c(test_keyboard(), source).unwrap();
+        assert_eq!(import.warnings, [
+            "Line 11: LAYOUT(...) has 4 keycodes, but Dactyl Manuform 5x7 has 74 keys",
+            "Line 13: 'RGB_TOG' has no equivalent in the editor and is kept as is",
+            "Line 15: LAYOUT(...) has 2 keycodes, but Dactyl Manuform 5x7 has 74 keys",
+        ]);
+
+        let layers = import.output;
+        let names: Vec<&str> = layers.iter().map(|layer| layer.name.as_str()).collect();
+        assert_eq!(names, ["Base", "Lower", "Raise", "Fn"]);
+        use KeyboardUsage::*;
+        assert_eq!(layers[0].keys[&(0, 0)], KeyAction::LayerTap { layer: 1, tap: KeyboardEscape });
+        assert_eq!(layers[0].keys[&(0, 1)], KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa });
+        assert_eq!(layers[0].keys[&(0, 2)], KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard1Exclamation });
+        assert_eq!(layers[0].unmapped[&(0, 3)].keycode, "RGB_TOG");
+        assert!(layers[1].keys.is_empty());
+        assert_eq!(layers[3].keys[&(0, 0)], KeyAction::Transparent);
+        assert_eq!(layers[3].keys[&(0, 1)], KeyAction::MomentaryLayer(0));
+    }
+
+    #[test]
+    fn test_keymap_c_round_trip() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].keys.insert((0, 0), KeyAction::ModTap { modifiers: Modifiers::LEFT_ALT, tap: KeyboardUsage::KeyboardEscape });
+        layers[1].name = "Nav Media".to_string();
+        layers[2].unmapped.insert((0, 2), UnmappedKeycode { format: FORMAT.to_string(), keycode: "RGB_TOG".to_string() });
+        let export = export_keymap_c(test_keyboard(), &layers);
+
+        let import = import_keymap_c(test_keyboard(), &export.output).unwrap();
+        assert_eq!(import.warnings.len(), 1, "{:?}", import.warnings);
+        assert!(import.warnings[0].ends_with("'RGB_TOG' has no equivalent in the editor and is kept as is"));
+        assert_eq!(import.output[1].name, "Nav Media");
+        assert_eq!(import.output[0].keys.get(&(0, 0)), layers[0].keys.get(&(0, 0)));
+        assert_eq!(export_keymap_c(test_keyboard(), &import.output).output, export.output);
+    }
+
+    #[test]
+    fn test_import_keymap_c_errors() {
+        assert_eq!(import_keymap_c(test_keyboard(), "#include QMK_KEYBOARD_H").unwrap_err(), "No keymaps[][MATRIX_ROWS][MATRIX_COLS] array found");
+        let unclosed = "const uint16_t keymaps[][MATRIX_ROWS][MATRIX_COLS] = {\n    [0] = LAYOUT(KC_A),\n";
+        assert_eq!(import_keymap_c(test_keyboard(), unclosed).unwrap_err(), "The keymaps array is not closed");
+        let unknown = "const uint16_t keymaps[][MATRIX_ROWS][MATRIX_COLS] = {\n    [_NAV] = LAYOUT(KC_A)\n};";
+        assert_eq!(import_keymap_c(test_keyboard(), unknown).unwrap_err(), "Line 2: unknown layer '_NAV'");
+        let empty = "const uint16_t keymaps[][MATRIX_ROWS][MATRIX_COLS] = {};";
+        assert!(import_keymap_c(test_keyboard(), empty).is_err());
+    }
+
+    #[test]
+    fn test_unmapped_keycodes_from_other_formats() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].unmapped.insert((0, 0), UnmappedKeycode { format: "zmk".to_string(), keycode: "&bt BT_CLR".to_string() });
+        let export = export_keymap_c(test_keyboard(), &layers);
+        assert!(export.output.contains("LAYOUT_5x7(\n        KC_NO, KC_1,"));
+        assert_eq!(export.warnings, ["Layer 'Base' [0, 0]: zmk keycode '&bt BT_CLR' has no QMK equivalent, exported as KC_NO"]);
+    }
+}
@@ -0,0 +1,404 @@
+//! # RMK
+//!
+//! `keyboard.toml` export. RMK reads its keymap from the `[layout]` section: the matrix size,
+//! the number of layers and a `keymap` with one list of matrix rows per layer, e.g.
+//! `["Escape", "Kc1", "MO(1)"]`. Matrix positions without a key are filled with `No`.
+//!
+//! Key names are those of RMK's `KeyCode` enum, modifier-wrapped keys are written as
+//! `WM(C, LCtrl)` and mod-taps as `MT(Escape, LCtrl)`.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::Layer;
+use super::{action_at, ascii_art, matrix_positions, Conversion};
+
+/// Modifier bits with their RMK modifier name
+const RMK_MODIFIERS: [(Modifiers, &str); 8] = [
+    (Modifiers::LEFT_CTRL, "LCtrl"),
+    (Modifiers::LEFT_SHIFT, "LShift"),
+    (Modifiers::LEFT_ALT, "LAlt"),
+    (Modifiers::LEFT_GUI, "LGui"),
+    (Modifiers::RIGHT_CTRL, "RCtrl"),
+    (Modifiers::RIGHT_SHIFT, "RShift"),
+    (Modifiers::RIGHT_ALT, "RAlt"),
+    (Modifiers::RIGHT_GUI, "RGui"),
+];
+
+/// RMK key names, the variants of its `KeyCode` enum
+static RMK_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "A"),
+        (KeyboardBb, "B"),
+        (KeyboardCc, "C"),
+        (KeyboardDd, "D"),
+        (KeyboardEe, "E"),
+        (KeyboardFf, "F"),
+        (KeyboardGg, "G"),
+        (KeyboardHh, "H"),
+        (KeyboardIi, "I"),
+        (KeyboardJj, "J"),
+        (KeyboardKk, "K"),
+        (KeyboardLl, "L"),
+        (KeyboardMm, "M"),
+        (KeyboardNn, "N"),
+        (KeyboardOo, "O"),
+        (KeyboardPp, "P"),
+        (KeyboardQq, "Q"),
+        (KeyboardRr, "R"),
+        (KeyboardSs, "S"),
+        (KeyboardTt, "T"),
+        (KeyboardUu, "U"),
+        (KeyboardVv, "V"),
+        (KeyboardWw, "W"),
+        (KeyboardXx, "X"),
+        (KeyboardYy, "Y"),
+        (KeyboardZz, "Z"),
+        (Keyboard1Exclamation, "Kc1"),
+        (Keyboard2At, "Kc2"),
+        (Keyboard3Hash, "Kc3"),
+        (Keyboard4Dollar, "Kc4"),
+        (Keyboard5Percent, "Kc5"),
+        (Keyboard6Caret, "Kc6"),
+        (Keyboard7Ampersand, "Kc7"),
+        (Keyboard8Asterisk, "Kc8"),
+        (Keyboard9OpenParens, "Kc9"),
+        (Keyboard0CloseParens, "Kc0"),
+        (KeyboardEnter, "Enter"),
+        (KeyboardEscape, "Escape"),
+        (KeyboardBackspace, "Backspace"),
+        (KeyboardTab, "Tab"),
+        (KeyboardSpacebar, "Space"),
+        (KeyboardDashUnderscore, "Minus"),
+        (KeyboardEqualPlus, "Equal"),
+        (KeyboardOpenBracketBrace, "LeftBracket"),
+        (KeyboardCloseBracketBrace, "RightBracket"),
+        (KeyboardBackslashBar, "Backslash"),
+        (KeyboardNonUSHash, "NonusHash"),
+        (KeyboardSemiColon, "Semicolon"),
+        (KeyboardSingleDoubleQuote, "Quote"),
+        (KeyboardBacktickTilde, "Grave"),
+        (KeyboardCommaLess, "Comma"),
+        (KeyboardPeriodGreater, "Dot"),
+        (KeyboardSlashQuestion, "Slash"),
+        (KeyboardCapsLock, "CapsLock"),
+        (KeyboardF1, "F1"),
+        (KeyboardF2, "F2"),
+        (KeyboardF3, "F3"),
+        (KeyboardF4, "F4"),
+        (KeyboardF5, "F5"),
+        (KeyboardF6, "F6"),
+        (KeyboardF7, "F7"),
+        (KeyboardF8, "F8"),
+        (KeyboardF9, "F9"),
+        (KeyboardF10, "F10"),
+        (KeyboardF11, "F11"),
+        (KeyboardF12, "F12"),
+        (KeyboardPrintScreen, "PrintScreen"),
+        (KeyboardScrollLock, "ScrollLock"),
+        (KeyboardPause, "Pause"),
+        (KeyboardInsert, "Insert"),
+        (KeyboardHome, "Home"),
+        (KeyboardPageUp, "PageUp"),
+        (KeyboardDelete, "Delete"),
+        (KeyboardEnd, "End"),
+        (KeyboardPageDown, "PageDown"),
+        (KeyboardRightArrow, "Right"),
+        (KeyboardLeftArrow, "Left"),
+        (KeyboardDownArrow, "Down"),
+        (KeyboardUpArrow, "Up"),
+        (KeypadNumLock, "NumLock"),
+        (KeypadDivide, "KpSlash"),
+        (KeypadMultiply, "KpAsterisk"),
+        (KeypadMinus, "KpMinus"),
+        (KeypadPlus, "KpPlus"),
+        (KeypadEnter, "KpEnter"),
+        (Keypad1End, "Kp1"),
+        (Keypad2DownArrow, "Kp2"),
+        (Keypad3PageDown, "Kp3"),
+        (Keypad4LeftArrow, "Kp4"),
+        (Keypad5, "Kp5"),
+        (Keypad6RightArrow, "Kp6"),
+        (Keypad7Home, "Kp7"),
+        (Keypad8UpArrow, "Kp8"),
+        (Keypad9PageUp, "Kp9"),
+        (Keypad0Insert, "Kp0"),
+        (KeypadPeriodDelete, "KpDot"),
+        (KeyboardNonUSSlash, "NonusBackslash"),
+        (KeyboardApplication, "Application"),
+        (KeyboardPower, "KbPower"),
+        (KeypadEqual, "KpEqual"),
+        (KeyboardF13, "F13"),
+        (KeyboardF14, "F14"),
+        (KeyboardF15, "F15"),
+        (KeyboardF16, "F16"),
+        (KeyboardF17, "F17"),
+        (KeyboardF18, "F18"),
+        (KeyboardF19, "F19"),
+        (KeyboardF20, "F20"),
+        (KeyboardF21, "F21"),
+        (KeyboardF22, "F22"),
+        (KeyboardF23, "F23"),
+        (KeyboardF24, "F24"),
+        (KeyboardExecute, "Execute"),
+        (KeyboardHelp, "Help"),
+        (KeyboardMenu, "Menu"),
+        (KeyboardSelect, "Select"),
+        (KeyboardStop, "Stop"),
+        (KeyboardAgain, "Again"),
+        (KeyboardUndo, "Undo"),
+        (KeyboardCut, "Cut"),
+        (KeyboardCopy, "Copy"),
+        (KeyboardPaste, "Paste"),
+        (KeyboardFind, "Find"),
+        (KeyboardMute, "KbMute"),
+        (KeyboardVolumeUp, "KbVolumeUp"),
+        (KeyboardVolumeDown, "KbVolumeDown"),
+        (KeyboardLockingCapsLock, "LockingCapsLock"),
+        (KeyboardLockingNumLock, "LockingNumLock"),
+        (KeyboardLockingScrollLock, "LockingScrollLock"),
+        (KeypadComma, "KpComma"),
+        (KeypadEqualSign, "KpEqualAs400"),
+        (KeyboardInternational1, "International1"),
+        (KeyboardInternational2, "International2"),
+        (KeyboardInternational3, "International3"),
+        (KeyboardInternational4, "International4"),
+        (KeyboardInternational5, "International5"),
+        (KeyboardInternational6, "International6"),
+        (KeyboardInternational7, "International7"),
+        (KeyboardInternational8, "International8"),
+        (KeyboardInternational9, "International9"),
+        (KeyboardLANG1, "Language1"),
+        (KeyboardLANG2, "Language2"),
+        (KeyboardLANG3, "Language3"),
+        (KeyboardLANG4, "Language4"),
+        (KeyboardLANG5, "Language5"),
+        (KeyboardLANG6, "Language6"),
+        (KeyboardLANG7, "Language7"),
+        (KeyboardLANG8, "Language8"),
+        (KeyboardLANG9, "Language9"),
+        (KeyboardAlternateErase, "AltErase"),
+        (KeyboardSysReqAttention, "SysReq"),
+        (KeyboardCancel, "Cancel"),
+        (KeyboardClear, "Clear"),
+        (KeyboardPrior, "Prior"),
+        (KeyboardReturn, "Return"),
+        (KeyboardSeparator, "Separator"),
+        (KeyboardOut, "Out"),
+        (KeyboardOper, "Oper"),
+        (KeyboardClearAgain, "ClearAgain"),
+        (KeyboardCrSelProps, "CrSel"),
+        (KeyboardExSel, "ExSel"),
+        (KeyboardLeftControl, "LCtrl"),
+        (KeyboardLeftShift, "LShift"),
+        (KeyboardLeftAlt, "LAlt"),
+        (KeyboardLeftGUI, "LGui"),
+        (KeyboardRightControl, "RCtrl"),
+        (KeyboardRightShift, "RShift"),
+        (KeyboardRightAlt, "RAlt"),
+        (KeyboardRightGUI, "RGui"),
+    ])
+});
+
+/// RMK names for consumer controls, which RMK sends as media keys
+const RMK_CONSUMER_KEYS: [(ConsumerUsage, &str); 24] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, "BrightnessUp"),
+    (ConsumerUsage::DisplayBrightnessDecrement, "BrightnessDown"),
+    (ConsumerUsage::FastForward, "MediaFastForward"),
+    (ConsumerUsage::Rewind, "MediaRewind"),
+    (ConsumerUsage::ScanNextTrack, "MediaNextTrack"),
+    (ConsumerUsage::ScanPreviousTrack, "MediaPrevTrack"),
+    (ConsumerUsage::Stop, "MediaStop"),
+    (ConsumerUsage::Eject, "MediaEject"),
+    (ConsumerUsage::PlayPause, "MediaPlayPause"),
+    (ConsumerUsage::Mute, "AudioMute"),
+    (ConsumerUsage::VolumeIncrement, "AudioVolUp"),
+    (ConsumerUsage::VolumeDecrement, "AudioVolDown"),
+    (ConsumerUsage::ALConsumerControlConfiguration, "MediaSelect"),
+    (ConsumerUsage::ALEmailReader, "Mail"),
+    (ConsumerUsage::ALCalculator, "Calculator"),
+    (ConsumerUsage::ALLocalMachineBrowser, "MyComputer"),
+    (ConsumerUsage::ALControlPanel, "ControlPanel"),
+    (ConsumerUsage::ACSearch, "WwwSearch"),
+    (ConsumerUsage::ACHome, "WwwHome"),
+    (ConsumerUsage::ACBack, "WwwBack"),
+    (ConsumerUsage::ACForward, "WwwForward"),
+    (ConsumerUsage::ACStop, "WwwStop"),
+    (ConsumerUsage::ACRefresh, "WwwRefresh"),
+    (ConsumerUsage::ACBookmarks, "WwwFavorites"),
+];
+
+/// RMK names for mouse keys
+const RMK_MOUSE_KEYS: [(MouseAction, &str); 16] = [
+    (MouseAction::Button1, "MouseBtn1"),
+    (MouseAction::Button2, "MouseBtn2"),
+    (MouseAction::Button3, "MouseBtn3"),
+    (MouseAction::Button4, "MouseBtn4"),
+    (MouseAction::Button5, "MouseBtn5"),
+    (MouseAction::MoveUp, "MouseUp"),
+    (MouseAction::MoveDown, "MouseDown"),
+    (MouseAction::MoveLeft, "MouseLeft"),
+    (MouseAction::MoveRight, "MouseRight"),
+    (MouseAction::WheelUp, "MouseWheelUp"),
+    (MouseAction::WheelDown, "MouseWheelDown"),
+    (MouseAction::WheelLeft, "MouseWheelLeft"),
+    (MouseAction::WheelRight, "MouseWheelRight"),
+    (MouseAction::Accel0, "MouseAccel0"),
+    (MouseAction::Accel1, "MouseAccel1"),
+    (MouseAction::Accel2, "MouseAccel2"),
+];
+
+/// The RMK key name of a usage, e.g. `Escape`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    RMK_KEYS.get(&usage).copied()
+}
+
+/// Write the keymap as the `[layout]` section of an RMK `keyboard.toml`
+pub fn export_keyboard_toml(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let positions = matrix_positions(keyboard);
+    let rows = positions.iter().map(|&(row, _)| row).max().map_or(0, |row| row + 1);
+    let cols = positions.iter().map(|&(_, col)| col).max().map_or(0, |col| col + 1);
+    let mut warnings = Vec::new();
+
+    let layer_rows: Vec<Vec<Vec<String>>> = layers.iter().enumerate()
+        .map(|(index, layer)| {
+            (0..rows)
+                .map(|row| {
+                    (0..cols)
+                        .map(|col| {
+                            let position = (row, col);
+                            if !keyboard.geometry.contains(position) {
+                                return quote("No");
+                            }
+                            let (action, warning) = match layer.unmapped.get(&position) {
+                                Some(unmapped) => ("No".to_string(), Some(format!(
+                                    "{} keycode '{}' has no RMK equivalent, exported as No", unmapped.format, unmapped.keycode
+                                ))),
+                                None => key_action(action_at(layers, index, position)),
+                            };
+                            if let Some(warning) = warning {
+                                warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, row, col, warning));
+                            }
+                            quote(&action)
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut output = format!("# {} keymap exported by layout-rs\n", keyboard.name);
+    output.push_str("# Replace the [layout] section of your RMK keyboard.toml with this one\n\n");
+    output.push_str(&format!("[layout]\nrows = {}\ncols = {}\nlayers = {}\nkeymap = [\n", rows, cols, layers.len()));
+
+    // Cells are padded to a common width so the rows line up as a grid
+    let width = layer_rows.iter().flatten().flatten().map(String::len).max().unwrap_or(0) + 1;
+    for (index, rows) in layer_rows.iter().enumerate() {
+        output.push_str(&format!("    # {}\n", layers[index].name));
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(&format!("    # {}\n", line));
+        }
+        output.push_str("    [\n");
+        for row in rows {
+            let cells: Vec<String> = row.iter()
+                .map(|cell| format!("{:<width$}", format!("{},", cell), width = width))
+                .collect();
+            let cells = cells.join(" ");
+            output.push_str(&format!("        [{}],\n", cells.trim_end().trim_end_matches(',')));
+        }
+        output.push_str("    ],\n");
+    }
+    output.push_str("]\n");
+
+    Conversion { output, warnings }
+}
+
+/// A TOML string
+fn quote(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// RMK action for a key, and a warning if it had to be approximated
+fn key_action(action: KeyAction) -> (String, Option<String>) {
+    let name = |usage: KeyboardUsage| key_name(usage)
+        .ok_or_else(|| format!("RMK has no key name for {:?}, exported as No", usage));
+    let modifiers = |modifiers: Modifiers| RMK_MODIFIERS.iter()
+        .filter(|&&(modifier, _)| modifiers.contains(modifier))
+        .map(|&(_, name)| name)
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    let output = match action {
+        KeyAction::Key(usage) => name(usage).map(str::to_string),
+        KeyAction::Consumer(usage) => RMK_CONSUMER_KEYS.iter()
+            .find(|&&(other, _)| other == usage)
+            .map(|&(_, name)| name.to_string())
+            .ok_or_else(|| format!("RMK has no key name for {:?}, exported as No", usage)),
+        KeyAction::Mouse(mouse) => RMK_MOUSE_KEYS.iter()
+            .find(|&&(other, _)| other == mouse)
+            .map(|&(_, name)| name.to_string())
+            .ok_or_else(|| format!("RMK has no key name for {:?}, exported as No", mouse)),
+        KeyAction::Modified { modifiers: held, key } => name(key).map(|key| format!("WM({}, {})", key, modifiers(held))),
+        KeyAction::ModTap { modifiers: held, tap } => name(tap).map(|tap| format!("MT({}, {})", tap, modifiers(held))),
+        KeyAction::LayerTap { layer, tap } => name(tap).map(|tap| format!("LT({}, {})", layer, tap)),
+        KeyAction::MomentaryLayer(layer) => Ok(format!("MO({})", layer)),
+        KeyAction::ToggleLayer(layer) => Ok(format!("TG({})", layer)),
+        KeyAction::OneShotLayer(layer) => Ok(format!("OSL({})", layer)),
+        KeyAction::Transparent => Ok("_".to_string()),
+        KeyAction::NoOp => Ok("No".to_string()),
+    };
+    match output {
+        Ok(output) => (output, None),
+        Err(warning) => ("No".to_string(), Some(warning)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_key_actions() {
+        let action = |action| key_action(action).0;
+
+        assert_eq!(action(KeyAction::Key(Keyboard1Exclamation)), "Kc1");
+        assert_eq!(action(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), "WM(Z, LCtrl | LShift)");
+        assert_eq!(action(KeyAction::ModTap { modifiers: Modifiers::RIGHT_ALT, tap: KeyboardEscape }), "MT(Escape, RAlt)");
+        assert_eq!(action(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), "LT(1, Space)");
+        assert_eq!(action(KeyAction::MomentaryLayer(2)), "MO(2)");
+        assert_eq!(action(KeyAction::ToggleLayer(1)), "TG(1)");
+        assert_eq!(action(KeyAction::OneShotLayer(1)), "OSL(1)");
+        assert_eq!(action(KeyAction::Transparent), "_");
+        assert_eq!(action(KeyAction::NoOp), "No");
+        assert_eq!(action(KeyAction::Consumer(ConsumerUsage::VolumeIncrement)), "AudioVolUp");
+        assert_eq!(action(KeyAction::Consumer(ConsumerUsage::ACBookmarks)), "WwwFavorites");
+        assert_eq!(action(KeyAction::Mouse(MouseAction::WheelLeft)), "MouseWheelLeft");
+
+        let (output, warning) = key_action(KeyAction::Key(Keypad00));
+        assert_eq!(output, "No");
+        assert_eq!(warning.as_deref(), Some("RMK has no key name for Keypad00, exported as No"));
+    }
+
+    #[test]
+    fn test_export_keyboard_toml() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_keyboard_toml(test_keyboard(), &layers);
+        let toml = &export.output;
+
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        assert!(toml.contains("[layout]\nrows = 8\ncols = 14\nlayers = 3\nkeymap = [\n    # Base\n"));
+        assert!(toml.contains("    # | Esc   | 1     |"));
+        assert!(toml.contains("        [\"Escape\",       \"Kc1\",          \"Kc2\","));
+        assert!(toml.contains("\"LGui\",         \"MO(1)\",        \"No\","));
+        assert!(toml.contains("        [\"_\",            \"F1\",           \"_\","));
+        assert!(toml.ends_with("    ],\n]\n"));
+        assert_eq!(toml.matches("    [\n").count(), 3);
+    }
+}
@@ -0,0 +1,589 @@
+//! # VIA and Vial
+//!
+//! - VIA layout `.json` import and export: `layers` hold one flat list per layer, the whole
+//!   `MATRIX_ROWS` × `MATRIX_COLS` grid in row-major order, including matrix positions
+//!   without a key.
+//! - Vial `.vil` import and export: `layout` nests layers, matrix rows and columns, with
+//!   `-1` for positions without a key.
+//!
+//! Both are laid out in the firmware's matrix, the model's [`KeyMatrix`], not the editor's
+//! grid. Files whose layers have another shape are rejected.
+//!
+//! Both store QMK's 16-bit keycode numbers (the ranges used since QMK 0.19 and VIA protocol
+//! 12). Imports also accept QMK keycode names and hex strings, which older files contain.
+//! Neither tool loads a file for another keyboard: VIA rejects a `vendorProductId` that does
+//! not match and Vial asks before loading a foreign `uid`. VIA exports write the model's
+//! [`KeyboardModel::usb_id`]; Vial's `uid` is chosen per firmware build, so Vial exports, and
+//! VIA exports for models without a USB ID, write `0` and warn that it needs replacing.
+
+use serde_json::{json, Value};
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::{KeyMatrix, KeyboardModel};
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::{Layer, UnmappedKeycode};
+use super::{action_at, layer_name, qmk, Conversion};
+
+/// Identifies keycodes kept from VIA and Vial files in [`UnmappedKeycode::format`]
+pub const FORMAT: &str = "via";
+
+const KC_NO: u16 = 0x0000;
+const KC_TRNS: u16 = 0x0001;
+/// Basic keycodes with modifiers held take `0x0100`-`0x1FFF`, the modifier bits shifted
+/// left by 8. Mod-taps take `0x2000`-`0x3FFF`.
+const QK_MOD_TAP: u16 = 0x2000;
+/// Layer-tap on layers 0-15, `0x4000`-`0x4FFF`
+const QK_LAYER_TAP: u16 = 0x4000;
+/// `MO()` on layers 0-31
+const QK_MOMENTARY: u16 = 0x5220;
+/// `TG()` on layers 0-31
+const QK_TOGGLE_LAYER: u16 = 0x5260;
+/// `OSL()` on layers 0-31
+const QK_ONE_SHOT_LAYER: u16 = 0x5280;
+
+/// QMK's media keycodes, in the `0x00A8`-`0x00C2` range the HID Keyboard page leaves reserved
+const CONSUMER_KEYCODES: [(ConsumerUsage, u16); 24] = [
+    (ConsumerUsage::Mute, 0x00A8),
+    (ConsumerUsage::VolumeIncrement, 0x00A9),
+    (ConsumerUsage::VolumeDecrement, 0x00AA),
+    (ConsumerUsage::ScanNextTrack, 0x00AB),
+    (ConsumerUsage::ScanPreviousTrack, 0x00AC),
+    (ConsumerUsage::Stop, 0x00AD),
+    (ConsumerUsage::PlayPause, 0x00AE),
+    (ConsumerUsage::ALConsumerControlConfiguration, 0x00AF),
+    (ConsumerUsage::Eject, 0x00B0),
+    (ConsumerUsage::ALEmailReader, 0x00B1),
+    (ConsumerUsage::ALCalculator, 0x00B2),
+    (ConsumerUsage::ALLocalMachineBrowser, 0x00B3),
+    (ConsumerUsage::ACSearch, 0x00B4),
+    (ConsumerUsage::ACHome, 0x00B5),
+    (ConsumerUsage::ACBack, 0x00B6),
+    (ConsumerUsage::ACForward, 0x00B7),
+    (ConsumerUsage::ACStop, 0x00B8),
+    (ConsumerUsage::ACRefresh, 0x00B9),
+    (ConsumerUsage::ACBookmarks, 0x00BA),
+    (ConsumerUsage::FastForward, 0x00BB),
+    (ConsumerUsage::Rewind, 0x00BC),
+    (ConsumerUsage::DisplayBrightnessIncrement, 0x00BD),
+    (ConsumerUsage::DisplayBrightnessDecrement, 0x00BE),
+    (ConsumerUsage::ALControlPanel, 0x00BF),
+];
+
+/// QMK's mouse keycodes, `0x00CD`-`0x00DF`, of which buttons 6-8 have no mouse action
+const MOUSE_KEYCODES: [(MouseAction, u16); 16] = [
+    (MouseAction::MoveUp, 0x00CD),
+    (MouseAction::MoveDown, 0x00CE),
+    (MouseAction::MoveLeft, 0x00CF),
+    (MouseAction::MoveRight, 0x00D0),
+    (MouseAction::Button1, 0x00D1),
+    (MouseAction::Button2, 0x00D2),
+    (MouseAction::Button3, 0x00D3),
+    (MouseAction::Button4, 0x00D4),
+    (MouseAction::Button5, 0x00D5),
+    (MouseAction::WheelUp, 0x00D9),
+    (MouseAction::WheelDown, 0x00DA),
+    (MouseAction::WheelLeft, 0x00DB),
+    (MouseAction::WheelRight, 0x00DC),
+    (MouseAction::Accel0, 0x00DD),
+    (MouseAction::Accel1, 0x00DE),
+    (MouseAction::Accel2, 0x00DF),
+];
+
+/// Left-hand modifiers with their right-hand counterparts and 5-bit QMK modifier bits.
+/// Right-hand modifiers set the same bits plus `MOD_RIGHT`.
+const MOD_BITS: [(Modifiers, Modifiers, u16); 4] = [
+    (Modifiers::LEFT_CTRL, Modifiers::RIGHT_CTRL, 0x01),
+    (Modifiers::LEFT_SHIFT, Modifiers::RIGHT_SHIFT, 0x02),
+    (Modifiers::LEFT_ALT, Modifiers::RIGHT_ALT, 0x04),
+    (Modifiers::LEFT_GUI, Modifiers::RIGHT_GUI, 0x08),
+];
+const MOD_RIGHT: u16 = 0x10;
+
+/// Highest layer that layer-taps, and the plain layer keys, can address
+const MAX_LAYER_TAP_LAYER: usize = 15;
+const MAX_LAYER_KEY_LAYER: usize = 31;
+
+/// The 16-bit QMK keycode of an action, and a warning if it had to be approximated
+pub fn keycode_number(action: KeyAction) -> (u16, Option<String>) {
+    let layer_key = |base: u16, layer: usize| {
+        if layer > MAX_LAYER_KEY_LAYER {
+            (KC_NO, Some(format!("VIA layer keys only reach layers 0-{}, exported as KC_NO", MAX_LAYER_KEY_LAYER)))
+        } else {
+            (base | layer as u16, None)
+        }
+    };
+
+    let result = match action {
+        KeyAction::Key(usage) => basic_number(usage).map(|code| (code, None)),
+        KeyAction::Consumer(usage) => CONSUMER_KEYCODES.iter()
+            .find(|&&(other, _)| other == usage)
+            .map(|&(_, code)| (code, None))
+            .ok_or_else(|| format!("VIA has no keycode for {:?}, exported as KC_NO", usage)),
+        KeyAction::Mouse(mouse) => MOUSE_KEYCODES.iter()
+            .find(|&&(other, _)| other == mouse)
+            .map(|&(_, code)| (code, None))
+            .ok_or_else(|| format!("VIA has no keycode for {:?}, exported as KC_NO", mouse)),
+        KeyAction::Modified { modifiers, key } => basic_number(key).map(|code| {
+            let (bits, warning) = mod_bits(modifiers);
+            (bits << 8 | code, warning)
+        }),
+        KeyAction::ModTap { modifiers, tap } => basic_number(tap).map(|code| {
+            let (bits, warning) = mod_bits(modifiers);
+            (QK_MOD_TAP | bits << 8 | code, warning)
+        }),
+        KeyAction::LayerTap { layer, tap } => basic_number(tap).map(|code| {
+            if layer > MAX_LAYER_TAP_LAYER {
+                (KC_NO, Some(format!("VIA layer-taps only reach layers 0-{}, exported as KC_NO", MAX_LAYER_TAP_LAYER)))
+            } else {
+                (QK_LAYER_TAP | (layer as u16) << 8 | code, None)
+            }
+        }),
+        KeyAction::MomentaryLayer(layer) => Ok(layer_key(QK_MOMENTARY, layer)),
+        KeyAction::ToggleLayer(layer) => Ok(layer_key(QK_TOGGLE_LAYER, layer)),
+        KeyAction::OneShotLayer(layer) => Ok(layer_key(QK_ONE_SHOT_LAYER, layer)),
+        KeyAction::Transparent => Ok((KC_TRNS, None)),
+        KeyAction::NoOp => Ok((KC_NO, None)),
+    };
+    result.unwrap_or_else(|warning| (KC_NO, Some(warning)))
+}
+
+/// The action of a 16-bit QMK keycode, if the editor can represent it
+pub fn keycode_action(code: u16) -> Option<KeyAction> {
+    let basic = || basic_usage(code & 0xFF);
+    let layer = |base: u16| (code - base) as usize;
+    if let Some(&(usage, _)) = CONSUMER_KEYCODES.iter().find(|&&(_, other)| other == code) {
+        return Some(KeyAction::Consumer(usage));
+    }
+    if let Some(&(mouse, _)) = MOUSE_KEYCODES.iter().find(|&&(_, other)| other == code) {
+        return Some(KeyAction::Mouse(mouse));
+    }
+    match code {
+        KC_NO => Some(KeyAction::NoOp),
+        KC_TRNS => Some(KeyAction::Transparent),
+        0x0004..=0x00FF => Some(KeyAction::Key(basic()?)),
+        0x0100..=0x1FFF => Some(KeyAction::Modified { modifiers: bits_modifiers(code >> 8), key: basic()? }),
+        0x2000..=0x3FFF => Some(KeyAction::ModTap { modifiers: bits_modifiers((code >> 8) & 0x1F), tap: basic()? }),
+        0x4000..=0x4FFF => Some(KeyAction::LayerTap { layer: ((code >> 8) & 0x0F) as usize, tap: basic()? }),
+        0x5220..=0x523F => Some(KeyAction::MomentaryLayer(layer(QK_MOMENTARY))),
+        0x5260..=0x527F => Some(KeyAction::ToggleLayer(layer(QK_TOGGLE_LAYER))),
+        0x5280..=0x529F => Some(KeyAction::OneShotLayer(layer(QK_ONE_SHOT_LAYER))),
+        _ => None,
+    }
+}
+
+/// Basic keycodes are HID usages, except where QMK reuses a range for its own keys
+fn basic_number(usage: KeyboardUsage) -> Result<u16, String> {
+    match qmk::keycode_name(usage) {
+        Some(_) => Ok(usage as u16),
+        None => Err(format!("VIA has no keycode for {:?}, exported as KC_NO", usage)),
+    }
+}
+
+fn basic_usage(code: u16) -> Option<KeyboardUsage> {
+    let usage = KeyboardUsage::from(code as u8);
+    qmk::keycode_name(usage).map(|_| usage)
+}
+
+/// 5-bit QMK modifier bits. QMK cannot mix left and right modifiers in one keycode.
+fn mod_bits(modifiers: Modifiers) -> (u16, Option<String>) {
+    let left = MOD_BITS.iter().any(|&(left, _, _)| modifiers.contains(left));
+    let right = MOD_BITS.iter().any(|&(_, right, _)| modifiers.contains(right));
+    let bits = MOD_BITS.iter()
+        .filter(|&&(left, right, _)| modifiers.contains(left) || modifiers.contains(right))
+        .fold(if right { MOD_RIGHT } else { 0 }, |bits, &(_, _, bit)| bits | bit);
+    let warning = (left && right).then(|| {
+        format!("QMK keycodes cannot mix left and right modifiers ({}); all act as right modifiers", modifiers)
+    });
+    (bits, warning)
+}
+
+fn bits_modifiers(bits: u16) -> Modifiers {
+    MOD_BITS.iter()
+        .filter(|&&(_, _, bit)| bits & bit != 0)
+        .fold(Modifiers::NONE, |modifiers, &(left, right, _)| {
+            modifiers | if bits & MOD_RIGHT != 0 { right } else { left }
+        })
+}
+
+/// JSON value for the key at a matrix position; `None` where no key is wired
+fn key_value(
+    keyboard: &KeyboardModel,
+    layers: &[Layer],
+    index: usize,
+    matrix_position: (usize, usize),
+    warnings: &mut Vec<String>,
+) -> Option<Value> {
+    let position = keyboard.matrix.key_at(matrix_position)?;
+    let layer = &layers[index];
+    let (value, warning) = match layer.unmapped.get(&position) {
+        Some(unmapped) if unmapped.format == FORMAT => match parse_hex(&unmapped.keycode) {
+            Some(code) => (json!(code), None),
+            None => (json!(unmapped.keycode), None),
+        },
+        Some(unmapped) => (json!(KC_NO), Some(format!(
+            "{} keycode '{}' has no VIA equivalent, exported as KC_NO", unmapped.format, unmapped.keycode
+        ))),
+        None => {
+            let (code, warning) = keycode_number(action_at(layers, index, position));
+            (json!(code), warning)
+        }
+    };
+    if let Some(warning) = warning {
+        warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+    }
+    Some(value)
+}
+
+/// Write the keymap as a VIA layout `.json`
+pub fn export_via(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let KeyMatrix { rows, cols, .. } = keyboard.matrix;
+    let mut warnings = Vec::new();
+    let layer_lists: Vec<Value> = (0..layers.len())
+        .map(|index| {
+            let codes: Vec<Value> = (0..rows)
+                .flat_map(|row| (0..cols).map(move |col| (row, col)))
+                .map(|position| key_value(keyboard, layers, index, position, &mut warnings).unwrap_or(json!(KC_NO)))
+                .collect();
+            Value::Array(codes)
+        })
+        .collect();
+
+    let vendor_product_id = match keyboard.usb_id {
+        Some((vendor, product)) => (u32::from(vendor) << 16) | u32::from(product),
+        None => {
+            warnings.push("'vendorProductId' is 0: set it to your keyboard's USB vendor and product ID before loading the file in VIA".to_string());
+            0
+        }
+    };
+    let file = json!({
+        "name": keyboard.name,
+        "vendorProductId": vendor_product_id,
+        "macros": [],
+        "layers": layer_lists,
+    });
+    Conversion { output: to_json(&file), warnings }
+}
+
+/// Write the keymap as a Vial `.vil` backup
+pub fn export_vial(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let KeyMatrix { rows, cols, .. } = keyboard.matrix;
+    let mut warnings = Vec::new();
+    let layout: Vec<Value> = (0..layers.len())
+        .map(|index| {
+            let grid: Vec<Value> = (0..rows)
+                .map(|row| {
+                    (0..cols)
+                        .map(|col| key_value(keyboard, layers, index, (row, col), &mut warnings).unwrap_or(json!(-1)))
+                        .collect()
+                })
+                .collect();
+            Value::Array(grid)
+        })
+        .collect();
+
+    warnings.push("'uid' is 0: copy your keyboard's uid from a .vil file saved by Vial before loading this one".to_string());
+    let file = json!({
+        "version": 1,
+        "uid": 0,
+        "layout": layout,
+        "encoder_layout": [],
+        "layout_options": -1,
+        "macro": [],
+    });
+    Conversion { output: to_json(&file), warnings }
+}
+
+/// Pretty JSON with every innermost list of keycodes on one line
+fn to_json(file: &Value) -> String {
+    let mut output = String::new();
+    write_json(file, 0, &mut output);
+    output.push('\n');
+    output
+}
+
+fn write_json(value: &Value, indent: usize, output: &mut String) {
+    let pad = |indent: usize| "  ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            output.push_str("{\n");
+            for (index, (key, value)) in map.iter().enumerate() {
+                output.push_str(&format!("{}{}: ", pad(indent + 1), Value::from(key.as_str())));
+                write_json(value, indent + 1, output);
+                output.push_str(if index + 1 < map.len() { ",\n" } else { "\n" });
+            }
+            output.push_str(&format!("{}}}", pad(indent)));
+        }
+        Value::Array(items) if items.iter().any(|item| item.is_array() || item.is_object()) => {
+            output.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                output.push_str(&pad(indent + 1));
+                write_json(item, indent + 1, output);
+                output.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            output.push_str(&format!("{}]", pad(indent)));
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(Value::to_string).collect();
+            output.push_str(&format!("[{}]", items.join(", ")));
+        }
+        value => output.push_str(&value.to_string()),
+    }
+}
+
+/// VIA layout `.json`, as saved from VIA's Save + Load pane
+#[derive(serde::Deserialize)]
+struct ViaFile {
+    layers: Vec<Vec<Value>>,
+}
+
+/// Vial `.vil` backup
+#[derive(serde::Deserialize)]
+struct VialFile {
+    layout: Vec<Vec<Vec<Value>>>,
+}
+
+/// Read a VIA layout `.json`. Keycodes the editor cannot represent are kept in
+/// [`Layer::unmapped`].
+pub fn import_via(keyboard: &KeyboardModel, json: &str) -> Result<Conversion<Vec<Layer>>, String> {
+    let file: ViaFile = serde_json::from_str(json)
+        .map_err(|e| format!("Not a VIA layout file: {}", e))?;
+    if file.layers.is_empty() {
+        return Err("VIA layout file has no layers".to_string());
+    }
+
+    let KeyMatrix { rows, cols, .. } = keyboard.matrix;
+    let mut warnings = Vec::new();
+    let layers = file.layers.iter().enumerate()
+        .map(|(index, codes)| {
+            if codes.len() != rows * cols {
+                return Err(format!(
+                    "Layer {} has {} keycodes, but the {} matrix has {} × {} positions",
+                    index, codes.len(), keyboard.name, rows, cols
+                ));
+            }
+            let grid = codes.iter().enumerate().map(|(offset, value)| ((offset / cols, offset % cols), value));
+            Ok(load_layer(keyboard, index, grid, &mut warnings))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Conversion { output: layers, warnings })
+}
+
+/// Read a Vial `.vil` backup. Keycodes the editor cannot represent are kept in
+/// [`Layer::unmapped`].
+pub fn import_vial(keyboard: &KeyboardModel, json: &str) -> Result<Conversion<Vec<Layer>>, String> {
+    let file: VialFile = serde_json::from_str(json)
+        .map_err(|e| format!("Not a Vial layout file: {}", e))?;
+    if file.layout.is_empty() {
+        return Err("Vial layout file has no layers".to_string());
+    }
+
+    let KeyMatrix { rows, cols, .. } = keyboard.matrix;
+    let mut warnings = Vec::new();
+    let layers = file.layout.iter().enumerate()
+        .map(|(index, grid)| {
+            if grid.len() != rows || grid.iter().any(|row| row.len() != cols) {
+                return Err(format!(
+                    "Layer {} is not a {} × {} matrix like the {}'s", index, rows, cols, keyboard.name
+                ));
+            }
+            let grid = grid.iter().enumerate()
+                .flat_map(|(row, codes)| codes.iter().enumerate().map(move |(col, value)| ((row, col), value)));
+            Ok(load_layer(keyboard, index, grid, &mut warnings))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Conversion { output: layers, warnings })
+}
+
+/// Build a layer from keycode values at matrix positions, skipping positions without a key
+fn load_layer<'a>(
+    keyboard: &KeyboardModel,
+    index: usize,
+    grid: impl Iterator<Item = ((usize, usize), &'a Value)>,
+    warnings: &mut Vec<String>,
+) -> Layer {
+    let mut layer = Layer::new(layer_name(keyboard, index));
+    for (matrix_position, value) in grid {
+        let Some(position) = keyboard.matrix.key_at(matrix_position) else {
+            continue;
+        };
+        let (action, keycode) = match value {
+            Value::Number(number) => match number.as_u64().and_then(|code| u16::try_from(code).ok()) {
+                Some(code) => (keycode_action(code), format!("0x{:04X}", code)),
+                // Vial writes -1 where the layout has no key
+                None => continue,
+            },
+            Value::String(keycode) => {
+                let action = match parse_hex(keycode) {
+                    Some(code) => keycode_action(code),
+                    None => qmk::parse_keycode(keycode, &|_| None),
+                };
+                (action, keycode.clone())
+            }
+            other => (None, other.to_string()),
+        };
+        match action {
+            Some(action) => {
+                layer.keys.insert(position, action);
+            }
+            None => {
+                warnings.push(format!(
+                    "Layer {} [{}, {}]: '{}' has no equivalent in the editor and is kept as is",
+                    index, position.0, position.1, keycode
+                ));
+                layer.unmapped.insert(position, UnmappedKeycode { format: FORMAT.to_string(), keycode });
+            }
+        }
+    }
+    layer
+}
+
+/// A keycode written as a hex string, e.g. `0x7C16`
+fn parse_hex(keycode: &str) -> Option<u16> {
+    u16::from_str_radix(keycode.strip_prefix("0x").or_else(|| keycode.strip_prefix("0X"))?, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::keyboards::find_keyboard;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_keycode_numbers() {
+        let number = |action| keycode_number(action).0;
+        assert_eq!(number(KeyAction::Key(KeyboardAa)), 0x0004);
+        assert_eq!(number(KeyAction::Key(KeyboardLeftControl)), 0x00E0);
+        assert_eq!(number(KeyAction::Consumer(ConsumerUsage::PlayPause)), 0x00AE);
+        assert_eq!(number(KeyAction::Mouse(MouseAction::Button1)), 0x00D1);
+        assert_eq!(number(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), 0x031D);
+        assert_eq!(number(KeyAction::Modified { modifiers: Modifiers::RIGHT_ALT, key: KeyboardEe }), 0x1408);
+        assert_eq!(number(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), 0x2129);
+        assert_eq!(number(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), 0x412C);
+        assert_eq!(number(KeyAction::MomentaryLayer(2)), 0x5222);
+        assert_eq!(number(KeyAction::ToggleLayer(1)), 0x5261);
+        assert_eq!(number(KeyAction::OneShotLayer(3)), 0x5283);
+        assert_eq!(number(KeyAction::Transparent), 0x0001);
+        assert_eq!(number(KeyAction::NoOp), 0x0000);
+
+        assert_eq!(keycode_number(KeyAction::Key(Keypad00)), (0, Some("VIA has no keycode for Keypad00, exported as KC_NO".to_string())));
+        assert!(keycode_number(KeyAction::LayerTap { layer: 16, tap: KeyboardAa }).1.is_some());
+        assert!(keycode_number(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::RIGHT_ALT, tap: KeyboardAa }).1.is_some());
+    }
+
+    #[test]
+    fn test_keycode_actions() {
+        for action in [
+            KeyAction::Key(KeyboardAa),
+            KeyAction::Key(KeyboardRightGUI),
+            KeyAction::Consumer(ConsumerUsage::VolumeIncrement),
+            KeyAction::Consumer(ConsumerUsage::ALControlPanel),
+            KeyAction::Mouse(MouseAction::MoveRight),
+            KeyAction::Mouse(MouseAction::Accel2),
+            KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard1Exclamation },
+            KeyAction::Modified { modifiers: Modifiers::RIGHT_CTRL | Modifiers::RIGHT_ALT, key: KeyboardDelete },
+            KeyAction::ModTap { modifiers: Modifiers::LEFT_GUI, tap: KeyboardFf },
+            KeyAction::LayerTap { layer: 15, tap: KeyboardEnter },
+            KeyAction::MomentaryLayer(31),
+            KeyAction::ToggleLayer(0),
+            KeyAction::OneShotLayer(1),
+            KeyAction::Transparent,
+            KeyAction::NoOp,
+        ] {
+            assert_eq!(keycode_action(keycode_number(action).0), Some(action), "{:?}", action);
+        }
+
+        // QMK's system keys, mouse button 8 and RGB_TOG
+        assert_eq!(keycode_action(0x00A5), None);
+        assert_eq!(keycode_action(0x00D8), None);
+        assert_eq!(keycode_action(0x7820), None);
+    }
+
+    #[test]
+    fn test_via_round_trip() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].keys.insert((0, 0), KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape });
+        layers[1].unmapped.insert((0, 1), UnmappedKeycode { format: FORMAT.to_string(), keycode: "0x7820".to_string() });
+
+        let export = export_via(test_keyboard(), &layers);
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        let file: Value = serde_json::from_str(&export.output).unwrap();
+        assert_eq!(file["vendorProductId"], 0x444D_3537);
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        // QMK scans the split 5x7 as 12 × 7, the right half from row 6
+        assert_eq!(file["layers"][0].as_array().unwrap().len(), 12 * 7);
+        assert_eq!(file["layers"][0][0], 0x2129);
+        assert_eq!(file["layers"][0][6 * 7], 0x0024);
+        assert_eq!(file["layers"][1][1], 0x7820);
+        assert!(export.output.contains("\"layers\": [\n    [8489, 30, 31,"), "{}", export.output);
+
+        let import = import_via(test_keyboard(), &export.output).unwrap();
+        assert_eq!(import.warnings, ["Layer 1 [0, 1]: '0x7820' has no equivalent in the editor and is kept as is"]);
+        assert_eq!(import.output[0].keys[&(0, 0)], layers[0].keys[&(0, 0)]);
+        assert_eq!(import.output[1].unmapped, layers[1].unmapped);
+        assert_eq!(export_via(test_keyboard(), &import.output).output, export.output);
+    }
+
+    #[test]
+    fn test_via_export_without_usb_id() {
+        let kinesis = find_keyboard("kinesis_advantage").unwrap();
+        let export = export_via(kinesis, &kinesis.default_layers);
+        let file: Value = serde_json::from_str(&export.output).unwrap();
+        assert_eq!(file["vendorProductId"], 0);
+        assert_eq!(export.warnings, ["'vendorProductId' is 0: set it to your keyboard's USB vendor and product ID before loading the file in VIA"]);
+
+        let corne = find_keyboard("corne").unwrap();
+        let file: Value = serde_json::from_str(&export_via(corne, &corne.default_layers).output).unwrap();
+        assert_eq!(file["vendorProductId"], 0x4653_0001);
+    }
+
+    #[test]
+    fn test_vial_round_trip() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[2].keys.insert((1, 1), KeyAction::Modified { modifiers: Modifiers::RIGHT_ALT, key: KeyboardEe });
+        let export = export_vial(test_keyboard(), &layers);
+        let file: Value = serde_json::from_str(&export.output).unwrap();
+        assert_eq!(file["uid"], 0);
+        assert_eq!(export.warnings, ["'uid' is 0: copy your keyboard's uid from a .vil file saved by Vial before loading this one"]);
+        assert_eq!(file["layout"][2][1][1], 0x1408);
+
+        assert_eq!(file["layout"][0].as_array().unwrap().len(), 12);
+        assert_eq!(file["layout"][0][6][0], 0x0024);
+        // Matrix positions without a key are -1
+        assert_eq!(file["layout"][0][5][0], -1);
+
+        let import = import_vial(test_keyboard(), &export.output).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        assert_eq!(import.output[2].keys[&(1, 1)], layers[2].keys[&(1, 1)]);
+        assert_eq!(export_vial(test_keyboard(), &import.output).output, export.output);
+    }
+
+    #[test]
+    fn test_import_vial_names() {
+        let mut grid = vec![vec![json!(-1); 7]; 12];
+        grid[0][..4].clone_from_slice(&[json!("KC_ESC"), json!("LT(1,KC_SPACE)"), json!("0x5221"), json!("RGB_TOG")]);
+        let vil = json!({"version": 1, "uid": 123, "layout": [grid]}).to_string();
+        let import = import_vial(test_keyboard(), &vil).unwrap();
+        let layer = &import.output[0];
+        assert_eq!(layer.name, "Base");
+        assert_eq!(layer.keys[&(0, 0)], KeyAction::Key(KeyboardEscape));
+        assert_eq!(layer.keys[&(0, 1)], KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar });
+        assert_eq!(layer.keys[&(0, 2)], KeyAction::MomentaryLayer(1));
+        assert_eq!(layer.unmapped[&(0, 3)].keycode, "RGB_TOG");
+        assert_eq!(import.warnings, ["Layer 0 [0, 3]: 'RGB_TOG' has no equivalent in the editor and is kept as is"]);
+
+        assert!(import_vial(test_keyboard(), r#"{"layout": []}"#).is_err());
+        assert!(import_via(test_keyboard(), r#"{"layers": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_import_wrong_matrix() {
+        // The editor's 8 × 14 grid is not the firmware's 12 × 7 matrix
+        let via = json!({"layers": [vec![0; 8 * 14]]}).to_string();
+        assert_eq!(
+            import_via(test_keyboard(), &via).unwrap_err(),
+            "Layer 0 has 112 keycodes, but the Dactyl Manuform 5x7 matrix has 12 × 7 positions"
+        );
+        let vil = json!({"layout": [vec![vec![0; 14]; 8]]}).to_string();
+        assert_eq!(import_vial(test_keyboard(), &vil).unwrap_err(), "Layer 0 is not a 12 × 7 matrix like the Dactyl Manuform 5x7's");
+    }
+}
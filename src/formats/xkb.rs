@@ -0,0 +1,565 @@
+//! # XKB
+//!
+//! Linux `xkb_symbols` export, to remap an ordinary keyboard in software. Each matrix position
+//! is read from a physical key (see [`source_keys`]), whose HID usage gives its evdev keycode
+//! and so its XKB key name, e.g. `<AC01>` for A. The key then sends the keysyms of the keymap:
+//! levels 1-2 are the base layer, levels 3-4 the first layer above it and levels 5-6 the
+//! second, reached through `ISO_Level3_Shift` and `ISO_Level5_Shift` where the keymap has
+//! `MO(1)` and `MO(2)`.
+//!
+//! Keys that send what they are printed with on every layer are left out of the file.
+//! Mouse keys become `Pointer_` keysyms, which only act while X's Mouse Keys are enabled.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+use crate::keymap::Layer;
+use super::{action_at, source_keys, Conversion};
+
+/// Name of the symbols file, which holds one section per keyboard model
+pub const FILE_NAME: &str = "layout_rs";
+
+/// Layers XKB can reach: the base layer and the two above it, on levels 3 and 5
+const MAX_LAYERS: usize = 3;
+
+/// Keysym for an action without one
+const NO_SYMBOL: &str = "NoSymbol";
+
+/// Evdev keycode of a usage on Linux, with the XKB name of the key (`<AC01>`)
+static EVDEV_KEYS: Lazy<HashMap<KeyboardUsage, (u16, &'static str)>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, (30, "AC01")),
+        (KeyboardBb, (48, "AB05")),
+        (KeyboardCc, (46, "AB03")),
+        (KeyboardDd, (32, "AC03")),
+        (KeyboardEe, (18, "AD03")),
+        (KeyboardFf, (33, "AC04")),
+        (KeyboardGg, (34, "AC05")),
+        (KeyboardHh, (35, "AC06")),
+        (KeyboardIi, (23, "AD08")),
+        (KeyboardJj, (36, "AC07")),
+        (KeyboardKk, (37, "AC08")),
+        (KeyboardLl, (38, "AC09")),
+        (KeyboardMm, (50, "AB07")),
+        (KeyboardNn, (49, "AB06")),
+        (KeyboardOo, (24, "AD09")),
+        (KeyboardPp, (25, "AD10")),
+        (KeyboardQq, (16, "AD01")),
+        (KeyboardRr, (19, "AD04")),
+        (KeyboardSs, (31, "AC02")),
+        (KeyboardTt, (20, "AD05")),
+        (KeyboardUu, (22, "AD07")),
+        (KeyboardVv, (47, "AB04")),
+        (KeyboardWw, (17, "AD02")),
+        (KeyboardXx, (45, "AB02")),
+        (KeyboardYy, (21, "AD06")),
+        (KeyboardZz, (44, "AB01")),
+        (Keyboard1Exclamation, (2, "AE01")),
+        (Keyboard2At, (3, "AE02")),
+        (Keyboard3Hash, (4, "AE03")),
+        (Keyboard4Dollar, (5, "AE04")),
+        (Keyboard5Percent, (6, "AE05")),
+        (Keyboard6Caret, (7, "AE06")),
+        (Keyboard7Ampersand, (8, "AE07")),
+        (Keyboard8Asterisk, (9, "AE08")),
+        (Keyboard9OpenParens, (10, "AE09")),
+        (Keyboard0CloseParens, (11, "AE10")),
+        (KeyboardEnter, (28, "RTRN")),
+        (KeyboardEscape, (1, "ESC")),
+        (KeyboardBackspace, (14, "BKSP")),
+        (KeyboardTab, (15, "TAB")),
+        (KeyboardSpacebar, (57, "SPCE")),
+        (KeyboardDashUnderscore, (12, "AE11")),
+        (KeyboardEqualPlus, (13, "AE12")),
+        (KeyboardOpenBracketBrace, (26, "AD11")),
+        (KeyboardCloseBracketBrace, (27, "AD12")),
+        (KeyboardBackslashBar, (43, "BKSL")),
+        (KeyboardSemiColon, (39, "AC10")),
+        (KeyboardSingleDoubleQuote, (40, "AC11")),
+        (KeyboardBacktickTilde, (41, "TLDE")),
+        (KeyboardCommaLess, (51, "AB08")),
+        (KeyboardPeriodGreater, (52, "AB09")),
+        (KeyboardSlashQuestion, (53, "AB10")),
+        (KeyboardCapsLock, (58, "CAPS")),
+        (KeyboardF1, (59, "FK01")),
+        (KeyboardF2, (60, "FK02")),
+        (KeyboardF3, (61, "FK03")),
+        (KeyboardF4, (62, "FK04")),
+        (KeyboardF5, (63, "FK05")),
+        (KeyboardF6, (64, "FK06")),
+        (KeyboardF7, (65, "FK07")),
+        (KeyboardF8, (66, "FK08")),
+        (KeyboardF9, (67, "FK09")),
+        (KeyboardF10, (68, "FK10")),
+        (KeyboardF11, (87, "FK11")),
+        (KeyboardF12, (88, "FK12")),
+        (KeyboardPrintScreen, (99, "PRSC")),
+        (KeyboardScrollLock, (70, "SCLK")),
+        (KeyboardPause, (119, "PAUS")),
+        (KeyboardInsert, (110, "INS")),
+        (KeyboardHome, (102, "HOME")),
+        (KeyboardPageUp, (104, "PGUP")),
+        (KeyboardDelete, (111, "DELE")),
+        (KeyboardEnd, (107, "END")),
+        (KeyboardPageDown, (109, "PGDN")),
+        (KeyboardRightArrow, (106, "RGHT")),
+        (KeyboardLeftArrow, (105, "LEFT")),
+        (KeyboardDownArrow, (108, "DOWN")),
+        (KeyboardUpArrow, (103, "UP")),
+        (KeypadNumLock, (69, "NMLK")),
+        (KeypadDivide, (98, "KPDV")),
+        (KeypadMultiply, (55, "KPMU")),
+        (KeypadMinus, (74, "KPSU")),
+        (KeypadPlus, (78, "KPAD")),
+        (KeypadEnter, (96, "KPEN")),
+        (Keypad1End, (79, "KP1")),
+        (Keypad2DownArrow, (80, "KP2")),
+        (Keypad3PageDown, (81, "KP3")),
+        (Keypad4LeftArrow, (75, "KP4")),
+        (Keypad5, (76, "KP5")),
+        (Keypad6RightArrow, (77, "KP6")),
+        (Keypad7Home, (71, "KP7")),
+        (Keypad8UpArrow, (72, "KP8")),
+        (Keypad9PageUp, (73, "KP9")),
+        (Keypad0Insert, (82, "KP0")),
+        (KeypadPeriodDelete, (83, "KPDL")),
+        (KeyboardNonUSSlash, (86, "LSGT")),
+        (KeyboardApplication, (127, "COMP")),
+        (KeyboardPower, (116, "POWR")),
+        (KeypadEqual, (117, "KPEQ")),
+        (KeyboardF13, (183, "FK13")),
+        (KeyboardF14, (184, "FK14")),
+        (KeyboardF15, (185, "FK15")),
+        (KeyboardF16, (186, "FK16")),
+        (KeyboardF17, (187, "FK17")),
+        (KeyboardF18, (188, "FK18")),
+        (KeyboardF19, (189, "FK19")),
+        (KeyboardF20, (190, "FK20")),
+        (KeyboardF21, (191, "FK21")),
+        (KeyboardF22, (192, "FK22")),
+        (KeyboardF23, (193, "FK23")),
+        (KeyboardF24, (194, "FK24")),
+        (KeyboardMute, (113, "MUTE")),
+        (KeyboardVolumeUp, (115, "VOL+")),
+        (KeyboardVolumeDown, (114, "VOL-")),
+        (KeyboardLeftControl, (29, "LCTL")),
+        (KeyboardLeftShift, (42, "LFSH")),
+        (KeyboardLeftAlt, (56, "LALT")),
+        (KeyboardLeftGUI, (125, "LWIN")),
+        (KeyboardRightControl, (97, "RCTL")),
+        (KeyboardRightShift, (54, "RTSH")),
+        (KeyboardRightAlt, (100, "RALT")),
+        (KeyboardRightGUI, (126, "RWIN")),
+    ])
+});
+
+/// Keysyms a usage sends on the US layout, unshifted and shifted where that differs
+static KEYSYMS: Lazy<HashMap<KeyboardUsage, (&'static str, Option<&'static str>)>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, ("a", Some("A"))),
+        (KeyboardBb, ("b", Some("B"))),
+        (KeyboardCc, ("c", Some("C"))),
+        (KeyboardDd, ("d", Some("D"))),
+        (KeyboardEe, ("e", Some("E"))),
+        (KeyboardFf, ("f", Some("F"))),
+        (KeyboardGg, ("g", Some("G"))),
+        (KeyboardHh, ("h", Some("H"))),
+        (KeyboardIi, ("i", Some("I"))),
+        (KeyboardJj, ("j", Some("J"))),
+        (KeyboardKk, ("k", Some("K"))),
+        (KeyboardLl, ("l", Some("L"))),
+        (KeyboardMm, ("m", Some("M"))),
+        (KeyboardNn, ("n", Some("N"))),
+        (KeyboardOo, ("o", Some("O"))),
+        (KeyboardPp, ("p", Some("P"))),
+        (KeyboardQq, ("q", Some("Q"))),
+        (KeyboardRr, ("r", Some("R"))),
+        (KeyboardSs, ("s", Some("S"))),
+        (KeyboardTt, ("t", Some("T"))),
+        (KeyboardUu, ("u", Some("U"))),
+        (KeyboardVv, ("v", Some("V"))),
+        (KeyboardWw, ("w", Some("W"))),
+        (KeyboardXx, ("x", Some("X"))),
+        (KeyboardYy, ("y", Some("Y"))),
+        (KeyboardZz, ("z", Some("Z"))),
+        (Keyboard1Exclamation, ("1", Some("exclam"))),
+        (Keyboard2At, ("2", Some("at"))),
+        (Keyboard3Hash, ("3", Some("numbersign"))),
+        (Keyboard4Dollar, ("4", Some("dollar"))),
+        (Keyboard5Percent, ("5", Some("percent"))),
+        (Keyboard6Caret, ("6", Some("asciicircum"))),
+        (Keyboard7Ampersand, ("7", Some("ampersand"))),
+        (Keyboard8Asterisk, ("8", Some("asterisk"))),
+        (Keyboard9OpenParens, ("9", Some("parenleft"))),
+        (Keyboard0CloseParens, ("0", Some("parenright"))),
+        (KeyboardEnter, ("Return", None)),
+        (KeyboardEscape, ("Escape", None)),
+        (KeyboardBackspace, ("BackSpace", None)),
+        (KeyboardTab, ("Tab", Some("ISO_Left_Tab"))),
+        (KeyboardSpacebar, ("space", None)),
+        (KeyboardDashUnderscore, ("minus", Some("underscore"))),
+        (KeyboardEqualPlus, ("equal", Some("plus"))),
+        (KeyboardOpenBracketBrace, ("bracketleft", Some("braceleft"))),
+        (KeyboardCloseBracketBrace, ("bracketright", Some("braceright"))),
+        (KeyboardBackslashBar, ("backslash", Some("bar"))),
+        (KeyboardSemiColon, ("semicolon", Some("colon"))),
+        (KeyboardSingleDoubleQuote, ("apostrophe", Some("quotedbl"))),
+        (KeyboardBacktickTilde, ("grave", Some("asciitilde"))),
+        (KeyboardCommaLess, ("comma", Some("less"))),
+        (KeyboardPeriodGreater, ("period", Some("greater"))),
+        (KeyboardSlashQuestion, ("slash", Some("question"))),
+        (KeyboardCapsLock, ("Caps_Lock", None)),
+        (KeyboardF1, ("F1", None)),
+        (KeyboardF2, ("F2", None)),
+        (KeyboardF3, ("F3", None)),
+        (KeyboardF4, ("F4", None)),
+        (KeyboardF5, ("F5", None)),
+        (KeyboardF6, ("F6", None)),
+        (KeyboardF7, ("F7", None)),
+        (KeyboardF8, ("F8", None)),
+        (KeyboardF9, ("F9", None)),
+        (KeyboardF10, ("F10", None)),
+        (KeyboardF11, ("F11", None)),
+        (KeyboardF12, ("F12", None)),
+        (KeyboardPrintScreen, ("Print", None)),
+        (KeyboardScrollLock, ("Scroll_Lock", None)),
+        (KeyboardPause, ("Pause", None)),
+        (KeyboardInsert, ("Insert", None)),
+        (KeyboardHome, ("Home", None)),
+        (KeyboardPageUp, ("Prior", None)),
+        (KeyboardDelete, ("Delete", None)),
+        (KeyboardEnd, ("End", None)),
+        (KeyboardPageDown, ("Next", None)),
+        (KeyboardRightArrow, ("Right", None)),
+        (KeyboardLeftArrow, ("Left", None)),
+        (KeyboardDownArrow, ("Down", None)),
+        (KeyboardUpArrow, ("Up", None)),
+        (KeypadNumLock, ("Num_Lock", None)),
+        (KeypadDivide, ("KP_Divide", None)),
+        (KeypadMultiply, ("KP_Multiply", None)),
+        (KeypadMinus, ("KP_Subtract", None)),
+        (KeypadPlus, ("KP_Add", None)),
+        (KeypadEnter, ("KP_Enter", None)),
+        (Keypad1End, ("KP_1", None)),
+        (Keypad2DownArrow, ("KP_2", None)),
+        (Keypad3PageDown, ("KP_3", None)),
+        (Keypad4LeftArrow, ("KP_4", None)),
+        (Keypad5, ("KP_5", None)),
+        (Keypad6RightArrow, ("KP_6", None)),
+        (Keypad7Home, ("KP_7", None)),
+        (Keypad8UpArrow, ("KP_8", None)),
+        (Keypad9PageUp, ("KP_9", None)),
+        (Keypad0Insert, ("KP_0", None)),
+        (KeypadPeriodDelete, ("KP_Decimal", None)),
+        (KeyboardNonUSSlash, ("less", Some("greater"))),
+        (KeyboardApplication, ("Menu", None)),
+        (KeyboardPower, ("XF86PowerOff", None)),
+        (KeypadEqual, ("KP_Equal", None)),
+        (KeyboardF13, ("F13", None)),
+        (KeyboardF14, ("F14", None)),
+        (KeyboardF15, ("F15", None)),
+        (KeyboardF16, ("F16", None)),
+        (KeyboardF17, ("F17", None)),
+        (KeyboardF18, ("F18", None)),
+        (KeyboardF19, ("F19", None)),
+        (KeyboardF20, ("F20", None)),
+        (KeyboardF21, ("F21", None)),
+        (KeyboardF22, ("F22", None)),
+        (KeyboardF23, ("F23", None)),
+        (KeyboardF24, ("F24", None)),
+        (KeyboardMute, ("XF86AudioMute", None)),
+        (KeyboardVolumeUp, ("XF86AudioRaiseVolume", None)),
+        (KeyboardVolumeDown, ("XF86AudioLowerVolume", None)),
+        (KeyboardLeftControl, ("Control_L", None)),
+        (KeyboardLeftShift, ("Shift_L", None)),
+        (KeyboardLeftAlt, ("Alt_L", None)),
+        (KeyboardLeftGUI, ("Super_L", None)),
+        (KeyboardRightControl, ("Control_R", None)),
+        (KeyboardRightShift, ("Shift_R", None)),
+        (KeyboardRightAlt, ("Alt_R", None)),
+        (KeyboardRightGUI, ("Super_R", None)),
+    ])
+});
+
+/// Evdev keycode and XKB key name of a usage, e.g. `(30, "AC01")` for A
+pub fn evdev_key(usage: KeyboardUsage) -> Option<(u16, &'static str)> {
+    EVDEV_KEYS.get(&usage).copied()
+}
+
+/// Unshifted and shifted keysym of a usage, e.g. `("1", "exclam")`
+pub fn keysyms(usage: KeyboardUsage) -> Option<(&'static str, &'static str)> {
+    KEYSYMS.get(&usage).map(|&(plain, shifted)| (plain, shifted.unwrap_or(plain)))
+}
+
+/// XF86 keysym of each consumer control that has one
+const CONSUMER_KEYSYMS: [(ConsumerUsage, &str); 23] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, "XF86MonBrightnessUp"),
+    (ConsumerUsage::DisplayBrightnessDecrement, "XF86MonBrightnessDown"),
+    (ConsumerUsage::FastForward, "XF86AudioForward"),
+    (ConsumerUsage::Rewind, "XF86AudioRewind"),
+    (ConsumerUsage::ScanNextTrack, "XF86AudioNext"),
+    (ConsumerUsage::ScanPreviousTrack, "XF86AudioPrev"),
+    (ConsumerUsage::Stop, "XF86AudioStop"),
+    (ConsumerUsage::Eject, "XF86Eject"),
+    (ConsumerUsage::PlayPause, "XF86AudioPlay"),
+    (ConsumerUsage::Mute, "XF86AudioMute"),
+    (ConsumerUsage::VolumeIncrement, "XF86AudioRaiseVolume"),
+    (ConsumerUsage::VolumeDecrement, "XF86AudioLowerVolume"),
+    (ConsumerUsage::ALConsumerControlConfiguration, "XF86Tools"),
+    (ConsumerUsage::ALEmailReader, "XF86Mail"),
+    (ConsumerUsage::ALCalculator, "XF86Calculator"),
+    (ConsumerUsage::ALLocalMachineBrowser, "XF86MyComputer"),
+    (ConsumerUsage::ACSearch, "XF86Search"),
+    (ConsumerUsage::ACHome, "XF86HomePage"),
+    (ConsumerUsage::ACBack, "XF86Back"),
+    (ConsumerUsage::ACForward, "XF86Forward"),
+    (ConsumerUsage::ACStop, "XF86Stop"),
+    (ConsumerUsage::ACRefresh, "XF86Refresh"),
+    (ConsumerUsage::ACBookmarks, "XF86Favorites"),
+];
+
+/// Pointer keysym of each mouse action that has one. X numbers the middle button 2 and the
+/// right button 3, and sends the vertical wheel as buttons 4 and 5.
+const MOUSE_KEYSYMS: [(MouseAction, &str); 9] = [
+    (MouseAction::Button1, "Pointer_Button1"),
+    (MouseAction::Button2, "Pointer_Button3"),
+    (MouseAction::Button3, "Pointer_Button2"),
+    (MouseAction::MoveUp, "Pointer_Up"),
+    (MouseAction::MoveDown, "Pointer_Down"),
+    (MouseAction::MoveLeft, "Pointer_Left"),
+    (MouseAction::MoveRight, "Pointer_Right"),
+    (MouseAction::WheelUp, "Pointer_Button4"),
+    (MouseAction::WheelDown, "Pointer_Button5"),
+];
+
+/// Write the keymap as an XKB symbols file with a section named after the keyboard model
+pub fn export_symbols(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let mut warnings = Vec::new();
+    let sources = source_keys(keyboard, layers, |usage| evdev_key(usage).map(|(_, name)| name), &mut warnings);
+    for layer in layers.iter().skip(MAX_LAYERS) {
+        warnings.push(format!("Layer '{}': XKB only has levels for two layers above the base layer, left out", layer.name));
+    }
+    let layer_count = layers.len().min(MAX_LAYERS);
+
+    let mut keys = Vec::new();
+    for &(position, source) in sources.iter().flatten() {
+        // Transparent keys send what the layer below sends
+        let mut levels: Vec<(&str, &str)> = Vec::with_capacity(layer_count);
+        for (index, layer) in layers.iter().enumerate().take(layer_count) {
+            let (symbols, warning) = match (layer.unmapped.get(&position), action_at(layers, index, position)) {
+                (Some(unmapped), _) => ((NO_SYMBOL, NO_SYMBOL), Some(format!(
+                    "{} keycode '{}' has no XKB equivalent, exported as NoSymbol", unmapped.format, unmapped.keycode
+                ))),
+                (None, KeyAction::Transparent) => (levels.last().copied().unwrap_or((NO_SYMBOL, NO_SYMBOL)), None),
+                (None, action) => action_keysyms(action),
+            };
+            if let Some(warning) = warning {
+                warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+            }
+            levels.push(symbols);
+        }
+
+        let printed = keysyms(source).unwrap_or((NO_SYMBOL, NO_SYMBOL));
+        if levels.iter().all(|&symbols| symbols == printed) {
+            continue;
+        }
+        let (code, name) = evdev_key(source).expect("source keys have an evdev keycode");
+        keys.push((position, code, name, key_symbols(&levels)));
+    }
+
+    let uses = |prefix: &str| keys.iter().any(|(_, _, _, symbols)| symbols.contains(prefix));
+    let layer_levels = ["levels 1-2", "levels 3-4 (ISO_Level3_Shift)", "levels 5-6 (ISO_Level5_Shift)"];
+    let section = &keyboard.id;
+
+    let mut output = format!("// {} keymap exported by layout-rs\n//\n", keyboard.name);
+    output.push_str("// Remaps an ordinary keyboard. Keys are named by their evdev keycode and send:\n");
+    for (layer, levels) in layers.iter().zip(layer_levels) {
+        output.push_str(&format!("//   {}: layer '{}'\n", levels, layer.name));
+    }
+    output.push_str(&format!(
+        "//\n\
+         // Install on X11:\n\
+         //   mkdir -p ~/.config/xkb/symbols && cp {file} ~/.config/xkb/symbols/\n\
+         //   setxkbmap -print | sed 's/\\(xkb_symbols.*\\)\"/\\1+{file}({section})\"/' | xkbcomp -I$HOME/.config/xkb - $DISPLAY\n\
+         // and undo it by running setxkbmap with your usual layout, e.g. `setxkbmap us`.\n\
+         //\n\
+         // Install on Wayland: copy the file as above, then add an option for it to\n\
+         // ~/.config/xkb/rules/evdev and enable `{file}:{section}` in your compositor:\n\
+         //   ! include %S/evdev\n\
+         //\n\
+         //   ! option = symbols\n\
+         //     {file}:{section} = +{file}({section})\n\n",
+        file = FILE_NAME, section = section,
+    ));
+
+    output.push_str(&format!("partial alphanumeric_keys modifier_keys\nxkb_symbols \"{}\" {{\n", section));
+    if uses("ISO_Level3_") {
+        output.push_str("    include \"level3(modifier_mapping)\"\n");
+    }
+    if uses("ISO_Level5_") {
+        output.push_str("    include \"level5(modifier_mapping)\"\n");
+    }
+    let width = keys.iter().map(|(_, _, name, _)| name.len()).max().unwrap_or(0);
+    for ((row, col), code, name, symbols) in &keys {
+        output.push_str(&format!(
+            "    key {:<width$} {{ {} }};  // [{}, {}], evdev {}\n",
+            format!("<{}>", name), symbols, row, col, code, width = width + 2
+        ));
+    }
+    output.push_str("};\n");
+
+    Conversion { output, warnings }
+}
+
+/// Type and symbols of a key with the given keysyms per layer
+fn key_symbols(levels: &[(&str, &str)]) -> String {
+    let mut symbols: Vec<&str> = levels.iter().flat_map(|&(plain, shifted)| [plain, shifted]).collect();
+    if symbols.iter().all(|&symbol| symbol == symbols[0]) {
+        return format!("type[Group1] = \"ONE_LEVEL\", symbols[Group1] = [ {} ]", symbols[0]);
+    }
+
+    // Letters keep following Caps Lock on the base layer
+    let alphabetic = levels[0].0.len() == 1
+        && levels[0].0.chars().all(|c| c.is_ascii_lowercase())
+        && levels[0].1 == levels[0].0.to_ascii_uppercase();
+    let key_type = match (levels.len(), alphabetic) {
+        (1, true) => "ALPHABETIC",
+        (1, false) => "TWO_LEVEL",
+        (2, true) => "FOUR_LEVEL_SEMIALPHABETIC",
+        (2, false) => "FOUR_LEVEL",
+        (_, true) => "EIGHT_LEVEL_SEMIALPHABETIC",
+        (_, false) => "EIGHT_LEVEL",
+    };
+    // Levels 7-8 (Level3 and Level5 together) have no layer
+    if levels.len() == MAX_LAYERS {
+        symbols.resize(8, NO_SYMBOL);
+    }
+    format!("type[Group1] = \"{}\", symbols[Group1] = [ {} ]", key_type, symbols.join(", "))
+}
+
+/// Unshifted and shifted keysym for an action, and a warning if it had to be approximated
+fn action_keysyms(action: KeyAction) -> ((&'static str, &'static str), Option<String>) {
+    let none = (NO_SYMBOL, NO_SYMBOL);
+    let key = |usage: KeyboardUsage| match keysyms(usage) {
+        Some(symbols) => (symbols, None),
+        None => (none, Some(format!("XKB has no keysym for {:?}, exported as NoSymbol", usage))),
+    };
+    // Keysyms that shift, latch or lock the level of a layer
+    let level = |layer: usize, kind: &str| {
+        let symbol = match (layer, kind) {
+            (1, "Shift") => "ISO_Level3_Shift",
+            (1, "Latch") => "ISO_Level3_Latch",
+            (1, "Lock") => "ISO_Level3_Lock",
+            (2, "Shift") => "ISO_Level5_Shift",
+            (2, "Latch") => "ISO_Level5_Latch",
+            (2, "Lock") => "ISO_Level5_Lock",
+            _ => return (none, Some(format!("XKB has no level for layer {}, exported as NoSymbol", layer))),
+        };
+        ((symbol, symbol), None)
+    };
+
+    match action {
+        KeyAction::Key(usage) => key(usage),
+        KeyAction::Consumer(usage) => match CONSUMER_KEYSYMS.iter().find(|&&(other, _)| other == usage) {
+            Some(&(_, symbol)) => ((symbol, symbol), None),
+            None => (none, Some(format!("XKB has no keysym for {:?}, exported as NoSymbol", usage))),
+        },
+        KeyAction::Mouse(mouse) => match MOUSE_KEYSYMS.iter().find(|&&(other, _)| other == mouse) {
+            Some(&(_, symbol)) => ((symbol, symbol), None),
+            None => (none, Some(format!("XKB has no keysym for {:?}, exported as NoSymbol", mouse))),
+        },
+        KeyAction::Modified { modifiers, key: usage }
+            if modifiers == Modifiers::LEFT_SHIFT || modifiers == Modifiers::RIGHT_SHIFT =>
+        {
+            match key(usage) {
+                ((_, shifted), None) => ((shifted, shifted), None),
+                other => other,
+            }
+        }
+        KeyAction::Modified { .. } => (none, Some(format!("XKB cannot send {} from one key, exported as NoSymbol", action))),
+        KeyAction::ModTap { tap, .. } | KeyAction::LayerTap { tap, .. } => match key(tap) {
+            (symbols, None) => (symbols, Some(format!("XKB has no hold-tap keys, {} exported as its tap key", action))),
+            other => other,
+        },
+        KeyAction::MomentaryLayer(layer) => level(layer, "Shift"),
+        KeyAction::OneShotLayer(layer) => level(layer, "Latch"),
+        KeyAction::ToggleLayer(layer) => level(layer, "Lock"),
+        KeyAction::Transparent | KeyAction::NoOp => (none, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_key_tables() {
+        assert_eq!(evdev_key(KeyboardAa), Some((30, "AC01")));
+        assert_eq!(evdev_key(KeyboardF13), Some((183, "FK13")));
+        assert_eq!(keysyms(Keyboard1Exclamation), Some(("1", "exclam")));
+        assert_eq!(keysyms(KeyboardEscape), Some(("Escape", "Escape")));
+        for usage in KEYSYMS.keys() {
+            assert!(evdev_key(*usage).is_some(), "{:?}", usage);
+        }
+        let mut names: Vec<&str> = EVDEV_KEYS.values().map(|&(_, name)| name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), EVDEV_KEYS.len());
+    }
+
+    #[test]
+    fn test_action_keysyms() {
+        let symbols = |action| action_keysyms(action).0;
+
+        assert_eq!(symbols(KeyAction::Key(KeyboardSlashQuestion)), ("slash", "question"));
+        assert_eq!(symbols(KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard9OpenParens }), ("parenleft", "parenleft"));
+        assert_eq!(symbols(KeyAction::MomentaryLayer(1)), ("ISO_Level3_Shift", "ISO_Level3_Shift"));
+        assert_eq!(symbols(KeyAction::ToggleLayer(2)), ("ISO_Level5_Lock", "ISO_Level5_Lock"));
+        assert_eq!(symbols(KeyAction::OneShotLayer(1)), ("ISO_Level3_Latch", "ISO_Level3_Latch"));
+        assert_eq!(symbols(KeyAction::Consumer(ConsumerUsage::VolumeIncrement)), ("XF86AudioRaiseVolume", "XF86AudioRaiseVolume"));
+        assert_eq!(symbols(KeyAction::Mouse(MouseAction::Button2)), ("Pointer_Button3", "Pointer_Button3"));
+        assert_eq!(symbols(KeyAction::NoOp), (NO_SYMBOL, NO_SYMBOL));
+
+        let (symbols, warning) = action_keysyms(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape });
+        assert_eq!(symbols, ("Escape", "Escape"));
+        assert_eq!(warning.as_deref(), Some("XKB has no hold-tap keys, MT(Ctrl, Esc) exported as its tap key"));
+        let (symbols, warning) = action_keysyms(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeyboardCc });
+        assert_eq!(symbols, (NO_SYMBOL, NO_SYMBOL));
+        assert_eq!(warning.as_deref(), Some("XKB cannot send Ctrl+C from one key, exported as NoSymbol"));
+        let (_, warning) = action_keysyms(KeyAction::MomentaryLayer(3));
+        assert_eq!(warning.as_deref(), Some("XKB has no level for layer 3, exported as NoSymbol"));
+    }
+
+    #[test]
+    fn test_export_symbols() {
+        use KeyAction::Key;
+
+        // Function keys on Lower's number row, arrows on Raise's home row
+        let mut layers = test_keyboard().default_layers.clone();
+        for (col, key) in [KeyboardF1, KeyboardF2, KeyboardF3, KeyboardF4, KeyboardF5].into_iter().enumerate() {
+            layers[1].keys.insert((0, col + 1), Key(key));
+        }
+        for (col, key) in [KeyboardLeftArrow, KeyboardDownArrow, KeyboardUpArrow, KeyboardRightArrow].into_iter().enumerate() {
+            layers[2].keys.insert((2, col + 6), Key(key));
+        }
+        layers[2].keys.insert((1, 1), KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard1Exclamation });
+        let export = export_symbols(test_keyboard(), &layers);
+
+        assert_eq!(export.warnings, [
+            "[2, 13]: the empty key is read from the COMP key",
+            "[4, 3]: 'MO(1)' is read from the LSGT key",
+            "[4, 10]: 'MO(2)' is read from the TLDE key",
+            "[7, 6]: 'BKSP' is read from the PRSC key",
+            "[7, 8]: 'Enter' is read from the SCLK key",
+        ]);
+        assert_eq!(export.output, include_str!("testdata/layout_rs_dactyl_manuform_5x7"));
+    }
+}
@@ -0,0 +1,653 @@
+//! # ZMK
+//!
+//! `.keymap` import and export. ZMK keymaps are devicetree files: the node with
+//! `compatible = "zmk,keymap"` holds one child node per layer, whose `bindings` list a
+//! behavior per key in matrix order, e.g. `&kp A`, `&mt LCTRL ESC` or `&mo L_LOWER`.
+//!
+//! Exported layers are numbered by `#define`s so layer keys read as `&mo L_LOWER`; the `L_`
+//! prefix keeps a layer named like a key from redefining that key. Imports resolve layer
+//! names through the file's `#define`s, and keep key bindings whose name the file redefines
+//! as they are. Key names are parsed from their short
+//! (`ESC`) and long (`ESCAPE`) forms, shifted symbols (`EXCL`) and modifier functions
+//! (`LC(LS(Z))`).
+//!
+//! Mouse keys use the `&mkp`, `&mmv` and `&msc` behaviors, which need `CONFIG_ZMK_POINTING=y`.
+//! ZMK has no pointer speed keys, so the acceleration steps export as `&none`.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use crate::actions::{KeyAction, Modifiers, MouseAction};
+use crate::keyboards::KeyboardModel;
+use crate::keycodes::{ConsumerUsage, KeyboardUsage, NamingScheme};
+use crate::keymap::{Layer, UnmappedKeycode};
+use super::{
+    action_at, ascii_art, define_constants, identifier_layer_name, layer_identifiers, line_number, matrix_positions,
+    strip_comments, Conversion,
+};
+
+/// Identifies bindings kept from ZMK files in [`UnmappedKeycode::format`]
+pub const FORMAT: &str = "zmk";
+
+/// Mouse actions with their ZMK behavior and parameter, e.g. `&mmv MOVE_UP`
+const ZMK_MOUSE_BINDINGS: [(MouseAction, &str, &str); 13] = [
+    (MouseAction::Button1, "&mkp", "LCLK"),
+    (MouseAction::Button2, "&mkp", "RCLK"),
+    (MouseAction::Button3, "&mkp", "MCLK"),
+    (MouseAction::Button4, "&mkp", "MB4"),
+    (MouseAction::Button5, "&mkp", "MB5"),
+    (MouseAction::MoveUp, "&mmv", "MOVE_UP"),
+    (MouseAction::MoveDown, "&mmv", "MOVE_DOWN"),
+    (MouseAction::MoveLeft, "&mmv", "MOVE_LEFT"),
+    (MouseAction::MoveRight, "&mmv", "MOVE_RIGHT"),
+    (MouseAction::WheelUp, "&msc", "SCRL_UP"),
+    (MouseAction::WheelDown, "&msc", "SCRL_DOWN"),
+    (MouseAction::WheelLeft, "&msc", "SCRL_LEFT"),
+    (MouseAction::WheelRight, "&msc", "SCRL_RIGHT"),
+];
+
+/// Modifier bits with their ZMK modifier function (`LC(kc)`) and key
+const ZMK_MODIFIERS: [(Modifiers, &str, KeyboardUsage); 8] = [
+    (Modifiers::LEFT_CTRL, "LC", KeyboardUsage::KeyboardLeftControl),
+    (Modifiers::LEFT_SHIFT, "LS", KeyboardUsage::KeyboardLeftShift),
+    (Modifiers::LEFT_ALT, "LA", KeyboardUsage::KeyboardLeftAlt),
+    (Modifiers::LEFT_GUI, "LG", KeyboardUsage::KeyboardLeftGUI),
+    (Modifiers::RIGHT_CTRL, "RC", KeyboardUsage::KeyboardRightControl),
+    (Modifiers::RIGHT_SHIFT, "RS", KeyboardUsage::KeyboardRightShift),
+    (Modifiers::RIGHT_ALT, "RA", KeyboardUsage::KeyboardRightAlt),
+    (Modifiers::RIGHT_GUI, "RG", KeyboardUsage::KeyboardRightGUI),
+];
+
+/// ZMK names for shifted symbols, e.g. `EXCL` for Shift+1
+static ZMK_SHIFTED_KEYS: Lazy<HashMap<&'static str, KeyboardUsage>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        ("TILDE", KeyboardBacktickTilde),
+        ("EXCL", Keyboard1Exclamation),
+        ("EXCLAMATION", Keyboard1Exclamation),
+        ("AT", Keyboard2At),
+        ("AT_SIGN", Keyboard2At),
+        ("HASH", Keyboard3Hash),
+        ("POUND", Keyboard3Hash),
+        ("DLLR", Keyboard4Dollar),
+        ("DOLLAR", Keyboard4Dollar),
+        ("PRCNT", Keyboard5Percent),
+        ("PERCENT", Keyboard5Percent),
+        ("CARET", Keyboard6Caret),
+        ("AMPS", Keyboard7Ampersand),
+        ("AMPERSAND", Keyboard7Ampersand),
+        ("STAR", Keyboard8Asterisk),
+        ("ASTRK", Keyboard8Asterisk),
+        ("ASTERISK", Keyboard8Asterisk),
+        ("LPAR", Keyboard9OpenParens),
+        ("LEFT_PARENTHESIS", Keyboard9OpenParens),
+        ("RPAR", Keyboard0CloseParens),
+        ("RIGHT_PARENTHESIS", Keyboard0CloseParens),
+        ("UNDER", KeyboardDashUnderscore),
+        ("UNDERSCORE", KeyboardDashUnderscore),
+        ("PLUS", KeyboardEqualPlus),
+        ("LBRC", KeyboardOpenBracketBrace),
+        ("LEFT_BRACE", KeyboardOpenBracketBrace),
+        ("RBRC", KeyboardCloseBracketBrace),
+        ("RIGHT_BRACE", KeyboardCloseBracketBrace),
+        ("PIPE", KeyboardBackslashBar),
+        ("COLON", KeyboardSemiColon),
+        ("DQT", KeyboardSingleDoubleQuote),
+        ("DOUBLE_QUOTES", KeyboardSingleDoubleQuote),
+        ("LT", KeyboardCommaLess),
+        ("LESS_THAN", KeyboardCommaLess),
+        ("GT", KeyboardPeriodGreater),
+        ("GREATER_THAN", KeyboardPeriodGreater),
+        ("QMARK", KeyboardSlashQuestion),
+        ("QUESTION", KeyboardSlashQuestion),
+    ])
+});
+
+/// The ZMK key name of a usage, e.g. `ESC`
+pub fn key_name(usage: KeyboardUsage) -> Option<&'static str> {
+    usage.alias(NamingScheme::Zmk)
+}
+
+/// The usage of a ZMK key name, by its short or long form
+pub fn key_usage(name: &str) -> Option<KeyboardUsage> {
+    KeyboardUsage::from_alias(NamingScheme::Zmk, name)
+}
+
+/// Parse a `&kp` parameter such as `A`, `EXCL` or `LC(LS(Z))` into a key or modified key
+fn parse_key(expression: &str) -> Option<KeyAction> {
+    let expression = expression.trim();
+    if let Some(usage) = key_usage(expression) {
+        return Some(KeyAction::Key(usage));
+    }
+    if let Some(usage) = ConsumerUsage::from_alias(NamingScheme::Zmk, expression) {
+        return Some(KeyAction::Consumer(usage));
+    }
+    if let Some(&key) = ZMK_SHIFTED_KEYS.get(expression) {
+        return Some(KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key });
+    }
+
+    let (function, inner) = expression.strip_suffix(')')?.split_once('(')?;
+    let &(modifiers, _, _) = ZMK_MODIFIERS.iter().find(|&&(_, name, _)| name == function.trim())?;
+    match parse_key(inner)? {
+        KeyAction::Key(key) => Some(KeyAction::Modified { modifiers, key }),
+        KeyAction::Modified { modifiers: inner, key } => Some(KeyAction::Modified { modifiers: modifiers | inner, key }),
+        _ => None,
+    }
+}
+
+/// Parse the hold parameter of `&mt`, a modifier key optionally wrapped in more modifiers
+fn parse_hold(expression: &str) -> Option<Modifiers> {
+    let modifier = |usage: KeyboardUsage| ZMK_MODIFIERS.iter()
+        .find(|&&(_, _, key)| key == usage)
+        .map(|&(modifier, _, _)| modifier);
+    match parse_key(expression)? {
+        KeyAction::Key(key) => modifier(key),
+        KeyAction::Modified { modifiers, key } => Some(modifiers | modifier(key)?),
+        _ => None,
+    }
+}
+
+/// Parse one binding, split into its behavior and parameters, e.g. `["&lt", "1", "SPACE"]`
+fn parse_binding(binding: &[&str], layer: &dyn Fn(&str) -> Option<usize>) -> Option<KeyAction> {
+    let parse_layer = |param: &str| param.parse::<usize>().ok().or_else(|| layer(param));
+    if redefined_key(binding, layer).is_some() {
+        return None;
+    }
+    match *binding {
+        ["&kp", key] => parse_key(key),
+        ["&mt", hold, tap] => Some(KeyAction::ModTap { modifiers: parse_hold(hold)?, tap: key_usage(tap)? }),
+        ["&lt", param, tap] => Some(KeyAction::LayerTap { layer: parse_layer(param)?, tap: key_usage(tap)? }),
+        ["&mo", param] => Some(KeyAction::MomentaryLayer(parse_layer(param)?)),
+        ["&tog", param] => Some(KeyAction::ToggleLayer(parse_layer(param)?)),
+        ["&sl", param] => Some(KeyAction::OneShotLayer(parse_layer(param)?)),
+        ["&trans"] => Some(KeyAction::Transparent),
+        ["&none"] => Some(KeyAction::NoOp),
+        [behavior, param] => ZMK_MOUSE_BINDINGS.iter()
+            .find(|&&(_, other, name)| other == behavior && name == param)
+            .map(|&(mouse, _, _)| KeyAction::Mouse(mouse)),
+        _ => None,
+    }
+}
+
+/// A key name in a binding that the file `#define`s. The preprocessor replaces it before ZMK
+/// reads the key, so `#define HOME 1` turns `&kp HOME` into `&kp 1`.
+fn redefined_key<'a>(binding: &[&'a str], constant: &dyn Fn(&str) -> Option<usize>) -> Option<&'a str> {
+    let keys = match binding {
+        ["&kp", ..] | ["&mt", ..] => &binding[1..],
+        ["&lt", _, ..] => &binding[2..],
+        _ => &[],
+    };
+    keys.iter()
+        .flat_map(|key| key.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')))
+        .find(|word| !word.is_empty() && constant(word).is_some())
+}
+
+/// Name of the ZMK shield (or board) a model is built as, used for the file name
+fn zmk_shield(keyboard: &KeyboardModel) -> &str {
+    match keyboard.id.as_str() {
+        "ferris_sweep" => "cradio",
+        id => id,
+    }
+}
+
+/// Name of the downloaded file, e.g. `corne.keymap`
+pub fn file_name(keyboard: &KeyboardModel) -> String {
+    format!("{}.keymap", zmk_shield(keyboard))
+}
+
+/// Write the keymap as a ZMK `.keymap`
+pub fn export_keymap(keyboard: &KeyboardModel, layers: &[Layer]) -> Conversion<String> {
+    let identifiers = layer_identifiers(layers);
+    // Prefixed so a layer named like a key (`HOME`) cannot redefine that key's name
+    let defines: Vec<String> = identifiers.iter()
+        .map(|identifier| format!("L_{}", identifier))
+        .collect();
+    let mut warnings = Vec::new();
+    let quote = |value: &str| serde_json::Value::from(value).to_string();
+
+    let mut output = format!("/*\n * {} keymap exported by layout-rs\n */\n\n", keyboard.name);
+    output.push_str("#include <behaviors.dtsi>\n#include <dt-bindings/zmk/keys.h>\n");
+    if layers.iter().any(|layer| layer.keys.values().any(|action| matches!(action, KeyAction::Mouse(_)))) {
+        output.push_str("#include <dt-bindings/zmk/pointing.h>\n");
+    }
+    output.push('\n');
+    for (index, define) in defines.iter().enumerate() {
+        output.push_str(&format!("#define {} {}\n", define, index));
+    }
+
+    output.push_str("\n/ {\n    keymap {\n        compatible = \"zmk,keymap\";\n");
+    for (index, layer) in layers.iter().enumerate() {
+        output.push_str(&format!("\n        {}_layer {{\n", identifiers[index].to_lowercase()));
+        output.push_str(&format!("            display-name = {};\n", quote(&layer.name)));
+        for line in ascii_art(keyboard, layers, index) {
+            output.push_str(&format!("            // {}\n", line));
+        }
+
+        // Bindings are padded to a common width so the rows line up as a grid
+        let rows = layer_rows(keyboard, layers, index, &defines, &mut warnings);
+        let width = rows.iter().flatten().map(String::len).max().unwrap_or(0);
+        output.push_str("            bindings = <\n");
+        for row in &rows {
+            let cells: Vec<String> = row.iter()
+                .map(|binding| format!("{:<width$}", binding, width = width))
+                .collect();
+            output.push_str(format!("                {}", cells.join("  ")).trim_end());
+            output.push('\n');
+        }
+        output.push_str("            >;\n        };\n");
+    }
+    output.push_str("    };\n};\n");
+
+    Conversion { output, warnings }
+}
+
+/// ZMK bindings of one layer, grouped by matrix row
+fn layer_rows(
+    keyboard: &KeyboardModel,
+    layers: &[Layer],
+    index: usize,
+    identifiers: &[String],
+    warnings: &mut Vec<String>,
+) -> Vec<Vec<String>> {
+    let layer = &layers[index];
+    matrix_positions(keyboard)
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|row| {
+            row.iter()
+                .map(|&position| {
+                    let (binding, warning) = match layer.unmapped.get(&position) {
+                        Some(unmapped) if unmapped.format == FORMAT => (unmapped.keycode.clone(), None),
+                        Some(unmapped) => ("&none".to_string(), Some(format!(
+                            "{} keycode '{}' has no ZMK equivalent, exported as &none", unmapped.format, unmapped.keycode
+                        ))),
+                        None => action_binding(action_at(layers, index, position), identifiers),
+                    };
+                    if let Some(warning) = warning {
+                        warnings.push(format!("Layer '{}' [{}, {}]: {}", layer.name, position.0, position.1, warning));
+                    }
+                    binding
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// ZMK binding for an action, and a warning if it had to be approximated
+fn action_binding(action: KeyAction, layers: &[String]) -> (String, Option<String>) {
+    let layer_name = |layer: usize| layers.get(layer).cloned().unwrap_or_else(|| layer.to_string());
+    let name = |usage: KeyboardUsage| key_name(usage)
+        .ok_or_else(|| format!("ZMK has no key name for {:?}, exported as &none", usage));
+    // Wrap a key in the modifier functions of every modifier in the set
+    let wrap = |modifiers: Modifiers, key: &str| ZMK_MODIFIERS.iter().rev()
+        .filter(|&&(modifier, _, _)| modifiers.contains(modifier))
+        .fold(key.to_string(), |key, &(_, function, _)| format!("{}({})", function, key));
+
+    let binding = match action {
+        KeyAction::Key(usage) => name(usage).map(|key| format!("&kp {}", key)),
+        KeyAction::Consumer(usage) => usage.alias(NamingScheme::Zmk)
+            .map(|key| format!("&kp {}", key))
+            .ok_or_else(|| format!("ZMK has no key name for {:?}, exported as &none", usage)),
+        KeyAction::Mouse(mouse) => ZMK_MOUSE_BINDINGS.iter()
+            .find(|&&(other, _, _)| other == mouse)
+            .map(|&(_, behavior, param)| format!("{} {}", behavior, param))
+            .ok_or_else(|| format!("ZMK has no binding for {:?}, exported as &none", mouse)),
+        KeyAction::Modified { modifiers, key } => name(key).map(|key| format!("&kp {}", wrap(modifiers, key))),
+        KeyAction::ModTap { modifiers, tap } => name(tap).map(|tap| {
+            // The hold parameter is a modifier key, with any further modifiers wrapped around it
+            match ZMK_MODIFIERS.iter().find(|&&(modifier, _, _)| modifiers.contains(modifier)) {
+                Some(&(first, _, key)) => {
+                    let others = ZMK_MODIFIERS.iter()
+                        .filter(|&&(modifier, _, _)| modifier != first && modifiers.contains(modifier))
+                        .fold(Modifiers::NONE, |others, &(modifier, _, _)| others | modifier);
+                    format!("&mt {} {}", wrap(others, key_name(key).unwrap_or_default()), tap)
+                }
+                None => format!("&kp {}", tap),
+            }
+        }),
+        KeyAction::LayerTap { layer, tap } => name(tap).map(|tap| format!("&lt {} {}", layer_name(layer), tap)),
+        KeyAction::MomentaryLayer(layer) => Ok(format!("&mo {}", layer_name(layer))),
+        KeyAction::ToggleLayer(layer) => Ok(format!("&tog {}", layer_name(layer))),
+        KeyAction::OneShotLayer(layer) => Ok(format!("&sl {}", layer_name(layer))),
+        KeyAction::Transparent => Ok("&trans".to_string()),
+        KeyAction::NoOp => Ok("&none".to_string()),
+    };
+    match binding {
+        Ok(binding) => (binding, None),
+        Err(warning) => ("&none".to_string(), Some(warning)),
+    }
+}
+
+/// Read the layers of a ZMK `.keymap`, in the order of the keymap node's children.
+/// Bindings that no [`KeyAction`] can represent, such as `&bt BT_CLR`, are kept in
+/// [`Layer::unmapped`] and reported with their line number.
+pub fn import_keymap(keyboard: &KeyboardModel, source: &str) -> Result<Conversion<Vec<Layer>>, String> {
+    let code = strip_comments(source);
+    let line = |offset: usize| line_number(&code, offset);
+    let constants = define_constants(&code);
+    let resolve = |name: &str| constants.get(name).copied();
+
+    let positions = matrix_positions(keyboard);
+    let mut warnings = Vec::new();
+    let mut layers = Vec::new();
+
+    for (index, node) in layer_nodes(&code)?.into_iter().enumerate() {
+        let body = &code[node.body.0..node.body.1];
+        let name = ["display-name", "label"].iter()
+            .find_map(|&property| property_value(body, property))
+            .and_then(|(_, value)| serde_json::from_str::<String>(value).ok())
+            .unwrap_or_else(|| identifier_layer_name(node.name.trim_end_matches("_layer")));
+        let name = if name.is_empty() { format!("Layer {}", index) } else { name };
+        let mut layer = Layer::new(name);
+
+        let Some((offset, bindings)) = property_value(body, "bindings") else {
+            warnings.push(format!("Line {}: layer '{}' has no bindings", line(node.body.0), layer.name));
+            layers.push(layer);
+            continue;
+        };
+        let Some(bindings) = bindings.strip_prefix('<').and_then(|bindings| bindings.strip_suffix('>')) else {
+            return Err(format!("Line {}: bindings of layer '{}' are not a <...> list", line(node.body.0 + offset), layer.name));
+        };
+        let bindings = split_bindings(bindings, node.body.0 + offset + 1);
+
+        if bindings.len() != positions.len() {
+            warnings.push(format!(
+                "Line {}: layer '{}' has {} bindings, but {} has {} keys",
+                line(node.body.0 + offset), layer.name, bindings.len(), keyboard.name, positions.len()
+            ));
+        }
+        for (&position, (offset, binding)) in positions.iter().zip(bindings) {
+            match parse_binding(&binding, &resolve) {
+                Some(action) => {
+                    layer.keys.insert(position, action);
+                }
+                None => {
+                    let keycode = binding.join(" ");
+                    warnings.push(match redefined_key(&binding, &resolve) {
+                        Some(name) => format!(
+                            "Line {}: '{}' uses '{}', which the file #defines, and is kept as is", line(offset), keycode, name
+                        ),
+                        None => format!("Line {}: '{}' has no equivalent in the editor and is kept as is", line(offset), keycode),
+                    });
+                    layer.unmapped.insert(position, UnmappedKeycode { format: FORMAT.to_string(), keycode });
+                }
+            }
+        }
+        layers.push(layer);
+    }
+
+    if layers.is_empty() {
+        return Err("The keymap node has no layers".to_string());
+    }
+    Ok(Conversion { output: layers, warnings })
+}
+
+/// A layer node of the keymap: its node name and the byte range of its body
+struct LayerNode<'a> {
+    name: &'a str,
+    body: (usize, usize),
+}
+
+/// Child nodes of the node with `compatible = "zmk,keymap"`
+fn layer_nodes(code: &str) -> Result<Vec<LayerNode<'_>>, String> {
+    let compatible = code.find("\"zmk,keymap\"")
+        .ok_or("No keymap node with compatible = \"zmk,keymap\" found")?;
+
+    // The innermost brace left open before the compatible property opens the keymap node
+    let mut depth = 0;
+    let open = code[..compatible].char_indices().rev()
+        .find(|&(_, c)| {
+            match c {
+                '}' => depth += 1,
+                '{' if depth == 0 => return true,
+                '{' => depth -= 1,
+                _ => {}
+            }
+            false
+        })
+        .map(|(index, _)| index)
+        .ok_or("The keymap node is not opened")?;
+
+    let mut nodes = Vec::new();
+    let mut depth = 0;
+    let mut statement = open + 1;
+    let mut name = "";
+    for (index, c) in code[open + 1..].char_indices() {
+        let index = open + 1 + index;
+        match c {
+            '{' if depth == 0 => {
+                // The header may carry a label, as in `lower: lower_layer`
+                name = code[statement..index].trim().rsplit([':', ' ', '\t', '\n']).next().unwrap_or_default();
+                depth = 1;
+                statement = index + 1;
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return Ok(nodes),
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    nodes.push(LayerNode { name, body: (statement, index) });
+                }
+            }
+            ';' if depth == 0 => statement = index + 1,
+            _ => {}
+        }
+    }
+    Err(format!("Line {}: the keymap node is not closed", line_number(code, open)))
+}
+
+/// Value of a `name = value;` property directly in a node body, with its offset in the body
+fn property_value<'a>(body: &'a str, name: &str) -> Option<(usize, &'a str)> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == ',';
+    body.match_indices(name)
+        .map(|(index, _)| index)
+        .filter(|&index| !body[..index].ends_with(is_name))
+        .find_map(|index| {
+            let rest = &body[index + name.len()..];
+            let value = rest.trim_start().strip_prefix('=')?;
+            let end = value.find(';')?;
+            let trimmed = value[..end].trim_start();
+            let offset = body.len() - value.len() + (value[..end].len() - trimmed.len());
+            Some((offset, trimmed.trim_end()))
+        })
+}
+
+/// Split a bindings list into bindings, each a behavior followed by its parameters, with the
+/// offset of the behavior in the source. `base` is the offset of `bindings` itself.
+fn split_bindings(bindings: &str, base: usize) -> Vec<(usize, Vec<&str>)> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+    for (index, c) in bindings.char_indices().chain([(bindings.len(), ' ')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    tokens.push((start, &bindings[start..index]));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(index);
+    }
+
+    let mut grouped: Vec<(usize, Vec<&str>)> = Vec::new();
+    for (offset, token) in tokens {
+        match grouped.last_mut() {
+            Some((_, binding)) if !token.starts_with('&') => binding.push(token),
+            _ => grouped.push((base + offset, vec![token])),
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::test_keyboard;
+    use crate::keyboards;
+    use KeyboardUsage::*;
+
+    #[test]
+    fn test_key_names() {
+        assert_eq!(key_name(KeyboardEscape), Some("ESC"));
+        assert_eq!(key_name(Keyboard1Exclamation), Some("N1"));
+        assert_eq!(key_usage("ESCAPE"), Some(KeyboardEscape));
+        assert_eq!(key_usage("LSHFT"), Some(KeyboardLeftShift));
+        assert_eq!(key_name(Keypad00), None);
+        for usage in (0..=u8::MAX).map(KeyboardUsage::from) {
+            if let Some(name) = key_name(usage) {
+                assert_eq!(key_usage(name), Some(usage), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_action_bindings() {
+        let layers = ["BASE".to_string(), "LOWER".to_string()];
+        let binding = |action| action_binding(action, &layers).0;
+
+        assert_eq!(binding(KeyAction::Key(KeyboardSpacebar)), "&kp SPACE");
+        assert_eq!(binding(KeyAction::Consumer(ConsumerUsage::ScanNextTrack)), "&kp C_NEXT");
+        assert_eq!(binding(KeyAction::Mouse(MouseAction::Button2)), "&mkp RCLK");
+        assert_eq!(binding(KeyAction::Mouse(MouseAction::WheelDown)), "&msc SCRL_DOWN");
+        assert_eq!(binding(KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, key: KeyboardZz }), "&kp LC(LS(Z))");
+        assert_eq!(binding(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL, tap: KeyboardEscape }), "&mt LCTRL ESC");
+        assert_eq!(binding(KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa }), "&mt LS(LCTRL) A");
+        assert_eq!(binding(KeyAction::LayerTap { layer: 1, tap: KeyboardSpacebar }), "&lt LOWER SPACE");
+        assert_eq!(binding(KeyAction::MomentaryLayer(1)), "&mo LOWER");
+        assert_eq!(binding(KeyAction::ToggleLayer(5)), "&tog 5");
+        assert_eq!(binding(KeyAction::OneShotLayer(0)), "&sl BASE");
+        assert_eq!(binding(KeyAction::Transparent), "&trans");
+        assert_eq!(binding(KeyAction::NoOp), "&none");
+
+        let (binding, warning) = action_binding(KeyAction::Key(Keypad00), &layers);
+        assert_eq!(binding, "&none");
+        assert_eq!(warning.as_deref(), Some("ZMK has no key name for Keypad00, exported as &none"));
+    }
+
+    #[test]
+    fn test_export_keymap() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardF1));
+        let export = export_keymap(test_keyboard(), &layers);
+        let keymap = &export.output;
+
+        assert!(export.warnings.is_empty(), "{:?}", export.warnings);
+        assert!(keymap.contains("#include <dt-bindings/zmk/keys.h>\n\n"));
+        assert!(keymap.contains("#define L_BASE 0\n#define L_LOWER 1\n#define L_RAISE 2\n"));
+        assert!(keymap.contains("        compatible = \"zmk,keymap\";\n\n        base_layer {\n            display-name = \"Base\";\n"));
+        assert!(keymap.contains("            // | Esc   | 1     |"));
+        assert!(keymap.contains("            bindings = <\n                &kp ESC      &kp N1       &kp N2"));
+        assert!(keymap.contains("&mo L_LOWER"));
+        assert!(keymap.contains("                &trans  &kp F1  &trans"));
+        assert_eq!(keymap.matches("bindings = <").count(), 3);
+        assert_eq!(file_name(keyboards::find_keyboard("ferris_sweep").unwrap()), "cradio.keymap");
+    }
+
+    #[test]
+    fn test_import_keymap() {
+        let source = r#"#include <behaviors.dtsi>
+#include <dt-bindings/zmk/keys.h>
+#define NAV 1 // navigation
+
+/ {
+    combos { compatible = "zmk,combos"; };
+
+    keymap0: keymap {
+        compatible = "zmk,keymap";
+
+        default_layer {
+            bindings = <
+                &lt NAV ESC  &mt LS(LCTRL) A  &kp LC(Z)  /* bluetooth */ &bt BT_CLR
+                &kp EXCL  &none
+            >;
+            sensor-bindings = <&inc_dec_kp C_VOL_UP C_VOL_DN>;
+        };
+
+        nav {
+            display-name = "Navigation";
+            bindings = <&trans &tog 0 &sl NAV>;
+        };
+    };
+};
+"#;
+        let import = import_keymap(test_keyboard(), source).unwrap();
+        assert_eq!(import.warnings, [
+            "Line 12: layer 'Default' has 6 bindings, but Dactyl Manuform 5x7 has 74 keys",
+            "Line 13: '&bt BT_CLR' has no equivalent in the editor and is kept as is",
+            "Line 21: layer 'Navigation' has 3 bindings, but Dactyl Manuform 5x7 has 74 keys",
+        ]);
+
+        let layers = import.output;
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].name, "Default");
+        assert_eq!(layers[0].keys[&(0, 0)], KeyAction::LayerTap { layer: 1, tap: KeyboardEscape });
+        assert_eq!(layers[0].keys[&(0, 1)], KeyAction::ModTap { modifiers: Modifiers::LEFT_CTRL | Modifiers::LEFT_SHIFT, tap: KeyboardAa });
+        assert_eq!(layers[0].keys[&(0, 2)], KeyAction::Modified { modifiers: Modifiers::LEFT_CTRL, key: KeyboardZz });
+        assert_eq!(layers[0].unmapped[&(0, 3)].keycode, "&bt BT_CLR");
+        assert_eq!(layers[0].keys[&(0, 4)], KeyAction::Modified { modifiers: Modifiers::LEFT_SHIFT, key: Keyboard1Exclamation });
+        assert_eq!(layers[0].keys[&(0, 5)], KeyAction::NoOp);
+        assert_eq!(layers[1].keys[&(0, 0)], KeyAction::Transparent);
+        assert_eq!(layers[1].keys[&(0, 1)], KeyAction::ToggleLayer(0));
+        assert_eq!(layers[1].keys[&(0, 2)], KeyAction::OneShotLayer(1));
+    }
+
+    #[test]
+    fn test_keymap_round_trip() {
+        let mut layers = test_keyboard().default_layers.clone();
+        layers[0].keys.insert((0, 0), KeyAction::ModTap { modifiers: Modifiers::RIGHT_ALT, tap: KeyboardEscape });
+        layers[1].keys.insert((0, 1), KeyAction::Mouse(MouseAction::MoveLeft));
+        layers[1].name = "Nav \"Media\"".to_string();
+        layers[2].unmapped.insert((0, 2), UnmappedKeycode { format: FORMAT.to_string(), keycode: "&bt BT_SEL 0".to_string() });
+        let export = export_keymap(test_keyboard(), &layers);
+
+        assert!(export.output.contains("#include <dt-bindings/zmk/pointing.h>\n"));
+
+        let import = import_keymap(test_keyboard(), &export.output).unwrap();
+        assert_eq!(import.warnings.len(), 1, "{:?}", import.warnings);
+        assert!(import.warnings[0].ends_with("'&bt BT_SEL 0' has no equivalent in the editor and is kept as is"));
+        assert_eq!(import.output[1].name, "Nav \"Media\"");
+        assert_eq!(import.output[0].keys.get(&(0, 0)), layers[0].keys.get(&(0, 0)));
+        assert_eq!(import.output[1].keys.get(&(0, 1)), Some(&KeyAction::Mouse(MouseAction::MoveLeft)));
+        assert_eq!(export_keymap(test_keyboard(), &import.output).output, export.output);
+    }
+
+    #[test]
+    fn test_layer_named_like_a_key() {
+        let corne = keyboards::find_keyboard("corne").unwrap();
+        let mut layers = corne.default_layers.clone();
+        layers[1].name = "Home".to_string();
+        layers[1].keys.insert((0, 1), KeyAction::Key(KeyboardHome));
+        layers[0].keys.insert((0, 1), KeyAction::MomentaryLayer(1));
+        let export = export_keymap(corne, &layers);
+
+        assert!(export.output.contains("#define L_HOME 1\n"));
+        assert!(export.output.contains("&mo L_HOME"));
+        assert!(!export.output.contains("#define HOME"));
+        let import = import_keymap(corne, &export.output).unwrap();
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+        assert_eq!(import.output[1].keys[&(0, 1)], KeyAction::Key(KeyboardHome));
+        assert_eq!(import.output[0].keys[&(0, 1)], KeyAction::MomentaryLayer(1));
+
+        // ZMK would read this as `&kp 1`, so the binding is kept rather than read as Home
+        let clashing = "#define HOME 1\n/ { keymap { compatible = \"zmk,keymap\";\n base { bindings = <&kp HOME &kp LC(HOME) &mo HOME>; }; }; };";
+        let import = import_keymap(corne, clashing).unwrap();
+        assert_eq!(import.warnings[1..], [
+            "Line 3: '&kp HOME' uses 'HOME', which the file #defines, and is kept as is",
+            "Line 3: '&kp LC(HOME)' uses 'HOME', which the file #defines, and is kept as is",
+        ]);
+        assert_eq!(import.output[0].unmapped[&(0, 0)].keycode, "&kp HOME");
+        assert_eq!(import.output[0].keys[&(0, 2)], KeyAction::MomentaryLayer(1));
+    }
+
+    #[test]
+    fn test_import_keymap_errors() {
+        assert_eq!(import_keymap(test_keyboard(), "/ { };").unwrap_err(), "No keymap node with compatible = \"zmk,keymap\" found");
+        let unclosed = "/ {\n    keymap {\n        compatible = \"zmk,keymap\";\n        base { bindings = <&kp A>; };\n";
+        assert_eq!(import_keymap(test_keyboard(), unclosed).unwrap_err(), "Line 2: the keymap node is not closed");
+        let empty = "/ { keymap { compatible = \"zmk,keymap\"; }; };";
+        assert_eq!(import_keymap(test_keyboard(), empty).unwrap_err(), "The keymap node has no layers");
+    }
+}
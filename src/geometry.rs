@@ -0,0 +1,177 @@
+//! # Keyboard Geometry
+//!
+//! Physical description of a keyboard: where every key sits, how big it is and how it is
+//! rotated, together with the matrix position `(row, col)` used to address it in a keymap.
+//!
+//! All measurements are in key units, where `1.0` is the width of a standard key. `x` and `y`
+//! are the top-left corner of the unrotated key, and rotation is applied clockwise around
+//! the key's center.
+
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+
+/// Placement of a single key
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyGeometry {
+    pub row: usize,
+    pub col: usize,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Clockwise rotation in degrees around the key's center
+    pub rotation: f32,
+}
+
+impl KeyGeometry {
+    /// A standard 1u key without rotation
+    pub fn new(row: usize, col: usize, x: f32, y: f32) -> Self {
+        Self { row, col, x, y, width: 1.0, height: 1.0, rotation: 0.0 }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Rotate the key clockwise by `angle` degrees around `origin`
+    pub fn rotated_about(mut self, origin: (f32, f32), angle: f32) -> Self {
+        let (cx, cy) = rotate_point(self.center(), origin, angle);
+        self.x = cx - self.width / 2.0;
+        self.y = cy - self.height / 2.0;
+        self.rotation += angle;
+        self
+    }
+
+    /// Mirror the key horizontally inside a board of the given width and give it a new
+    /// matrix position, e.g. to build the right half of a split keyboard from the left one
+    pub fn mirrored(&self, board_width: f32, (row, col): (usize, usize)) -> Self {
+        Self {
+            row,
+            col,
+            x: board_width - self.x - self.width,
+            rotation: -self.rotation,
+            ..self.clone()
+        }
+    }
+
+    /// Corners of the key after rotation
+    fn corners(&self) -> [(f32, f32); 4] {
+        let center = self.center();
+        [
+            (self.x, self.y),
+            (self.x + self.width, self.y),
+            (self.x + self.width, self.y + self.height),
+            (self.x, self.y + self.height),
+        ]
+        .map(|corner| rotate_point(corner, center, self.rotation))
+    }
+}
+
+/// Rotate `point` clockwise (in screen coordinates) by `angle` degrees around `origin`
+fn rotate_point(point: (f32, f32), origin: (f32, f32), angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (dx, dy) = (point.0 - origin.0, point.1 - origin.1);
+    (origin.0 + dx * cos - dy * sin, origin.1 + dx * sin + dy * cos)
+}
+
+/// Complete physical layout of a keyboard
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardGeometry {
+    pub name: String,
+    keys: Vec<KeyGeometry>,
+}
+
+impl KeyboardGeometry {
+    /// Create a geometry, rejecting keys that share a matrix position
+    pub fn new(name: impl Into<String>, keys: Vec<KeyGeometry>) -> Result<Self, String> {
+        let mut seen = HashSet::new();
+        for key in &keys {
+            if !seen.insert(key.position()) {
+                return Err(format!("Duplicate matrix position {:?}", key.position()));
+            }
+        }
+        Ok(Self { name: name.into(), keys })
+    }
+
+    pub fn keys(&self) -> &[KeyGeometry] {
+        &self.keys
+    }
+
+    pub fn contains(&self, position: (usize, usize)) -> bool {
+        self.keys.iter().any(|key| key.position() == position)
+    }
+
+    /// Bounding box `(min_x, min_y, max_x, max_y)` of all keys, including rotation
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        self.keys.iter()
+            .flat_map(|key| key.corners())
+            .fold(
+                (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                |(min_x, min_y, max_x, max_y), (x, y)| (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+            )
+    }
+
+    /// Check that every position of a keymap exists on this keyboard.
+    /// Returns the sorted list of positions that do not.
+    pub fn validate_keymap<T>(&self, keymap: &HashMap<(usize, usize), T>) -> Result<(), Vec<(usize, usize)>> {
+        let mut unknown: Vec<(usize, usize)> = keymap.keys()
+            .copied()
+            .filter(|&position| !self.contains(position))
+            .collect();
+
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            unknown.sort();
+            Err(unknown)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_positions_are_rejected() {
+        let keys = vec![KeyGeometry::new(0, 0, 0.0, 0.0), KeyGeometry::new(0, 0, 1.0, 0.0)];
+        assert!(KeyboardGeometry::new("Broken", keys).is_err());
+    }
+
+    #[test]
+    fn test_rotated_about() {
+        let key = KeyGeometry::new(0, 0, 0.0, 0.0).rotated_about((0.0, 0.0), 90.0);
+        let (cx, cy) = key.center();
+        assert!((cx + 0.5).abs() < 1e-5);
+        assert!((cy - 0.5).abs() < 1e-5);
+        assert_eq!(key.rotation, 90.0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let keys = vec![KeyGeometry::new(0, 0, 0.0, 0.0), KeyGeometry { width: 2.0, ..KeyGeometry::new(0, 1, 1.0, 1.0) }];
+        let geometry = KeyboardGeometry::new("Test", keys).unwrap();
+        assert_eq!(geometry.bounds(), (0.0, 0.0, 3.0, 2.0));
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let key = KeyGeometry { rotation: 15.0, ..KeyGeometry::new(0, 0, 1.0, 2.0) };
+        let mirrored = key.mirrored(10.0, (0, 9));
+        assert_eq!(mirrored.position(), (0, 9));
+        assert_eq!((mirrored.x, mirrored.y), (8.0, 2.0));
+        assert_eq!(mirrored.rotation, -15.0);
+    }
+
+    #[test]
+    fn test_validate_keymap() {
+        let keys = vec![KeyGeometry::new(0, 0, 0.0, 0.0), KeyGeometry::new(0, 1, 1.0, 0.0)];
+        let geometry = KeyboardGeometry::new("Test", keys).unwrap();
+        let keymap: HashMap<(usize, usize), ()> = [((0, 0), ()), ((9, 9), ()), ((1, 0), ())].into_iter().collect();
+        assert_eq!(geometry.validate_keymap(&keymap), Err(vec![(1, 0), (9, 9)]));
+    }
+}
@@ -0,0 +1,576 @@
+//! # Keyboard Catalogue
+//!
+//! Built-in keyboard models the editor can switch between. Each model brings its own
+//! [`KeyboardGeometry`], default layers and storage key, so layouts for different boards
+//! are saved and exported separately. Split models also know the [`KeyMatrix`] their QMK
+//! firmware scans, which VIA and Vial files are laid out in, and models with a QMK
+//! keyboard definition know its USB vendor and product ID.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use crate::actions::KeyAction;
+use crate::keymap::Layer;
+use crate::geometry::{KeyGeometry, KeyboardGeometry};
+use crate::keycodes::KeyboardUsage;
+
+/// Identifier of the model used when nothing else has been selected
+pub const DEFAULT_KEYBOARD_ID: &str = "dactyl_manuform_5x7";
+
+/// A keyboard the editor knows how to display and configure
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyboardModel {
+    /// Stable identifier, used in storage and exports
+    pub id: String,
+    pub name: String,
+    /// localStorage key holding this model's saved layout
+    pub storage_key: String,
+    pub geometry: KeyboardGeometry,
+    /// Default layers, base layer first
+    pub default_layers: Vec<Layer>,
+    /// Switch matrix of the firmware
+    pub matrix: KeyMatrix,
+    /// USB vendor and product ID of the QMK firmware, when the board has a standard one
+    pub usb_id: Option<(u16, u16)>,
+}
+
+impl KeyboardModel {
+    /// A model with the given base layer and empty `Lower` and `Raise` layers
+    pub fn new(id: &str, name: &str, geometry: KeyboardGeometry, base: HashMap<(usize, usize), KeyAction>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            storage_key: format!("{}_keymap", id),
+            matrix: KeyMatrix::from_geometry(&geometry),
+            usb_id: None,
+            geometry,
+            default_layers: vec![
+                Layer { keys: base, ..Layer::new("Base") },
+                Layer::new("Lower"),
+                Layer::new("Raise"),
+            ],
+        }
+    }
+
+    /// The model with the switch matrix its firmware scans, placing every key with `position`
+    fn with_matrix(mut self, size: (usize, usize), position: impl Fn((usize, usize)) -> (usize, usize)) -> Self {
+        self.matrix = KeyMatrix::new(&self.geometry, size, position);
+        self
+    }
+
+    /// The model with the USB vendor and product ID its QMK firmware reports
+    fn with_usb_id(mut self, vendor: u16, product: u16) -> Self {
+        self.usb_id = Some((vendor, product));
+        self
+    }
+}
+
+/// Rows and columns a firmware scans, and where each key sits in them. Matrix positions can
+/// differ from the editor's: split halves are scanned as one tall matrix, and thumb keys are
+/// wired into the rows and columns of their half.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    /// Matrix position of each key position
+    positions: HashMap<(usize, usize), (usize, usize)>,
+}
+
+impl KeyMatrix {
+    /// Matrix placing every key of the geometry with `position`
+    pub fn new(
+        geometry: &KeyboardGeometry,
+        (rows, cols): (usize, usize),
+        position: impl Fn((usize, usize)) -> (usize, usize),
+    ) -> Self {
+        let positions = geometry.keys().iter()
+            .map(|key| (key.position(), position(key.position())))
+            .collect();
+        Self { rows, cols, positions }
+    }
+
+    /// Matrix laid out like the editor's grid, for boards whose wiring is not known
+    pub fn from_geometry(geometry: &KeyboardGeometry) -> Self {
+        let size = geometry.keys().iter()
+            .map(|key| key.position())
+            .fold((0, 0), |(rows, cols), (row, col)| (rows.max(row + 1), cols.max(col + 1)));
+        Self::new(geometry, size, |position| position)
+    }
+
+    /// Matrix position of a key
+    pub fn position(&self, key: (usize, usize)) -> Option<(usize, usize)> {
+        self.positions.get(&key).copied()
+    }
+
+    /// Key at a matrix position, if one is wired there
+    pub fn key_at(&self, position: (usize, usize)) -> Option<(usize, usize)> {
+        self.positions.iter()
+            .find(|&(_, &matrix)| matrix == position)
+            .map(|(&key, _)| key)
+    }
+}
+
+static KEYBOARD_MODELS: Lazy<Vec<KeyboardModel>> = Lazy::new(|| {
+    vec![
+        KeyboardModel {
+            // Saved before the catalogue existed, so keep the original storage key
+            storage_key: "dactyl_keymap".to_string(),
+            ..KeyboardModel::new(DEFAULT_KEYBOARD_ID, "Dactyl Manuform 5x7", dactyl_manuform_5x7_geometry(), DACTYL_5X7_KEYMAP.clone())
+        }.with_matrix((12, 7), dactyl_manuform_5x7_matrix).with_usb_id(0x444D, 0x3537),
+        KeyboardModel::new("dactyl_manuform_5x6", "Dactyl Manuform 5x6", dactyl_manuform_geometry("Dactyl Manuform 5x6", 4), layer_from_rows(&[
+            (0, 0, &["Esc", "1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "BKSP"]),
+            (1, 0, &["Tab", "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "-"]),
+            (2, 0, &["L Shift", "A", "S", "D", "F", "G", "H", "J", "K", "L", ";", "'"]),
+            (3, 0, &["L Ctrl", "Z", "X", "C", "V", "B", "N", "M", ",", ".", "/", "\\"]),
+            (4, 2, &["[", "]"]),
+            (4, 8, &["=", "`"]),
+            (5, 4, &["Space", "MO(1)"]),
+            (5, 6, &["MO(2)", "Enter"]),
+            (6, 4, &["Home", "End"]),
+            (6, 6, &["PgUp", "PgDn"]),
+            (7, 4, &["L GUI", "L Alt"]),
+            (7, 6, &["R Alt", "R GUI"]),
+        ])).with_matrix((12, 6), |key| dactyl_manuform_matrix(key, 4)).with_usb_id(0x444D, 0x3536),
+        KeyboardModel::new("dactyl_manuform_4x6", "Dactyl Manuform 4x6", dactyl_manuform_geometry("Dactyl Manuform 4x6", 3), layer_from_rows(&[
+            (0, 0, &["Tab", "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "BKSP"]),
+            (1, 0, &["L Shift", "A", "S", "D", "F", "G", "H", "J", "K", "L", ";", "'"]),
+            (2, 0, &["L Ctrl", "Z", "X", "C", "V", "B", "N", "M", ",", ".", "/", "\\"]),
+            (3, 2, &["[", "]"]),
+            (3, 8, &["-", "="]),
+            (4, 4, &["Space", "MO(1)"]),
+            (4, 6, &["MO(2)", "Enter"]),
+            (5, 4, &["Esc", "Del"]),
+            (5, 6, &["Ins", "`"]),
+            (6, 4, &["L GUI", "L Alt"]),
+            (6, 6, &["R Alt", "R GUI"]),
+        ])).with_matrix((10, 6), |key| dactyl_manuform_matrix(key, 3)).with_usb_id(0x444D, 0x3436),
+        KeyboardModel::new("corne", "Corne (crkbd)", corne_geometry(), layer_from_rows(&[
+            (0, 0, &["Tab", "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "BKSP"]),
+            (1, 0, &["L Ctrl", "A", "S", "D", "F", "G", "H", "J", "K", "L", ";", "'"]),
+            (2, 0, &["L Shift", "Z", "X", "C", "V", "B", "N", "M", ",", ".", "/", "Esc"]),
+            (3, 3, &["L GUI", "MO(1)", "Space", "Enter", "MO(2)", "R Alt"]),
+        ])).with_matrix((8, 6), |key| mirrored_matrix(key, 4, 11)).with_usb_id(0x4653, 0x0001),
+        KeyboardModel::new("ferris_sweep", "Ferris Sweep", ferris_sweep_geometry(), layer_from_rows(&[
+            (0, 0, &["Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P"]),
+            (1, 0, &["A", "S", "D", "F", "G", "H", "J", "K", "L", ";"]),
+            (2, 0, &["Z", "X", "C", "V", "B", "N", "M", ",", ".", "/"]),
+            (3, 3, &["MO(1)", "Space", "BKSP", "MO(2)"]),
+        ])).with_matrix((8, 5), |key| mirrored_matrix(key, 4, 9)),
+        KeyboardModel::new("kinesis_advantage", "Kinesis Advantage", kinesis_advantage_geometry(), layer_from_rows(&[
+            (0, 0, &["=", "1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "-"]),
+            (1, 0, &["Tab", "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "\\"]),
+            (2, 0, &["Esc", "A", "S", "D", "F", "G", "H", "J", "K", "L", ";", "'"]),
+            (3, 0, &["L Shift", "Z", "X", "C", "V", "B", "N", "M", ",", ".", "/", "R Shift"]),
+            (4, 1, &["`", "Ins", "←", "→"]),
+            (4, 7, &["↑", "↓", "[", "]"]),
+            (5, 4, &["L Ctrl", "L Alt", "R GUI", "R Ctrl"]),
+            (6, 5, &["Home", "PgUp"]),
+            (7, 3, &["BKSP", "Del", "End", "PgDn", "Enter", "Space"]),
+        ])),
+    ]
+});
+
+/// All built-in keyboard models, in picker order
+pub fn keyboard_models() -> &'static [KeyboardModel] {
+    &KEYBOARD_MODELS
+}
+
+pub fn find_keyboard(id: &str) -> Option<&'static KeyboardModel> {
+    KEYBOARD_MODELS.iter().find(|model| model.id == id)
+}
+
+pub fn default_keyboard() -> &'static KeyboardModel {
+    find_keyboard(DEFAULT_KEYBOARD_ID).expect("default keyboard is in the catalogue")
+}
+
+/// Build a layer from runs of key labels: `(row, first column, labels)`
+fn layer_from_rows(rows: &[(usize, usize, &[&str])]) -> HashMap<(usize, usize), KeyAction> {
+    rows.iter()
+        .flat_map(|&(row, start_col, labels)| {
+            labels.iter().enumerate().map(move |(offset, label)| {
+                let action = label.parse().unwrap_or_else(|e| panic!("Invalid default key '{}': {}", label, e));
+                ((row, start_col + offset), action)
+            })
+        })
+        .collect()
+}
+
+/// Mirror the left half of a split keyboard to build the right half, with the right half's
+/// columns numbered from the outside in: `last_col - col`
+fn mirror_halves(left: Vec<KeyGeometry>, board_width: f32, last_col: usize) -> Vec<KeyGeometry> {
+    let right: Vec<KeyGeometry> = left.iter()
+        .map(|key| key.mirrored(board_width, (key.row, last_col - key.col)))
+        .collect();
+    left.into_iter().chain(right).collect()
+}
+
+/// Dactyl Manuform with 6 columns per half: `full_rows` full rows, a 2-key bottom row and a
+/// 6-key thumb cluster
+fn dactyl_manuform_geometry(name: &str, full_rows: usize) -> KeyboardGeometry {
+    let mut left = Vec::new();
+    for row in 0..full_rows {
+        for col in 0..6 {
+            left.push(KeyGeometry::new(row, col, col as f32, row as f32));
+        }
+    }
+    for col in 2..4 {
+        left.push(KeyGeometry::new(full_rows, col, col as f32, full_rows as f32));
+    }
+
+    let thumb_row = full_rows + 1;
+    let origin = (3.5, thumb_row as f32 + 0.5);
+    let thumb = [(0, 4, 0.0, 0.0), (0, 5, 1.0, 0.0), (1, 4, 1.0, 1.0), (1, 5, 2.0, 1.0), (2, 4, 1.0, 2.0), (2, 5, 2.0, 2.0)];
+    for (row, col, dx, dy) in thumb {
+        let key = KeyGeometry::new(thumb_row + row, col, origin.0 + dx, origin.1 + dy);
+        left.push(key.rotated_about(origin, 15.0));
+    }
+
+    KeyboardGeometry::new(name, mirror_halves(left, 14.0, 11)).expect("Dactyl Manuform geometry has unique positions")
+}
+
+/// QMK matrix position of a Dactyl Manuform 5x6 or 4x6 key: each half scans `full_rows + 2`
+/// rows of 6 columns, right half last. The upper thumb pair shares the bottom row and the
+/// other four thumb keys take the row below.
+fn dactyl_manuform_matrix((row, col): (usize, usize), full_rows: usize) -> (usize, usize) {
+    let (first_row, col, right) = match col {
+        0..6 => (0, col, false),
+        col => (full_rows + 2, col - 6, true),
+    };
+    let (row, col) = match row.checked_sub(full_rows + 1) {
+        Some(0) => (full_rows, col),
+        Some(1) => (full_rows + 1, col),
+        Some(_) if right => (full_rows + 1, col + 2),
+        Some(_) => (full_rows + 1, col - 2),
+        None => (row, col),
+    };
+    (first_row + row, col)
+}
+
+/// QMK matrix position of a key on a split board whose halves are wired alike: the right
+/// half scans the `half_rows` rows after the left half's, with columns counted from its
+/// outer edge like the left half's
+fn mirrored_matrix((row, col): (usize, usize), half_rows: usize, last_col: usize) -> (usize, usize) {
+    match col {
+        col if 2 * col > last_col => (half_rows + row, last_col - col),
+        col => (row, col),
+    }
+}
+
+/// Corne: 3x6 column-staggered halves with 3 thumb keys each
+fn corne_geometry() -> KeyboardGeometry {
+    let stagger = [0.4, 0.4, 0.15, 0.0, 0.15, 0.25];
+    let mut left = Vec::new();
+    for row in 0..3 {
+        for (col, offset) in stagger.iter().enumerate() {
+            left.push(KeyGeometry::new(row, col, col as f32, row as f32 + offset));
+        }
+    }
+    left.push(KeyGeometry::new(3, 3, 3.5, 3.4));
+    left.push(KeyGeometry::new(3, 4, 4.5, 3.4));
+    left.push(KeyGeometry { height: 1.5, ..KeyGeometry::new(3, 5, 5.5, 3.2) }.rotated_about((5.5, 3.2), 15.0));
+
+    KeyboardGeometry::new("Corne (crkbd)", mirror_halves(left, 13.5, 11)).expect("Corne geometry has unique positions")
+}
+
+/// Ferris Sweep: 3x5 column-staggered halves with 2 thumb keys each
+fn ferris_sweep_geometry() -> KeyboardGeometry {
+    let stagger = [0.6, 0.2, 0.0, 0.2, 0.35];
+    let mut left = Vec::new();
+    for row in 0..3 {
+        for (col, offset) in stagger.iter().enumerate() {
+            left.push(KeyGeometry::new(row, col, col as f32, row as f32 + offset));
+        }
+    }
+    left.push(KeyGeometry::new(3, 3, 3.3, 3.4));
+    left.push(KeyGeometry::new(3, 4, 4.4, 3.5).rotated_about((4.4, 3.5), 15.0));
+
+    KeyboardGeometry::new("Ferris Sweep", mirror_halves(left, 12.0, 9)).expect("Ferris Sweep geometry has unique positions")
+}
+
+/// Kinesis Advantage: two concave 6-column key wells with a thumb cluster that includes
+/// two 2u keys per side
+fn kinesis_advantage_geometry() -> KeyboardGeometry {
+    let well = [0.2, 0.2, 0.1, 0.0, 0.1, 0.15];
+    let mut left = Vec::new();
+    for row in 0..4 {
+        for (col, offset) in well.iter().enumerate() {
+            left.push(KeyGeometry::new(row, col, col as f32, row as f32 + offset));
+        }
+    }
+    for (col, offset) in well.iter().enumerate().take(5).skip(1) {
+        left.push(KeyGeometry::new(4, col, col as f32, 4.0 + offset));
+    }
+
+    let origin = (5.5, 4.5);
+    let thumb = [
+        KeyGeometry::new(5, 4, 1.0, 0.0),
+        KeyGeometry::new(5, 5, 2.0, 0.0),
+        KeyGeometry::new(6, 5, 2.0, 1.0),
+        KeyGeometry { height: 2.0, ..KeyGeometry::new(7, 3, 0.0, 1.0) },
+        KeyGeometry { height: 2.0, ..KeyGeometry::new(7, 4, 1.0, 1.0) },
+        KeyGeometry::new(7, 5, 2.0, 2.0),
+    ];
+    for key in thumb {
+        let key = KeyGeometry { x: origin.0 + key.x, y: origin.1 + key.y, ..key };
+        left.push(key.rotated_about(origin, 20.0));
+    }
+
+    KeyboardGeometry::new("Kinesis Advantage", mirror_halves(left, 17.0, 11)).expect("Kinesis Advantage geometry has unique positions")
+}
+
+/// Default base layer of the Dactyl Manuform 5x7
+static DACTYL_5X7_KEYMAP: Lazy<HashMap<(usize, usize), KeyAction>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+    
+    let mut map = HashMap::new();
+    
+    // Left hand regular keys
+    // Row 0 (cols 0-6)
+    map.insert((0, 0), KeyboardEscape);
+    map.insert((0, 1), Keyboard1Exclamation);
+    map.insert((0, 2), Keyboard2At);
+    map.insert((0, 3), Keyboard3Hash);
+    map.insert((0, 4), Keyboard4Dollar);
+    map.insert((0, 5), Keyboard5Percent);
+    map.insert((0, 6), Keyboard6Caret);
+    
+    // Row 1 (cols 0-6)
+    map.insert((1, 0), KeyboardTab);
+    map.insert((1, 1), KeyboardQq);
+    map.insert((1, 2), KeyboardWw);
+    map.insert((1, 3), KeyboardEe);
+    map.insert((1, 4), KeyboardRr);
+    map.insert((1, 5), KeyboardTt);
+    map.insert((1, 6), KeyboardYy);
+    
+    // Row 2 (cols 0-6)
+    map.insert((2, 0), KeyboardCapsLock);
+    map.insert((2, 1), KeyboardAa);
+    map.insert((2, 2), KeyboardSs);
+    map.insert((2, 3), KeyboardDd);
+    map.insert((2, 4), KeyboardFf);
+    map.insert((2, 5), KeyboardGg);
+    map.insert((2, 6), KeyboardHh);
+    
+    // Row 3 (cols 0-5)
+    map.insert((3, 0), KeyboardLeftShift);
+    map.insert((3, 1), KeyboardZz);
+    map.insert((3, 2), KeyboardXx);
+    map.insert((3, 3), KeyboardCc);
+    map.insert((3, 4), KeyboardVv);
+    map.insert((3, 5), KeyboardBb);
+    
+    // Row 4 (cols 0-3)
+    map.insert((4, 0), KeyboardLeftControl);
+    map.insert((4, 1), KeyboardLeftAlt);
+    map.insert((4, 2), KeyboardLeftGUI);
+    
+    // Right hand regular keys
+    // Row 0 (cols 7-13)
+    map.insert((0, 7), Keyboard7Ampersand);
+    map.insert((0, 8), Keyboard8Asterisk);
+    map.insert((0, 9), Keyboard9OpenParens);
+    map.insert((0, 10), Keyboard0CloseParens);
+    map.insert((0, 11), KeyboardDashUnderscore);
+    map.insert((0, 12), KeyboardEqualPlus);
+    map.insert((0, 13), KeyboardBackspace);
+    
+    // Row 1 (cols 7-13)
+    map.insert((1, 7), KeyboardUu);
+    map.insert((1, 8), KeyboardIi);
+    map.insert((1, 9), KeyboardOo);
+    map.insert((1, 10), KeyboardPp);
+    map.insert((1, 11), KeyboardOpenBracketBrace);
+    map.insert((1, 12), KeyboardCloseBracketBrace);
+    map.insert((1, 13), KeyboardBackslashBar);
+    
+    // Row 2 (cols 7-13)
+    map.insert((2, 7), KeyboardJj);
+    map.insert((2, 8), KeyboardKk);
+    map.insert((2, 9), KeyboardLl);
+    map.insert((2, 10), KeyboardSemiColon);
+    map.insert((2, 11), KeyboardSingleDoubleQuote);
+    map.insert((2, 12), KeyboardEnter);
+    
+    // Row 3 (cols 8-13)
+    map.insert((3, 8), KeyboardNn);
+    map.insert((3, 9), KeyboardMm);
+    map.insert((3, 10), KeyboardCommaLess);
+    map.insert((3, 11), KeyboardPeriodGreater);
+    map.insert((3, 12), KeyboardSlashQuestion);
+    map.insert((3, 13), KeyboardRightShift);
+    
+    // Row 4 (cols 11-13)
+    map.insert((4, 11), KeyboardRightGUI);
+    map.insert((4, 12), KeyboardRightAlt);
+    map.insert((4, 13), KeyboardRightControl);
+    
+    // Left thumb cluster
+    // Row 5 (cols 5-6)
+    map.insert((5, 5), KeyboardHome);
+    map.insert((5, 6), KeyboardEnd);
+    
+    // Row 6 (cols 5-6)
+    map.insert((6, 5), KeyboardPageUp);
+    map.insert((6, 6), KeyboardPageDown);
+    
+    // Row 7 (cols 5-6)
+    map.insert((7, 5), KeyboardSpacebar);
+    map.insert((7, 6), KeyboardBackspace);
+    
+    // Right thumb cluster
+    // Row 5 (cols 8-9)
+    map.insert((5, 8), KeyboardLeftArrow);
+    map.insert((5, 9), KeyboardRightArrow);
+    
+    // Row 6 (cols 7-8)
+    map.insert((6, 7), KeyboardUpArrow);
+    map.insert((6, 8), KeyboardDownArrow);
+    
+    // Row 7 (cols 7-8)
+    map.insert((7, 7), KeyboardDelete);
+    map.insert((7, 8), KeyboardEnter);
+    
+    let mut map: HashMap<(usize, usize), KeyAction> = map.into_iter()
+        .map(|(position, usage)| (position, KeyAction::from(usage)))
+        .collect();
+
+    // Layer keys: Lower (left) and Raise (right)
+    map.insert((4, 3), KeyAction::MomentaryLayer(1));
+    map.insert((4, 10), KeyAction::MomentaryLayer(2));
+
+    // Unused position
+    map.insert((2, 13), KeyAction::NoOp);
+
+    map
+});
+
+/// Dactyl Manuform 5x7: two 7-column halves with 5 rows and a 6-key thumb cluster each
+fn dactyl_manuform_5x7_geometry() -> KeyboardGeometry {
+    let mut keys = Vec::new();
+
+    // Left hand: full rows 0-2, 6 keys on row 3, 4 keys on row 4
+    for (row, cols) in [(0, 0..7), (1, 0..7), (2, 0..7), (3, 0..6), (4, 0..4)] {
+        for col in cols {
+            keys.push(KeyGeometry::new(row, col, col as f32, row as f32));
+        }
+    }
+
+    // Right hand, with a 2u gap between the halves
+    for (row, cols) in [(0, 7..14), (1, 7..14), (2, 7..14), (3, 8..14), (4, 10..14)] {
+        for col in cols {
+            keys.push(KeyGeometry::new(row, col, col as f32 + 2.0, row as f32));
+        }
+    }
+
+    // Thumb clusters: three rows of two, angled in towards the center
+    let left_thumb = [(5, 5, 0.0, 0.0), (5, 6, 1.0, 0.0), (6, 5, 1.0, 1.0), (6, 6, 2.0, 1.0), (7, 5, 1.0, 2.0), (7, 6, 2.0, 2.0)];
+    let left_origin = (4.5, 5.5);
+    for (row, col, dx, dy) in left_thumb {
+        let key = KeyGeometry::new(row, col, left_origin.0 + dx, left_origin.1 + dy);
+        keys.push(key.rotated_about(left_origin, 15.0));
+    }
+
+    let right_thumb = [(5, 8, 1.0, 0.0), (5, 9, 2.0, 0.0), (6, 7, 0.0, 1.0), (6, 8, 1.0, 1.0), (7, 7, 0.0, 2.0), (7, 8, 1.0, 2.0)];
+    let right_origin = (11.5, 5.5);
+    for (row, col, dx, dy) in right_thumb {
+        let key = KeyGeometry::new(row, col, right_origin.0 - 3.0 + dx, right_origin.1 + dy);
+        keys.push(key.rotated_about(right_origin, -15.0));
+    }
+
+    KeyboardGeometry::new("Dactyl Manuform 5x7", keys).expect("Dactyl Manuform 5x7 geometry has unique positions")
+}
+
+/// QMK matrix position of a Dactyl Manuform 5x7 key: 6 rows of 7 columns per half, right
+/// half last. The upper thumb pair shares the bottom row and the other four thumb keys take
+/// an extra row.
+fn dactyl_manuform_5x7_matrix((row, col): (usize, usize)) -> (usize, usize) {
+    match (row, col) {
+        (5, 5..=6) => (4, col - 1),
+        (6, 5..=6) => (5, col - 1),
+        (7, 5..=6) => (5, col - 3),
+        (5, 8..=9) => (10, col - 7),
+        (6, 7..=8) => (11, col - 6),
+        (7, 7..=8) => (11, col - 4),
+        (row, 0..=6) => (row, col),
+        (row, col) => (row + 6, col - 7),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dactyl_manuform_5x7_key_count() {
+        let geometry = &default_keyboard().geometry;
+        // (7 + 7 + 7 + 6 + 4) keys and 6 thumb keys per half
+        assert_eq!(geometry.keys().len(), 2 * (31 + 6));
+        assert!(geometry.contains((0, 0)));
+        assert!(geometry.contains((7, 8)));
+        assert!(!geometry.contains((3, 6)));
+        assert!(!geometry.contains((4, 5)));
+    }
+
+    #[test]
+    fn test_catalogue_key_counts() {
+        let counts: Vec<(&str, usize)> = keyboard_models().iter()
+            .map(|model| (model.id.as_str(), model.geometry.keys().len()))
+            .collect();
+        assert_eq!(counts, [
+            ("dactyl_manuform_5x7", 74),
+            ("dactyl_manuform_5x6", 64),
+            ("dactyl_manuform_4x6", 52),
+            ("corne", 42),
+            ("ferris_sweep", 34),
+            ("kinesis_advantage", 68),
+        ]);
+    }
+
+    #[test]
+    fn test_default_keymaps_match_geometry() {
+        for model in keyboard_models() {
+            let base = &model.default_layers[0].keys;
+            assert_eq!(model.geometry.validate_keymap(base), Ok(()), "{}", model.name);
+
+            // Every physical key has a default assignment
+            for key in model.geometry.keys() {
+                assert!(base.contains_key(&key.position()), "{}: no default for {:?}", model.name, key.position());
+            }
+        }
+    }
+
+    #[test]
+    fn test_matrices_place_every_key_once() {
+        for model in keyboard_models() {
+            let mut positions: Vec<(usize, usize)> = model.geometry.keys().iter()
+                .map(|key| model.matrix.position(key.position()).unwrap())
+                .collect();
+            assert!(positions.iter().all(|&(row, col)| row < model.matrix.rows && col < model.matrix.cols), "{}", model.name);
+            positions.sort();
+            positions.dedup();
+            assert_eq!(positions.len(), model.geometry.keys().len(), "{}", model.name);
+        }
+
+        // QMK scans the split 5x7 as 12 rows of 7, the right half's rows after the left's
+        let matrix = &default_keyboard().matrix;
+        assert_eq!((matrix.rows, matrix.cols), (12, 7));
+        assert_eq!(matrix.position((0, 7)), Some((6, 0)));
+        assert_eq!(matrix.position((7, 6)), Some((5, 3)));
+        assert_eq!(matrix.key_at((11, 4)), Some((7, 8)));
+        assert_eq!(matrix.key_at((5, 0)), None);
+
+        let corne = &find_keyboard("corne").unwrap().matrix;
+        assert_eq!((corne.rows, corne.cols), (8, 6));
+        assert_eq!(corne.position((0, 11)), Some((4, 0)));
+        assert_eq!(corne.position((3, 6)), Some((7, 5)));
+    }
+
+    #[test]
+    fn test_storage_keys_are_unique() {
+        let mut keys: Vec<&str> = keyboard_models().iter().map(|model| model.storage_key.as_str()).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), keyboard_models().len());
+        assert_eq!(default_keyboard().storage_key, "dactyl_keymap");
+    }
+}
@@ -35,7 +35,9 @@
 
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
-use serde::{Serialize, Deserialize};
+use std::fmt;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
 
 /// KeyboardUsage describes the key codes to be used in implementing a USB keyboard.
 ///
@@ -46,7 +48,7 @@ use serde::{Serialize, Deserialize};
 #[repr(u8)]
 #[allow(unused)]
 #[non_exhaustive]
-#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash)]
 pub enum KeyboardUsage {
     // 0x00: Reserved
     /// Keyboard ErrorRollOver (Footnote 1)
@@ -492,9 +494,6 @@ pub enum KeyboardUsage {
     /// Reserved keyboard values (used for all reserved / invalid values)
     Reserved = 0xE8,
     // 0xE8-0xFF: Reserved
-    KeyboardRaise = 0xE9,
-    KeyboardLower = 0xEA,
-    KeyboardEmpty = 0xFF,
 }
 
 impl From<u8> for KeyboardUsage {
@@ -719,9 +718,6 @@ impl From<u8> for KeyboardUsage {
             0xE6 => Self::KeyboardRightAlt,
             0xE7 => Self::KeyboardRightGUI,
             0xE8 => Self::Reserved,
-            0xE9 => Self::KeyboardRaise,
-            0xEA => Self::KeyboardLower,
-            0xFF => Self::KeyboardEmpty,
             _ => Self::Reserved,
         }
     }
@@ -771,7 +767,7 @@ impl From<&str> for KeyboardUsage {
     }
 }
 
-impl Into<String> for KeyboardUsage {
+impl From<KeyboardUsage> for String {
     /// Convert a KeyboardUsage enum to a String using the label mapping
     /// 
     /// # Examples
@@ -788,12 +784,12 @@ impl Into<String> for KeyboardUsage {
     /// let label: String = KeyboardUsage::Reserved.into();
     /// assert_eq!(label, "Unknown");
     /// ```
-    fn into(self) -> String {
-        KEYCODE_LABELS.get(&self).unwrap_or(&"Unknown").to_string()
+    fn from(keycode: KeyboardUsage) -> Self {
+        KEYCODE_LABELS.get(&keycode).unwrap_or(&"Unknown").to_string()
     }
 }
 
-impl Into<&'static str> for KeyboardUsage {
+impl From<KeyboardUsage> for &'static str {
     /// Convert a KeyboardUsage enum to a static string slice using the label mapping
     /// 
     /// # Examples
@@ -810,11 +806,69 @@ impl Into<&'static str> for KeyboardUsage {
     /// let label: &str = KeyboardUsage::Reserved.into();
     /// assert_eq!(label, "Unknown");
     /// ```
-    fn into(self) -> &'static str {
-        KEYCODE_LABELS.get(&self).unwrap_or(&"Unknown")
+    fn from(keycode: KeyboardUsage) -> Self {
+        KEYCODE_LABELS.get(&keycode).unwrap_or(&"Unknown")
     }
 }
 
+impl Serialize for KeyboardUsage {
+    /// Serialize as the HID usage code, so saved data does not depend on variant names
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyboardUsage {
+    /// Deserialize from a HID usage code, or from a variant name as written by older versions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::keycodes::KeyboardUsage;
+    ///
+    /// let keycode: KeyboardUsage = serde_json::from_str("4").unwrap();
+    /// assert_eq!(keycode, KeyboardUsage::KeyboardAa);
+    ///
+    /// let keycode: KeyboardUsage = serde_json::from_str("\"KeyboardAa\"").unwrap();
+    /// assert_eq!(keycode, KeyboardUsage::KeyboardAa);
+    /// ```
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UsageVisitor)
+    }
+}
+
+struct UsageVisitor;
+
+impl Visitor<'_> for UsageVisitor {
+    type Value = KeyboardUsage;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a HID keyboard usage code or KeyboardUsage variant name")
+    }
+
+    fn visit_u64<E: de::Error>(self, code: u64) -> Result<KeyboardUsage, E> {
+        u8::try_from(code)
+            .ok()
+            .map(KeyboardUsage::from)
+            .filter(|&usage| usage as u64 == code)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(code), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<KeyboardUsage, E> {
+        VARIANT_NAMES.get(name)
+            .copied()
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(name), &self))
+    }
+}
+
+/// Lazy-initialized mapping from variant names to KeyboardUsage, for data saved by name
+static VARIANT_NAMES: Lazy<HashMap<String, KeyboardUsage>> = Lazy::new(|| {
+    (0..=u8::MAX)
+        .map(KeyboardUsage::from)
+        .map(|usage| (format!("{:?}", usage), usage))
+        .collect()
+});
+
 /// Lazy-initialized mapping from KeyboardUsage to display labels
 static KEYCODE_LABELS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
     use KeyboardUsage::*;
@@ -895,6 +949,18 @@ static KEYCODE_LABELS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(||
     map.insert(KeyboardF10, "F10");
     map.insert(KeyboardF11, "F11");
     map.insert(KeyboardF12, "F12");
+    map.insert(KeyboardF13, "F13");
+    map.insert(KeyboardF14, "F14");
+    map.insert(KeyboardF15, "F15");
+    map.insert(KeyboardF16, "F16");
+    map.insert(KeyboardF17, "F17");
+    map.insert(KeyboardF18, "F18");
+    map.insert(KeyboardF19, "F19");
+    map.insert(KeyboardF20, "F20");
+    map.insert(KeyboardF21, "F21");
+    map.insert(KeyboardF22, "F22");
+    map.insert(KeyboardF23, "F23");
+    map.insert(KeyboardF24, "F24");
     
     // Navigation keys
     map.insert(KeyboardPrintScreen, "PrtSc");
@@ -940,29 +1006,1293 @@ static KEYCODE_LABELS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(||
     map.insert(Keypad0Insert, "Num 0");
     map.insert(KeypadPeriodDelete, "Num .");
     map.insert(KeypadEqual, "Num =");
+    map.insert(KeypadComma, "Num ,");
+    map.insert(KeypadEqualSign, "AS/400 =");
+    map.insert(Keypad00, "Num 00");
+    map.insert(Keypad000, "Num 000");
+    map.insert(KeypadOpenParens, "Num (");
+    map.insert(KeypadCloseParens, "Num )");
+    map.insert(KeypadOpenBrace, "Num {");
+    map.insert(KeypadCloseBrace, "Num }");
+    map.insert(KeypadTab, "Num Tab");
+    map.insert(KeypadBackspace, "Num BKSP");
+    map.insert(KeypadA, "Num A");
+    map.insert(KeypadB, "Num B");
+    map.insert(KeypadC, "Num C");
+    map.insert(KeypadD, "Num D");
+    map.insert(KeypadE, "Num E");
+    map.insert(KeypadF, "Num F");
+    map.insert(KeypadBitwiseXor, "Num ^");
+    map.insert(KeypadLogicalXor, "Num XOR");
+    map.insert(KeypadModulo, "Num %");
+    map.insert(KeypadLeftShift, "Num <<");
+    map.insert(KeypadRightShift, "Num >>");
+    map.insert(KeypadBitwiseAnd, "Num &");
+    map.insert(KeypadLogicalAnd, "Num &&");
+    map.insert(KeypadBitwiseOr, "Num |");
+    map.insert(KeypadLogicalOr, "Num ||");
+    map.insert(KeypadColon, "Num :");
+    map.insert(KeypadHash, "Num #");
+    map.insert(KeypadSpace, "Num Space");
+    map.insert(KeypadAt, "Num @");
+    map.insert(KeypadExclamation, "Num !");
+    map.insert(KeypadMemoryStore, "Num MS");
+    map.insert(KeypadMemoryRecall, "Num MR");
+    map.insert(KeypadMemoryClear, "Num MC");
+    map.insert(KeypadMemoryAdd, "Num M+");
+    map.insert(KeypadMemorySubtract, "Num M-");
+    map.insert(KeypadMemoryMultiply, "Num M*");
+    map.insert(KeypadMemoryDivide, "Num M/");
+    map.insert(KeypadPositiveNegative, "Num +/-");
+    map.insert(KeypadClear, "Num Clear");
+    map.insert(KeypadClearEntry, "Num CE");
+    map.insert(KeypadBinary, "Num Bin");
+    map.insert(KeypadOctal, "Num Oct");
+    map.insert(KeypadDecimal, "Num Dec");
+    map.insert(KeypadHexadecimal, "Num Hex");
 
-    // Media and function keys
-    map.insert(KeyboardVolumeUp, "Vol+");
-    map.insert(KeyboardVolumeDown, "Vol-");
-    map.insert(KeyboardMute, "Mute");
-
-    // Miscellaneous keys
-    map.insert(KeyboardRaise, "Raise");
-    map.insert(KeyboardLower, "Lower");
-    map.insert(KeyboardEmpty, "");
+    // Keyboard page media keys, which most systems ignore in favour of [`ConsumerUsage`]
+    map.insert(KeyboardVolumeUp, "KbVol+");
+    map.insert(KeyboardVolumeDown, "KbVol-");
+    map.insert(KeyboardMute, "KbMute");
 
     // System keys
     map.insert(KeyboardApplication, "App");
     map.insert(KeyboardPower, "Power");
+    map.insert(KeyboardExecute, "Exec");
+    map.insert(KeyboardHelp, "Help");
+    map.insert(KeyboardMenu, "Menu");
+    map.insert(KeyboardSelect, "Select");
+    map.insert(KeyboardStop, "KbStop");
+    map.insert(KeyboardAgain, "Again");
+    map.insert(KeyboardUndo, "Undo");
+    map.insert(KeyboardCut, "Cut");
+    map.insert(KeyboardCopy, "Copy");
+    map.insert(KeyboardPaste, "Paste");
+    map.insert(KeyboardFind, "Find");
+    map.insert(KeyboardAlternateErase, "AltErase");
+    map.insert(KeyboardSysReqAttention, "SysRq");
+    map.insert(KeyboardCancel, "Cancel");
+    map.insert(KeyboardClear, "Clear");
+    map.insert(KeyboardPrior, "Prior");
+    map.insert(KeyboardReturn, "Return");
+    map.insert(KeyboardSeparator, "Separator");
+    map.insert(KeyboardOut, "Out");
+    map.insert(KeyboardOper, "Oper");
+    map.insert(KeyboardClearAgain, "ClrAgain");
+    map.insert(KeyboardCrSelProps, "CrSel");
+    map.insert(KeyboardExSel, "ExSel");
+
+    // Legacy locking keys
+    map.insert(KeyboardLockingCapsLock, "Lock Caps");
+    map.insert(KeyboardLockingNumLock, "Lock NumLk");
+    map.insert(KeyboardLockingScrollLock, "Lock ScrLk");
+
+    // International keys
+    map.insert(KeyboardNonUSHash, "ISO #");
+    map.insert(KeyboardNonUSSlash, "ISO \\");
+    map.insert(KeyboardInternational1, "Intl1");
+    map.insert(KeyboardInternational2, "Intl2");
+    map.insert(KeyboardInternational3, "Intl3");
+    map.insert(KeyboardInternational4, "Intl4");
+    map.insert(KeyboardInternational5, "Intl5");
+    map.insert(KeyboardInternational6, "Intl6");
+    map.insert(KeyboardInternational7, "Intl7");
+    map.insert(KeyboardInternational8, "Intl8");
+    map.insert(KeyboardInternational9, "Intl9");
+    map.insert(KeyboardLANG1, "Lang1");
+    map.insert(KeyboardLANG2, "Lang2");
+    map.insert(KeyboardLANG3, "Lang3");
+    map.insert(KeyboardLANG4, "Lang4");
+    map.insert(KeyboardLANG5, "Lang5");
+    map.insert(KeyboardLANG6, "Lang6");
+    map.insert(KeyboardLANG7, "Lang7");
+    map.insert(KeyboardLANG8, "Lang8");
+    map.insert(KeyboardLANG9, "Lang9");
+    map.insert(ThousandsSeparator, "ThouSep");
+    map.insert(DecimalSeparator, "DecSep");
+    map.insert(CurrencyUnit, "Currency");
+    map.insert(CurrencySubunit, "Subunit");
+
+    // Error codes a keyboard reports instead of keys
+    map.insert(KeyboardErrorRollOver, "ErrRollOver");
+    map.insert(KeyboardPOSTFail, "POSTFail");
+    map.insert(KeyboardErrorUndefined, "ErrUndef");
+    map.insert(Reserved, "Unknown");
 
     map
 });
 
-/// Lazy-initialized reverse mapping from display labels to KeyboardUsage
+/// Lazy-initialized reverse mapping from display labels, and the names of every
+/// [`NamingScheme`], to KeyboardUsage. Labels win over names, and earlier schemes over later ones.
 static LABEL_KEYCODES: Lazy<HashMap<&'static str, KeyboardUsage>> = Lazy::new(|| {
-    KEYCODE_LABELS.iter().map(|(&k, &v)| (v, k)).collect()
+    let mut labels = HashMap::new();
+    for scheme in NamingScheme::ALL.into_iter().rev() {
+        labels.extend(&ALIAS_USAGES[&scheme]);
+    }
+    labels.extend(KEYCODE_LABELS.iter().map(|(&k, &v)| (v, k)));
+    labels
+});
+
+/// Naming schemes of other keyboard tools, whose key names [`KeyboardUsage::alias`] and
+/// [`KeyboardUsage::from_alias`] translate. Key labels are parsed from any of them too.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum NamingScheme {
+    /// QMK basic keycodes, e.g. `KC_ESC`
+    Qmk,
+    /// ZMK key names, e.g. `ESC`
+    Zmk,
+    /// Kanata key names, e.g. `esc`
+    Kanata,
+    /// Linux evdev key codes, e.g. `KEY_ESC`
+    Evdev,
+}
+
+impl NamingScheme {
+    /// All schemes, in the order label parsing tries them
+    pub const ALL: [Self; 4] = [Self::Qmk, Self::Zmk, Self::Kanata, Self::Evdev];
+
+    /// The scheme's name for each usage it has one for
+    fn names(self) -> &'static HashMap<KeyboardUsage, &'static str> {
+        match self {
+            Self::Qmk => &QMK_KEYCODES,
+            Self::Zmk => &ZMK_KEYS,
+            Self::Kanata => &KANATA_KEYS,
+            Self::Evdev => &EVDEV_KEYS,
+        }
+    }
+
+    /// Other names the scheme accepts, e.g. long forms
+    fn other_names(self) -> Option<&'static HashMap<&'static str, KeyboardUsage>> {
+        match self {
+            Self::Qmk => Some(&QMK_KEYCODE_ALIASES),
+            Self::Zmk => Some(&ZMK_KEY_ALIASES),
+            Self::Kanata | Self::Evdev => None,
+        }
+    }
+}
+
+impl KeyboardUsage {
+    /// The usage's name in a naming scheme
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::keycodes::{KeyboardUsage, NamingScheme};
+    ///
+    /// assert_eq!(KeyboardUsage::KeyboardEscape.alias(NamingScheme::Qmk), Some("KC_ESC"));
+    /// assert_eq!(KeyboardUsage::KeyboardEscape.alias(NamingScheme::Evdev), Some("KEY_ESC"));
+    /// ```
+    pub fn alias(self, scheme: NamingScheme) -> Option<&'static str> {
+        scheme.names().get(&self).copied()
+    }
+
+    /// The usage a name stands for in a naming scheme, by its name or any other the scheme accepts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::keycodes::{KeyboardUsage, NamingScheme};
+    ///
+    /// assert_eq!(KeyboardUsage::from_alias(NamingScheme::Zmk, "ESCAPE"), Some(KeyboardUsage::KeyboardEscape));
+    /// assert_eq!(KeyboardUsage::from_alias(NamingScheme::Kanata, "KC_ESC"), None);
+    /// ```
+    pub fn from_alias(scheme: NamingScheme, name: &str) -> Option<Self> {
+        ALIAS_USAGES.get(&scheme).and_then(|usages| usages.get(name)).copied()
+    }
+}
+
+/// Lazy-initialized mapping from every name a scheme accepts to KeyboardUsage, per scheme
+static ALIAS_USAGES: Lazy<HashMap<NamingScheme, HashMap<&'static str, KeyboardUsage>>> = Lazy::new(|| {
+    NamingScheme::ALL
+        .into_iter()
+        .map(|scheme| {
+            let mut usages: HashMap<&'static str, KeyboardUsage> = scheme.other_names().cloned().unwrap_or_default();
+            usages.extend(scheme.names().iter().map(|(&usage, &name)| (name, usage)));
+            (scheme, usages)
+        })
+        .collect()
+});
+
+/// QMK basic keycode names. QMK has no keycodes for the HID error codes, the keypad
+/// extensions `0xB0`-`0xDD` (that range holds QMK's own system, media and mouse keys) or
+/// reserved usages.
+static QMK_KEYCODES: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "KC_A"),
+        (KeyboardBb, "KC_B"),
+        (KeyboardCc, "KC_C"),
+        (KeyboardDd, "KC_D"),
+        (KeyboardEe, "KC_E"),
+        (KeyboardFf, "KC_F"),
+        (KeyboardGg, "KC_G"),
+        (KeyboardHh, "KC_H"),
+        (KeyboardIi, "KC_I"),
+        (KeyboardJj, "KC_J"),
+        (KeyboardKk, "KC_K"),
+        (KeyboardLl, "KC_L"),
+        (KeyboardMm, "KC_M"),
+        (KeyboardNn, "KC_N"),
+        (KeyboardOo, "KC_O"),
+        (KeyboardPp, "KC_P"),
+        (KeyboardQq, "KC_Q"),
+        (KeyboardRr, "KC_R"),
+        (KeyboardSs, "KC_S"),
+        (KeyboardTt, "KC_T"),
+        (KeyboardUu, "KC_U"),
+        (KeyboardVv, "KC_V"),
+        (KeyboardWw, "KC_W"),
+        (KeyboardXx, "KC_X"),
+        (KeyboardYy, "KC_Y"),
+        (KeyboardZz, "KC_Z"),
+        (Keyboard1Exclamation, "KC_1"),
+        (Keyboard2At, "KC_2"),
+        (Keyboard3Hash, "KC_3"),
+        (Keyboard4Dollar, "KC_4"),
+        (Keyboard5Percent, "KC_5"),
+        (Keyboard6Caret, "KC_6"),
+        (Keyboard7Ampersand, "KC_7"),
+        (Keyboard8Asterisk, "KC_8"),
+        (Keyboard9OpenParens, "KC_9"),
+        (Keyboard0CloseParens, "KC_0"),
+        (KeyboardEnter, "KC_ENT"),
+        (KeyboardEscape, "KC_ESC"),
+        (KeyboardBackspace, "KC_BSPC"),
+        (KeyboardTab, "KC_TAB"),
+        (KeyboardSpacebar, "KC_SPC"),
+        (KeyboardDashUnderscore, "KC_MINS"),
+        (KeyboardEqualPlus, "KC_EQL"),
+        (KeyboardOpenBracketBrace, "KC_LBRC"),
+        (KeyboardCloseBracketBrace, "KC_RBRC"),
+        (KeyboardBackslashBar, "KC_BSLS"),
+        (KeyboardNonUSHash, "KC_NUHS"),
+        (KeyboardSemiColon, "KC_SCLN"),
+        (KeyboardSingleDoubleQuote, "KC_QUOT"),
+        (KeyboardBacktickTilde, "KC_GRV"),
+        (KeyboardCommaLess, "KC_COMM"),
+        (KeyboardPeriodGreater, "KC_DOT"),
+        (KeyboardSlashQuestion, "KC_SLSH"),
+        (KeyboardCapsLock, "KC_CAPS"),
+        (KeyboardF1, "KC_F1"),
+        (KeyboardF2, "KC_F2"),
+        (KeyboardF3, "KC_F3"),
+        (KeyboardF4, "KC_F4"),
+        (KeyboardF5, "KC_F5"),
+        (KeyboardF6, "KC_F6"),
+        (KeyboardF7, "KC_F7"),
+        (KeyboardF8, "KC_F8"),
+        (KeyboardF9, "KC_F9"),
+        (KeyboardF10, "KC_F10"),
+        (KeyboardF11, "KC_F11"),
+        (KeyboardF12, "KC_F12"),
+        (KeyboardPrintScreen, "KC_PSCR"),
+        (KeyboardScrollLock, "KC_SCRL"),
+        (KeyboardPause, "KC_PAUS"),
+        (KeyboardInsert, "KC_INS"),
+        (KeyboardHome, "KC_HOME"),
+        (KeyboardPageUp, "KC_PGUP"),
+        (KeyboardDelete, "KC_DEL"),
+        (KeyboardEnd, "KC_END"),
+        (KeyboardPageDown, "KC_PGDN"),
+        (KeyboardRightArrow, "KC_RGHT"),
+        (KeyboardLeftArrow, "KC_LEFT"),
+        (KeyboardDownArrow, "KC_DOWN"),
+        (KeyboardUpArrow, "KC_UP"),
+        (KeypadNumLock, "KC_NUM"),
+        (KeypadDivide, "KC_PSLS"),
+        (KeypadMultiply, "KC_PAST"),
+        (KeypadMinus, "KC_PMNS"),
+        (KeypadPlus, "KC_PPLS"),
+        (KeypadEnter, "KC_PENT"),
+        (Keypad1End, "KC_P1"),
+        (Keypad2DownArrow, "KC_P2"),
+        (Keypad3PageDown, "KC_P3"),
+        (Keypad4LeftArrow, "KC_P4"),
+        (Keypad5, "KC_P5"),
+        (Keypad6RightArrow, "KC_P6"),
+        (Keypad7Home, "KC_P7"),
+        (Keypad8UpArrow, "KC_P8"),
+        (Keypad9PageUp, "KC_P9"),
+        (Keypad0Insert, "KC_P0"),
+        (KeypadPeriodDelete, "KC_PDOT"),
+        (KeyboardNonUSSlash, "KC_NUBS"),
+        (KeyboardApplication, "KC_APP"),
+        (KeyboardPower, "KC_KB_POWER"),
+        (KeypadEqual, "KC_PEQL"),
+        (KeyboardF13, "KC_F13"),
+        (KeyboardF14, "KC_F14"),
+        (KeyboardF15, "KC_F15"),
+        (KeyboardF16, "KC_F16"),
+        (KeyboardF17, "KC_F17"),
+        (KeyboardF18, "KC_F18"),
+        (KeyboardF19, "KC_F19"),
+        (KeyboardF20, "KC_F20"),
+        (KeyboardF21, "KC_F21"),
+        (KeyboardF22, "KC_F22"),
+        (KeyboardF23, "KC_F23"),
+        (KeyboardF24, "KC_F24"),
+        (KeyboardExecute, "KC_EXEC"),
+        (KeyboardHelp, "KC_HELP"),
+        (KeyboardMenu, "KC_MENU"),
+        (KeyboardSelect, "KC_SLCT"),
+        (KeyboardStop, "KC_STOP"),
+        (KeyboardAgain, "KC_AGIN"),
+        (KeyboardUndo, "KC_UNDO"),
+        (KeyboardCut, "KC_CUT"),
+        (KeyboardCopy, "KC_COPY"),
+        (KeyboardPaste, "KC_PSTE"),
+        (KeyboardFind, "KC_FIND"),
+        (KeyboardMute, "KC_KB_MUTE"),
+        (KeyboardVolumeUp, "KC_KB_VOLUME_UP"),
+        (KeyboardVolumeDown, "KC_KB_VOLUME_DOWN"),
+        (KeyboardLockingCapsLock, "KC_LCAP"),
+        (KeyboardLockingNumLock, "KC_LNUM"),
+        (KeyboardLockingScrollLock, "KC_LSCR"),
+        (KeypadComma, "KC_PCMM"),
+        (KeypadEqualSign, "KC_KP_EQUAL_AS400"),
+        (KeyboardInternational1, "KC_INT1"),
+        (KeyboardInternational2, "KC_INT2"),
+        (KeyboardInternational3, "KC_INT3"),
+        (KeyboardInternational4, "KC_INT4"),
+        (KeyboardInternational5, "KC_INT5"),
+        (KeyboardInternational6, "KC_INT6"),
+        (KeyboardInternational7, "KC_INT7"),
+        (KeyboardInternational8, "KC_INT8"),
+        (KeyboardInternational9, "KC_INT9"),
+        (KeyboardLANG1, "KC_LNG1"),
+        (KeyboardLANG2, "KC_LNG2"),
+        (KeyboardLANG3, "KC_LNG3"),
+        (KeyboardLANG4, "KC_LNG4"),
+        (KeyboardLANG5, "KC_LNG5"),
+        (KeyboardLANG6, "KC_LNG6"),
+        (KeyboardLANG7, "KC_LNG7"),
+        (KeyboardLANG8, "KC_LNG8"),
+        (KeyboardLANG9, "KC_LNG9"),
+        (KeyboardAlternateErase, "KC_ERAS"),
+        (KeyboardSysReqAttention, "KC_SYRQ"),
+        (KeyboardCancel, "KC_CNCL"),
+        (KeyboardClear, "KC_CLR"),
+        (KeyboardPrior, "KC_PRIR"),
+        (KeyboardReturn, "KC_RETN"),
+        (KeyboardSeparator, "KC_SEPR"),
+        (KeyboardOut, "KC_OUT"),
+        (KeyboardOper, "KC_OPER"),
+        (KeyboardClearAgain, "KC_CLAG"),
+        (KeyboardCrSelProps, "KC_CRSL"),
+        (KeyboardExSel, "KC_EXSL"),
+        (KeyboardLeftControl, "KC_LCTL"),
+        (KeyboardLeftShift, "KC_LSFT"),
+        (KeyboardLeftAlt, "KC_LALT"),
+        (KeyboardLeftGUI, "KC_LGUI"),
+        (KeyboardRightControl, "KC_RCTL"),
+        (KeyboardRightShift, "KC_RSFT"),
+        (KeyboardRightAlt, "KC_RALT"),
+        (KeyboardRightGUI, "KC_RGUI"),
+    ])
 });
 
+/// Other QMK names for basic keycodes: the long names and older aliases
+static QMK_KEYCODE_ALIASES: Lazy<HashMap<&'static str, KeyboardUsage>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        ("KC_ENTER", KeyboardEnter),
+        ("KC_ESCAPE", KeyboardEscape),
+        ("KC_BACKSPACE", KeyboardBackspace),
+        ("KC_SPACE", KeyboardSpacebar),
+        ("KC_MINUS", KeyboardDashUnderscore),
+        ("KC_EQUAL", KeyboardEqualPlus),
+        ("KC_LEFT_BRACKET", KeyboardOpenBracketBrace),
+        ("KC_LBRACKET", KeyboardOpenBracketBrace),
+        ("KC_RIGHT_BRACKET", KeyboardCloseBracketBrace),
+        ("KC_RBRACKET", KeyboardCloseBracketBrace),
+        ("KC_BACKSLASH", KeyboardBackslashBar),
+        ("KC_NONUS_HASH", KeyboardNonUSHash),
+        ("KC_SEMICOLON", KeyboardSemiColon),
+        ("KC_QUOTE", KeyboardSingleDoubleQuote),
+        ("KC_GRAVE", KeyboardBacktickTilde),
+        ("KC_COMMA", KeyboardCommaLess),
+        ("KC_SLASH", KeyboardSlashQuestion),
+        ("KC_CAPS_LOCK", KeyboardCapsLock),
+        ("KC_CAPSLOCK", KeyboardCapsLock),
+        ("KC_PRINT_SCREEN", KeyboardPrintScreen),
+        ("KC_SCROLL_LOCK", KeyboardScrollLock),
+        ("KC_SLCK", KeyboardScrollLock),
+        ("KC_PAUSE", KeyboardPause),
+        ("KC_BRK", KeyboardPause),
+        ("KC_INSERT", KeyboardInsert),
+        ("KC_PAGE_UP", KeyboardPageUp),
+        ("KC_DELETE", KeyboardDelete),
+        ("KC_PAGE_DOWN", KeyboardPageDown),
+        ("KC_RIGHT", KeyboardRightArrow),
+        ("KC_NUM_LOCK", KeypadNumLock),
+        ("KC_NLCK", KeypadNumLock),
+        ("KC_KP_SLASH", KeypadDivide),
+        ("KC_KP_ASTERISK", KeypadMultiply),
+        ("KC_KP_MINUS", KeypadMinus),
+        ("KC_KP_PLUS", KeypadPlus),
+        ("KC_KP_ENTER", KeypadEnter),
+        ("KC_KP_1", Keypad1End),
+        ("KC_KP_2", Keypad2DownArrow),
+        ("KC_KP_3", Keypad3PageDown),
+        ("KC_KP_4", Keypad4LeftArrow),
+        ("KC_KP_5", Keypad5),
+        ("KC_KP_6", Keypad6RightArrow),
+        ("KC_KP_7", Keypad7Home),
+        ("KC_KP_8", Keypad8UpArrow),
+        ("KC_KP_9", Keypad9PageUp),
+        ("KC_KP_0", Keypad0Insert),
+        ("KC_KP_DOT", KeypadPeriodDelete),
+        ("KC_NONUS_BACKSLASH", KeyboardNonUSSlash),
+        ("KC_APPLICATION", KeyboardApplication),
+        ("KC_KP_EQUAL", KeypadEqual),
+        ("KC_EXECUTE", KeyboardExecute),
+        ("KC_SELECT", KeyboardSelect),
+        ("KC_AGAIN", KeyboardAgain),
+        ("KC_PASTE", KeyboardPaste),
+        ("KC_LOCKING_CAPS_LOCK", KeyboardLockingCapsLock),
+        ("KC_LOCKING_NUM_LOCK", KeyboardLockingNumLock),
+        ("KC_LOCKING_SCROLL_LOCK", KeyboardLockingScrollLock),
+        ("KC_KP_COMMA", KeypadComma),
+        ("KC_INTERNATIONAL_1", KeyboardInternational1),
+        ("KC_INTERNATIONAL_2", KeyboardInternational2),
+        ("KC_INTERNATIONAL_3", KeyboardInternational3),
+        ("KC_INTERNATIONAL_4", KeyboardInternational4),
+        ("KC_INTERNATIONAL_5", KeyboardInternational5),
+        ("KC_INTERNATIONAL_6", KeyboardInternational6),
+        ("KC_INTERNATIONAL_7", KeyboardInternational7),
+        ("KC_INTERNATIONAL_8", KeyboardInternational8),
+        ("KC_INTERNATIONAL_9", KeyboardInternational9),
+        ("KC_LANGUAGE_1", KeyboardLANG1),
+        ("KC_LANGUAGE_2", KeyboardLANG2),
+        ("KC_LANGUAGE_3", KeyboardLANG3),
+        ("KC_LANGUAGE_4", KeyboardLANG4),
+        ("KC_LANGUAGE_5", KeyboardLANG5),
+        ("KC_LANGUAGE_6", KeyboardLANG6),
+        ("KC_LANGUAGE_7", KeyboardLANG7),
+        ("KC_LANGUAGE_8", KeyboardLANG8),
+        ("KC_LANGUAGE_9", KeyboardLANG9),
+        ("KC_ALTERNATE_ERASE", KeyboardAlternateErase),
+        ("KC_SYSTEM_REQUEST", KeyboardSysReqAttention),
+        ("KC_CANCEL", KeyboardCancel),
+        ("KC_CLEAR", KeyboardClear),
+        ("KC_PRIOR", KeyboardPrior),
+        ("KC_RETURN", KeyboardReturn),
+        ("KC_SEPARATOR", KeyboardSeparator),
+        ("KC_CLEAR_AGAIN", KeyboardClearAgain),
+        ("KC_CRSEL", KeyboardCrSelProps),
+        ("KC_EXSEL", KeyboardExSel),
+        ("KC_LEFT_CTRL", KeyboardLeftControl),
+        ("KC_LEFT_SHIFT", KeyboardLeftShift),
+        ("KC_LEFT_ALT", KeyboardLeftAlt),
+        ("KC_LOPT", KeyboardLeftAlt),
+        ("KC_LEFT_GUI", KeyboardLeftGUI),
+        ("KC_LCMD", KeyboardLeftGUI),
+        ("KC_LWIN", KeyboardLeftGUI),
+        ("KC_RIGHT_CTRL", KeyboardRightControl),
+        ("KC_RIGHT_SHIFT", KeyboardRightShift),
+        ("KC_RIGHT_ALT", KeyboardRightAlt),
+        ("KC_ROPT", KeyboardRightAlt),
+        ("KC_ALGR", KeyboardRightAlt),
+        ("KC_RIGHT_GUI", KeyboardRightGUI),
+        ("KC_RCMD", KeyboardRightGUI),
+        ("KC_RWIN", KeyboardRightGUI),
+    ])
+});
+
+/// ZMK key names from `dt-bindings/zmk/keys.h`, the short form of each
+static ZMK_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "A"),
+        (KeyboardBb, "B"),
+        (KeyboardCc, "C"),
+        (KeyboardDd, "D"),
+        (KeyboardEe, "E"),
+        (KeyboardFf, "F"),
+        (KeyboardGg, "G"),
+        (KeyboardHh, "H"),
+        (KeyboardIi, "I"),
+        (KeyboardJj, "J"),
+        (KeyboardKk, "K"),
+        (KeyboardLl, "L"),
+        (KeyboardMm, "M"),
+        (KeyboardNn, "N"),
+        (KeyboardOo, "O"),
+        (KeyboardPp, "P"),
+        (KeyboardQq, "Q"),
+        (KeyboardRr, "R"),
+        (KeyboardSs, "S"),
+        (KeyboardTt, "T"),
+        (KeyboardUu, "U"),
+        (KeyboardVv, "V"),
+        (KeyboardWw, "W"),
+        (KeyboardXx, "X"),
+        (KeyboardYy, "Y"),
+        (KeyboardZz, "Z"),
+        (Keyboard1Exclamation, "N1"),
+        (Keyboard2At, "N2"),
+        (Keyboard3Hash, "N3"),
+        (Keyboard4Dollar, "N4"),
+        (Keyboard5Percent, "N5"),
+        (Keyboard6Caret, "N6"),
+        (Keyboard7Ampersand, "N7"),
+        (Keyboard8Asterisk, "N8"),
+        (Keyboard9OpenParens, "N9"),
+        (Keyboard0CloseParens, "N0"),
+        (KeyboardEnter, "RET"),
+        (KeyboardEscape, "ESC"),
+        (KeyboardBackspace, "BSPC"),
+        (KeyboardTab, "TAB"),
+        (KeyboardSpacebar, "SPACE"),
+        (KeyboardDashUnderscore, "MINUS"),
+        (KeyboardEqualPlus, "EQUAL"),
+        (KeyboardOpenBracketBrace, "LBKT"),
+        (KeyboardCloseBracketBrace, "RBKT"),
+        (KeyboardBackslashBar, "BSLH"),
+        (KeyboardNonUSHash, "NON_US_HASH"),
+        (KeyboardSemiColon, "SEMI"),
+        (KeyboardSingleDoubleQuote, "SQT"),
+        (KeyboardBacktickTilde, "GRAVE"),
+        (KeyboardCommaLess, "COMMA"),
+        (KeyboardPeriodGreater, "DOT"),
+        (KeyboardSlashQuestion, "FSLH"),
+        (KeyboardCapsLock, "CAPS"),
+        (KeyboardF1, "F1"),
+        (KeyboardF2, "F2"),
+        (KeyboardF3, "F3"),
+        (KeyboardF4, "F4"),
+        (KeyboardF5, "F5"),
+        (KeyboardF6, "F6"),
+        (KeyboardF7, "F7"),
+        (KeyboardF8, "F8"),
+        (KeyboardF9, "F9"),
+        (KeyboardF10, "F10"),
+        (KeyboardF11, "F11"),
+        (KeyboardF12, "F12"),
+        (KeyboardPrintScreen, "PSCRN"),
+        (KeyboardScrollLock, "SLCK"),
+        (KeyboardPause, "PAUSE_BREAK"),
+        (KeyboardInsert, "INS"),
+        (KeyboardHome, "HOME"),
+        (KeyboardPageUp, "PG_UP"),
+        (KeyboardDelete, "DEL"),
+        (KeyboardEnd, "END"),
+        (KeyboardPageDown, "PG_DN"),
+        (KeyboardRightArrow, "RIGHT"),
+        (KeyboardLeftArrow, "LEFT"),
+        (KeyboardDownArrow, "DOWN"),
+        (KeyboardUpArrow, "UP"),
+        (KeypadNumLock, "KP_NUM"),
+        (KeypadDivide, "KP_DIVIDE"),
+        (KeypadMultiply, "KP_MULTIPLY"),
+        (KeypadMinus, "KP_MINUS"),
+        (KeypadPlus, "KP_PLUS"),
+        (KeypadEnter, "KP_ENTER"),
+        (Keypad1End, "KP_N1"),
+        (Keypad2DownArrow, "KP_N2"),
+        (Keypad3PageDown, "KP_N3"),
+        (Keypad4LeftArrow, "KP_N4"),
+        (Keypad5, "KP_N5"),
+        (Keypad6RightArrow, "KP_N6"),
+        (Keypad7Home, "KP_N7"),
+        (Keypad8UpArrow, "KP_N8"),
+        (Keypad9PageUp, "KP_N9"),
+        (Keypad0Insert, "KP_N0"),
+        (KeypadPeriodDelete, "KP_DOT"),
+        (KeyboardNonUSSlash, "NON_US_BSLH"),
+        (KeyboardApplication, "K_APP"),
+        (KeyboardPower, "K_PWR"),
+        (KeypadEqual, "KP_EQUAL"),
+        (KeyboardF13, "F13"),
+        (KeyboardF14, "F14"),
+        (KeyboardF15, "F15"),
+        (KeyboardF16, "F16"),
+        (KeyboardF17, "F17"),
+        (KeyboardF18, "F18"),
+        (KeyboardF19, "F19"),
+        (KeyboardF20, "F20"),
+        (KeyboardF21, "F21"),
+        (KeyboardF22, "F22"),
+        (KeyboardF23, "F23"),
+        (KeyboardF24, "F24"),
+        (KeyboardExecute, "K_EXEC"),
+        (KeyboardHelp, "K_HELP"),
+        (KeyboardMenu, "K_MENU"),
+        (KeyboardSelect, "K_SELECT"),
+        (KeyboardStop, "K_STOP"),
+        (KeyboardAgain, "K_AGAIN"),
+        (KeyboardUndo, "K_UNDO"),
+        (KeyboardCut, "K_CUT"),
+        (KeyboardCopy, "K_COPY"),
+        (KeyboardPaste, "K_PASTE"),
+        (KeyboardFind, "K_FIND"),
+        (KeyboardMute, "K_MUTE"),
+        (KeyboardVolumeUp, "K_VOL_UP"),
+        (KeyboardVolumeDown, "K_VOL_DN"),
+        (KeyboardLockingCapsLock, "LOCKING_CAPS"),
+        (KeyboardLockingNumLock, "LOCKING_NUM"),
+        (KeyboardLockingScrollLock, "LOCKING_SCROLL"),
+        (KeypadComma, "KP_COMMA"),
+        (KeypadEqualSign, "KP_EQUAL_AS400"),
+        (KeyboardInternational1, "INT1"),
+        (KeyboardInternational2, "INT2"),
+        (KeyboardInternational3, "INT3"),
+        (KeyboardInternational4, "INT4"),
+        (KeyboardInternational5, "INT5"),
+        (KeyboardInternational6, "INT6"),
+        (KeyboardInternational7, "INT7"),
+        (KeyboardInternational8, "INT8"),
+        (KeyboardInternational9, "INT9"),
+        (KeyboardLANG1, "LANG1"),
+        (KeyboardLANG2, "LANG2"),
+        (KeyboardLANG3, "LANG3"),
+        (KeyboardLANG4, "LANG4"),
+        (KeyboardLANG5, "LANG5"),
+        (KeyboardLANG6, "LANG6"),
+        (KeyboardLANG7, "LANG7"),
+        (KeyboardLANG8, "LANG8"),
+        (KeyboardLANG9, "LANG9"),
+        (KeyboardAlternateErase, "ALT_ERASE"),
+        (KeyboardSysReqAttention, "SYSREQ"),
+        (KeyboardCancel, "K_CANCEL"),
+        (KeyboardClear, "CLEAR"),
+        (KeyboardPrior, "PRIOR"),
+        (KeyboardReturn, "RET2"),
+        (KeyboardSeparator, "SEPARATOR"),
+        (KeyboardOut, "OUT"),
+        (KeyboardOper, "OPER"),
+        (KeyboardClearAgain, "CLEAR_AGAIN"),
+        (KeyboardCrSelProps, "CRSEL"),
+        (KeyboardExSel, "EXSEL"),
+        (KeypadOpenParens, "KP_LPAR"),
+        (KeypadCloseParens, "KP_RPAR"),
+        (KeyboardLeftControl, "LCTRL"),
+        (KeyboardLeftShift, "LSHFT"),
+        (KeyboardLeftAlt, "LALT"),
+        (KeyboardLeftGUI, "LGUI"),
+        (KeyboardRightControl, "RCTRL"),
+        (KeyboardRightShift, "RSHFT"),
+        (KeyboardRightAlt, "RALT"),
+        (KeyboardRightGUI, "RGUI"),
+    ])
+});
+
+/// Other ZMK names for keys: the long names and common aliases
+static ZMK_KEY_ALIASES: Lazy<HashMap<&'static str, KeyboardUsage>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        ("NUMBER_1", Keyboard1Exclamation),
+        ("NUMBER_2", Keyboard2At),
+        ("NUMBER_3", Keyboard3Hash),
+        ("NUMBER_4", Keyboard4Dollar),
+        ("NUMBER_5", Keyboard5Percent),
+        ("NUMBER_6", Keyboard6Caret),
+        ("NUMBER_7", Keyboard7Ampersand),
+        ("NUMBER_8", Keyboard8Asterisk),
+        ("NUMBER_9", Keyboard9OpenParens),
+        ("NUMBER_0", Keyboard0CloseParens),
+        ("RETURN", KeyboardEnter),
+        ("ENTER", KeyboardEnter),
+        ("ESCAPE", KeyboardEscape),
+        ("BACKSPACE", KeyboardBackspace),
+        ("SPC", KeyboardSpacebar),
+        ("LEFT_BRACKET", KeyboardOpenBracketBrace),
+        ("RIGHT_BRACKET", KeyboardCloseBracketBrace),
+        ("BACKSLASH", KeyboardBackslashBar),
+        ("SEMICOLON", KeyboardSemiColon),
+        ("SINGLE_QUOTE", KeyboardSingleDoubleQuote),
+        ("APOSTROPHE", KeyboardSingleDoubleQuote),
+        ("APOS", KeyboardSingleDoubleQuote),
+        ("PERIOD", KeyboardPeriodGreater),
+        ("SLASH", KeyboardSlashQuestion),
+        ("CAPSLOCK", KeyboardCapsLock),
+        ("PRINTSCREEN", KeyboardPrintScreen),
+        ("SCROLLLOCK", KeyboardScrollLock),
+        ("PAUSE_BREAK", KeyboardPause),
+        ("INSERT", KeyboardInsert),
+        ("PAGE_UP", KeyboardPageUp),
+        ("DELETE", KeyboardDelete),
+        ("PAGE_DOWN", KeyboardPageDown),
+        ("RIGHT_ARROW", KeyboardRightArrow),
+        ("LEFT_ARROW", KeyboardLeftArrow),
+        ("DOWN_ARROW", KeyboardDownArrow),
+        ("UP_ARROW", KeyboardUpArrow),
+        ("KP_NUMLOCK", KeypadNumLock),
+        ("KP_SLASH", KeypadDivide),
+        ("KP_ASTERISK", KeypadMultiply),
+        ("KP_SUBTRACT", KeypadMinus),
+        ("KP_N1", Keypad1End),
+        ("KP_NUMBER_1", Keypad1End),
+        ("KP_NUMBER_2", Keypad2DownArrow),
+        ("KP_NUMBER_3", Keypad3PageDown),
+        ("KP_NUMBER_4", Keypad4LeftArrow),
+        ("KP_NUMBER_5", Keypad5),
+        ("KP_NUMBER_6", Keypad6RightArrow),
+        ("KP_NUMBER_7", Keypad7Home),
+        ("KP_NUMBER_8", Keypad8UpArrow),
+        ("KP_NUMBER_9", Keypad9PageUp),
+        ("KP_NUMBER_0", Keypad0Insert),
+        ("KP_PERIOD", KeypadPeriodDelete),
+        ("KP_LEFT_PARENTHESIS", KeypadOpenParens),
+        ("KP_RIGHT_PARENTHESIS", KeypadCloseParens),
+        ("K_APPLICATION", KeyboardApplication),
+        ("K_CONTEXT_MENU", KeyboardApplication),
+        ("K_POWER", KeyboardPower),
+        ("K_EXECUTE", KeyboardExecute),
+        ("K_MUTE", KeyboardMute),
+        ("K_VOLUME_UP", KeyboardVolumeUp),
+        ("K_VOLUME_DOWN", KeyboardVolumeDown),
+        ("INTERNATIONAL_1", KeyboardInternational1),
+        ("INTERNATIONAL_2", KeyboardInternational2),
+        ("INTERNATIONAL_3", KeyboardInternational3),
+        ("INTERNATIONAL_4", KeyboardInternational4),
+        ("INTERNATIONAL_5", KeyboardInternational5),
+        ("INTERNATIONAL_6", KeyboardInternational6),
+        ("INTERNATIONAL_7", KeyboardInternational7),
+        ("INTERNATIONAL_8", KeyboardInternational8),
+        ("INTERNATIONAL_9", KeyboardInternational9),
+        ("LANGUAGE_1", KeyboardLANG1),
+        ("LANGUAGE_2", KeyboardLANG2),
+        ("LANGUAGE_3", KeyboardLANG3),
+        ("LANGUAGE_4", KeyboardLANG4),
+        ("LANGUAGE_5", KeyboardLANG5),
+        ("LANGUAGE_6", KeyboardLANG6),
+        ("LANGUAGE_7", KeyboardLANG7),
+        ("LANGUAGE_8", KeyboardLANG8),
+        ("LANGUAGE_9", KeyboardLANG9),
+        ("LEFT_CONTROL", KeyboardLeftControl),
+        ("LCTL", KeyboardLeftControl),
+        ("LEFT_SHIFT", KeyboardLeftShift),
+        ("LSHIFT", KeyboardLeftShift),
+        ("LEFT_ALT", KeyboardLeftAlt),
+        ("LEFT_GUI", KeyboardLeftGUI),
+        ("LCMD", KeyboardLeftGUI),
+        ("LWIN", KeyboardLeftGUI),
+        ("LMETA", KeyboardLeftGUI),
+        ("RIGHT_CONTROL", KeyboardRightControl),
+        ("RCTL", KeyboardRightControl),
+        ("RIGHT_SHIFT", KeyboardRightShift),
+        ("RSHIFT", KeyboardRightShift),
+        ("RIGHT_ALT", KeyboardRightAlt),
+        ("RIGHT_GUI", KeyboardRightGUI),
+        ("RCMD", KeyboardRightGUI),
+        ("RWIN", KeyboardRightGUI),
+        ("RMETA", KeyboardRightGUI),
+    ])
+});
+
+/// Kanata key names, as accepted in `defsrc` and `deflayer`
+static KANATA_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "a"),
+        (KeyboardBb, "b"),
+        (KeyboardCc, "c"),
+        (KeyboardDd, "d"),
+        (KeyboardEe, "e"),
+        (KeyboardFf, "f"),
+        (KeyboardGg, "g"),
+        (KeyboardHh, "h"),
+        (KeyboardIi, "i"),
+        (KeyboardJj, "j"),
+        (KeyboardKk, "k"),
+        (KeyboardLl, "l"),
+        (KeyboardMm, "m"),
+        (KeyboardNn, "n"),
+        (KeyboardOo, "o"),
+        (KeyboardPp, "p"),
+        (KeyboardQq, "q"),
+        (KeyboardRr, "r"),
+        (KeyboardSs, "s"),
+        (KeyboardTt, "t"),
+        (KeyboardUu, "u"),
+        (KeyboardVv, "v"),
+        (KeyboardWw, "w"),
+        (KeyboardXx, "x"),
+        (KeyboardYy, "y"),
+        (KeyboardZz, "z"),
+        (Keyboard1Exclamation, "1"),
+        (Keyboard2At, "2"),
+        (Keyboard3Hash, "3"),
+        (Keyboard4Dollar, "4"),
+        (Keyboard5Percent, "5"),
+        (Keyboard6Caret, "6"),
+        (Keyboard7Ampersand, "7"),
+        (Keyboard8Asterisk, "8"),
+        (Keyboard9OpenParens, "9"),
+        (Keyboard0CloseParens, "0"),
+        (KeyboardEnter, "ret"),
+        (KeyboardEscape, "esc"),
+        (KeyboardBackspace, "bspc"),
+        (KeyboardTab, "tab"),
+        (KeyboardSpacebar, "spc"),
+        (KeyboardDashUnderscore, "min"),
+        (KeyboardEqualPlus, "eql"),
+        (KeyboardOpenBracketBrace, "lbrc"),
+        (KeyboardCloseBracketBrace, "rbrc"),
+        (KeyboardBackslashBar, "bksl"),
+        (KeyboardSemiColon, "scln"),
+        (KeyboardSingleDoubleQuote, "apo"),
+        (KeyboardBacktickTilde, "grv"),
+        (KeyboardCommaLess, "comm"),
+        (KeyboardPeriodGreater, "."),
+        (KeyboardSlashQuestion, "/"),
+        (KeyboardCapsLock, "caps"),
+        (KeyboardF1, "f1"),
+        (KeyboardF2, "f2"),
+        (KeyboardF3, "f3"),
+        (KeyboardF4, "f4"),
+        (KeyboardF5, "f5"),
+        (KeyboardF6, "f6"),
+        (KeyboardF7, "f7"),
+        (KeyboardF8, "f8"),
+        (KeyboardF9, "f9"),
+        (KeyboardF10, "f10"),
+        (KeyboardF11, "f11"),
+        (KeyboardF12, "f12"),
+        (KeyboardPrintScreen, "prnt"),
+        (KeyboardScrollLock, "slck"),
+        (KeyboardPause, "pause"),
+        (KeyboardInsert, "ins"),
+        (KeyboardHome, "home"),
+        (KeyboardPageUp, "pgup"),
+        (KeyboardDelete, "del"),
+        (KeyboardEnd, "end"),
+        (KeyboardPageDown, "pgdn"),
+        (KeyboardRightArrow, "rght"),
+        (KeyboardLeftArrow, "left"),
+        (KeyboardDownArrow, "down"),
+        (KeyboardUpArrow, "up"),
+        (KeypadNumLock, "nlck"),
+        (KeypadDivide, "kp/"),
+        (KeypadMultiply, "kp*"),
+        (KeypadMinus, "kp-"),
+        (KeypadPlus, "kp+"),
+        (KeypadEnter, "kprt"),
+        (Keypad1End, "kp1"),
+        (Keypad2DownArrow, "kp2"),
+        (Keypad3PageDown, "kp3"),
+        (Keypad4LeftArrow, "kp4"),
+        (Keypad5, "kp5"),
+        (Keypad6RightArrow, "kp6"),
+        (Keypad7Home, "kp7"),
+        (Keypad8UpArrow, "kp8"),
+        (Keypad9PageUp, "kp9"),
+        (Keypad0Insert, "kp0"),
+        (KeypadPeriodDelete, "kp."),
+        (KeyboardNonUSSlash, "lsgt"),
+        (KeyboardApplication, "menu"),
+        (KeypadEqual, "kp="),
+        (KeyboardF13, "f13"),
+        (KeyboardF14, "f14"),
+        (KeyboardF15, "f15"),
+        (KeyboardF16, "f16"),
+        (KeyboardF17, "f17"),
+        (KeyboardF18, "f18"),
+        (KeyboardF19, "f19"),
+        (KeyboardF20, "f20"),
+        (KeyboardF21, "f21"),
+        (KeyboardF22, "f22"),
+        (KeyboardF23, "f23"),
+        (KeyboardF24, "f24"),
+        (KeyboardMute, "mute"),
+        (KeyboardVolumeUp, "volu"),
+        (KeyboardVolumeDown, "vold"),
+        (KeyboardInternational1, "ro"),
+        (KeyboardInternational3, "yen"),
+        (KeyboardInternational4, "henk"),
+        (KeyboardInternational5, "mhnk"),
+        (KeyboardLeftControl, "lctl"),
+        (KeyboardLeftShift, "lsft"),
+        (KeyboardLeftAlt, "lalt"),
+        (KeyboardLeftGUI, "lmet"),
+        (KeyboardRightControl, "rctl"),
+        (KeyboardRightShift, "rsft"),
+        (KeyboardRightAlt, "ralt"),
+        (KeyboardRightGUI, "rmet"),
+    ])
+});
+
+/// Linux input event codes from `linux/input-event-codes.h`, as the kernel's HID driver maps
+/// usages to them. The Non-US # key shares `KEY_BACKSLASH` with Backslash and is left out.
+static EVDEV_KEYS: Lazy<HashMap<KeyboardUsage, &'static str>> = Lazy::new(|| {
+    use KeyboardUsage::*;
+
+    HashMap::from([
+        (KeyboardAa, "KEY_A"),
+        (KeyboardBb, "KEY_B"),
+        (KeyboardCc, "KEY_C"),
+        (KeyboardDd, "KEY_D"),
+        (KeyboardEe, "KEY_E"),
+        (KeyboardFf, "KEY_F"),
+        (KeyboardGg, "KEY_G"),
+        (KeyboardHh, "KEY_H"),
+        (KeyboardIi, "KEY_I"),
+        (KeyboardJj, "KEY_J"),
+        (KeyboardKk, "KEY_K"),
+        (KeyboardLl, "KEY_L"),
+        (KeyboardMm, "KEY_M"),
+        (KeyboardNn, "KEY_N"),
+        (KeyboardOo, "KEY_O"),
+        (KeyboardPp, "KEY_P"),
+        (KeyboardQq, "KEY_Q"),
+        (KeyboardRr, "KEY_R"),
+        (KeyboardSs, "KEY_S"),
+        (KeyboardTt, "KEY_T"),
+        (KeyboardUu, "KEY_U"),
+        (KeyboardVv, "KEY_V"),
+        (KeyboardWw, "KEY_W"),
+        (KeyboardXx, "KEY_X"),
+        (KeyboardYy, "KEY_Y"),
+        (KeyboardZz, "KEY_Z"),
+        (Keyboard1Exclamation, "KEY_1"),
+        (Keyboard2At, "KEY_2"),
+        (Keyboard3Hash, "KEY_3"),
+        (Keyboard4Dollar, "KEY_4"),
+        (Keyboard5Percent, "KEY_5"),
+        (Keyboard6Caret, "KEY_6"),
+        (Keyboard7Ampersand, "KEY_7"),
+        (Keyboard8Asterisk, "KEY_8"),
+        (Keyboard9OpenParens, "KEY_9"),
+        (Keyboard0CloseParens, "KEY_0"),
+        (KeyboardEnter, "KEY_ENTER"),
+        (KeyboardEscape, "KEY_ESC"),
+        (KeyboardBackspace, "KEY_BACKSPACE"),
+        (KeyboardTab, "KEY_TAB"),
+        (KeyboardSpacebar, "KEY_SPACE"),
+        (KeyboardDashUnderscore, "KEY_MINUS"),
+        (KeyboardEqualPlus, "KEY_EQUAL"),
+        (KeyboardOpenBracketBrace, "KEY_LEFTBRACE"),
+        (KeyboardCloseBracketBrace, "KEY_RIGHTBRACE"),
+        (KeyboardBackslashBar, "KEY_BACKSLASH"),
+        (KeyboardSemiColon, "KEY_SEMICOLON"),
+        (KeyboardSingleDoubleQuote, "KEY_APOSTROPHE"),
+        (KeyboardBacktickTilde, "KEY_GRAVE"),
+        (KeyboardCommaLess, "KEY_COMMA"),
+        (KeyboardPeriodGreater, "KEY_DOT"),
+        (KeyboardSlashQuestion, "KEY_SLASH"),
+        (KeyboardCapsLock, "KEY_CAPSLOCK"),
+        (KeyboardF1, "KEY_F1"),
+        (KeyboardF2, "KEY_F2"),
+        (KeyboardF3, "KEY_F3"),
+        (KeyboardF4, "KEY_F4"),
+        (KeyboardF5, "KEY_F5"),
+        (KeyboardF6, "KEY_F6"),
+        (KeyboardF7, "KEY_F7"),
+        (KeyboardF8, "KEY_F8"),
+        (KeyboardF9, "KEY_F9"),
+        (KeyboardF10, "KEY_F10"),
+        (KeyboardF11, "KEY_F11"),
+        (KeyboardF12, "KEY_F12"),
+        (KeyboardPrintScreen, "KEY_SYSRQ"),
+        (KeyboardScrollLock, "KEY_SCROLLLOCK"),
+        (KeyboardPause, "KEY_PAUSE"),
+        (KeyboardInsert, "KEY_INSERT"),
+        (KeyboardHome, "KEY_HOME"),
+        (KeyboardPageUp, "KEY_PAGEUP"),
+        (KeyboardDelete, "KEY_DELETE"),
+        (KeyboardEnd, "KEY_END"),
+        (KeyboardPageDown, "KEY_PAGEDOWN"),
+        (KeyboardRightArrow, "KEY_RIGHT"),
+        (KeyboardLeftArrow, "KEY_LEFT"),
+        (KeyboardDownArrow, "KEY_DOWN"),
+        (KeyboardUpArrow, "KEY_UP"),
+        (KeypadNumLock, "KEY_NUMLOCK"),
+        (KeypadDivide, "KEY_KPSLASH"),
+        (KeypadMultiply, "KEY_KPASTERISK"),
+        (KeypadMinus, "KEY_KPMINUS"),
+        (KeypadPlus, "KEY_KPPLUS"),
+        (KeypadEnter, "KEY_KPENTER"),
+        (Keypad1End, "KEY_KP1"),
+        (Keypad2DownArrow, "KEY_KP2"),
+        (Keypad3PageDown, "KEY_KP3"),
+        (Keypad4LeftArrow, "KEY_KP4"),
+        (Keypad5, "KEY_KP5"),
+        (Keypad6RightArrow, "KEY_KP6"),
+        (Keypad7Home, "KEY_KP7"),
+        (Keypad8UpArrow, "KEY_KP8"),
+        (Keypad9PageUp, "KEY_KP9"),
+        (Keypad0Insert, "KEY_KP0"),
+        (KeypadPeriodDelete, "KEY_KPDOT"),
+        (KeyboardNonUSSlash, "KEY_102ND"),
+        (KeyboardApplication, "KEY_COMPOSE"),
+        (KeyboardPower, "KEY_POWER"),
+        (KeypadEqual, "KEY_KPEQUAL"),
+        (KeyboardF13, "KEY_F13"),
+        (KeyboardF14, "KEY_F14"),
+        (KeyboardF15, "KEY_F15"),
+        (KeyboardF16, "KEY_F16"),
+        (KeyboardF17, "KEY_F17"),
+        (KeyboardF18, "KEY_F18"),
+        (KeyboardF19, "KEY_F19"),
+        (KeyboardF20, "KEY_F20"),
+        (KeyboardF21, "KEY_F21"),
+        (KeyboardF22, "KEY_F22"),
+        (KeyboardF23, "KEY_F23"),
+        (KeyboardF24, "KEY_F24"),
+        (KeyboardMute, "KEY_MUTE"),
+        (KeyboardVolumeUp, "KEY_VOLUMEUP"),
+        (KeyboardVolumeDown, "KEY_VOLUMEDOWN"),
+        (KeyboardExecute, "KEY_OPEN"),
+        (KeyboardHelp, "KEY_HELP"),
+        (KeyboardMenu, "KEY_PROPS"),
+        (KeyboardSelect, "KEY_FRONT"),
+        (KeyboardStop, "KEY_STOP"),
+        (KeyboardAgain, "KEY_AGAIN"),
+        (KeyboardUndo, "KEY_UNDO"),
+        (KeyboardCut, "KEY_CUT"),
+        (KeyboardCopy, "KEY_COPY"),
+        (KeyboardPaste, "KEY_PASTE"),
+        (KeyboardFind, "KEY_FIND"),
+        (KeypadComma, "KEY_KPCOMMA"),
+        (KeyboardInternational1, "KEY_RO"),
+        (KeyboardInternational2, "KEY_KATAKANAHIRAGANA"),
+        (KeyboardInternational3, "KEY_YEN"),
+        (KeyboardInternational4, "KEY_HENKAN"),
+        (KeyboardInternational5, "KEY_MUHENKAN"),
+        (KeyboardInternational6, "KEY_KPJPCOMMA"),
+        (KeyboardLANG1, "KEY_HANGEUL"),
+        (KeyboardLANG2, "KEY_HANJA"),
+        (KeyboardLANG3, "KEY_KATAKANA"),
+        (KeyboardLANG4, "KEY_HIRAGANA"),
+        (KeyboardLANG5, "KEY_ZENKAKUHANKAKU"),
+        (KeypadOpenParens, "KEY_KPLEFTPAREN"),
+        (KeypadCloseParens, "KEY_KPRIGHTPAREN"),
+        (KeypadPositiveNegative, "KEY_KPPLUSMINUS"),
+        (KeyboardLeftControl, "KEY_LEFTCTRL"),
+        (KeyboardLeftShift, "KEY_LEFTSHIFT"),
+        (KeyboardLeftAlt, "KEY_LEFTALT"),
+        (KeyboardLeftGUI, "KEY_LEFTMETA"),
+        (KeyboardRightControl, "KEY_RIGHTCTRL"),
+        (KeyboardRightShift, "KEY_RIGHTSHIFT"),
+        (KeyboardRightAlt, "KEY_RIGHTALT"),
+        (KeyboardRightGUI, "KEY_RIGHTMETA"),
+    ])
+});
+
+/// ConsumerUsage describes the Consumer page controls a keyboard can send besides keys: media
+/// transport, volume, display brightness and application launch keys. Unlike the Keyboard page
+/// Mute and Volume keys, operating systems act on these everywhere.
+///
+/// Reference: <https://usb.org/sites/default/files/hut1_3_0.pdf> (Section 15, page 117)
+#[repr(u16)]
+#[derive(Copy, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ConsumerUsage {
+    /// Display Brightness Increment
+    DisplayBrightnessIncrement = 0x06F,
+    /// Display Brightness Decrement
+    DisplayBrightnessDecrement = 0x070,
+    /// Fast Forward
+    FastForward = 0x0B3,
+    /// Rewind
+    Rewind = 0x0B4,
+    /// Scan Next Track
+    ScanNextTrack = 0x0B5,
+    /// Scan Previous Track
+    ScanPreviousTrack = 0x0B6,
+    /// Stop
+    Stop = 0x0B7,
+    /// Eject
+    Eject = 0x0B8,
+    /// Play/Pause
+    PlayPause = 0x0CD,
+    /// Mute
+    Mute = 0x0E2,
+    /// Volume Increment
+    VolumeIncrement = 0x0E9,
+    /// Volume Decrement
+    VolumeDecrement = 0x0EA,
+    /// AL Consumer Control Configuration (the media player)
+    ALConsumerControlConfiguration = 0x183,
+    /// AL Email Reader
+    ALEmailReader = 0x18A,
+    /// AL Calculator
+    ALCalculator = 0x192,
+    /// AL Local Machine Browser (My Computer)
+    ALLocalMachineBrowser = 0x194,
+    /// AL Control Panel
+    ALControlPanel = 0x19F,
+    /// AC Search
+    ACSearch = 0x221,
+    /// AC Home
+    ACHome = 0x223,
+    /// AC Back
+    ACBack = 0x224,
+    /// AC Forward
+    ACForward = 0x225,
+    /// AC Stop
+    ACStop = 0x226,
+    /// AC Refresh
+    ACRefresh = 0x227,
+    /// AC Bookmarks
+    ACBookmarks = 0x22A,
+}
+
+/// A consumer control with its label and its names as QMK, ZMK, Kanata and evdev keys
+type ConsumerNames = (ConsumerUsage, &'static str, Option<&'static str>, Option<&'static str>, Option<&'static str>, Option<&'static str>);
+
+/// Every consumer control, in usage order
+const CONSUMER_NAMES: [ConsumerNames; 24] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, "Bri+", Some("KC_BRIU"), Some("C_BRI_UP"), Some("brup"), Some("KEY_BRIGHTNESSUP")),
+    (ConsumerUsage::DisplayBrightnessDecrement, "Bri-", Some("KC_BRID"), Some("C_BRI_DN"), Some("brdown"), Some("KEY_BRIGHTNESSDOWN")),
+    (ConsumerUsage::FastForward, "FFwd", Some("KC_MFFD"), Some("C_FF"), None, Some("KEY_FASTFORWARD")),
+    (ConsumerUsage::Rewind, "Rewind", Some("KC_MRWD"), Some("C_RW"), None, Some("KEY_REWIND")),
+    (ConsumerUsage::ScanNextTrack, "Next", Some("KC_MNXT"), Some("C_NEXT"), Some("next"), Some("KEY_NEXTSONG")),
+    (ConsumerUsage::ScanPreviousTrack, "Prev", Some("KC_MPRV"), Some("C_PREV"), Some("prev"), Some("KEY_PREVIOUSSONG")),
+    (ConsumerUsage::Stop, "Stop", Some("KC_MSTP"), Some("C_STOP"), None, Some("KEY_STOPCD")),
+    (ConsumerUsage::Eject, "Eject", Some("KC_EJCT"), Some("C_EJECT"), None, Some("KEY_EJECTCD")),
+    (ConsumerUsage::PlayPause, "Play", Some("KC_MPLY"), Some("C_PP"), Some("pp"), Some("KEY_PLAYPAUSE")),
+    (ConsumerUsage::Mute, "Mute", Some("KC_MUTE"), Some("C_MUTE"), Some("mute"), Some("KEY_MUTE")),
+    (ConsumerUsage::VolumeIncrement, "Vol+", Some("KC_VOLU"), Some("C_VOL_UP"), Some("volu"), Some("KEY_VOLUMEUP")),
+    (ConsumerUsage::VolumeDecrement, "Vol-", Some("KC_VOLD"), Some("C_VOL_DN"), Some("voldwn"), Some("KEY_VOLUMEDOWN")),
+    (ConsumerUsage::ALConsumerControlConfiguration, "Player", Some("KC_MSEL"), Some("C_AL_CCC"), None, Some("KEY_CONFIG")),
+    (ConsumerUsage::ALEmailReader, "Mail", Some("KC_MAIL"), Some("C_AL_MAIL"), None, Some("KEY_MAIL")),
+    (ConsumerUsage::ALCalculator, "Calc", Some("KC_CALC"), Some("C_AL_CALC"), None, Some("KEY_CALC")),
+    (ConsumerUsage::ALLocalMachineBrowser, "My PC", Some("KC_MYCM"), Some("C_AL_MY_COMPUTER"), None, Some("KEY_FILE")),
+    (ConsumerUsage::ALControlPanel, "CtlPnl", Some("KC_CPNL"), Some("C_AL_CONTROL_PANEL"), None, Some("KEY_CONTROLPANEL")),
+    (ConsumerUsage::ACSearch, "Search", Some("KC_WSCH"), Some("C_AC_SEARCH"), None, Some("KEY_SEARCH")),
+    (ConsumerUsage::ACHome, "WHome", Some("KC_WHOM"), Some("C_AC_HOME"), None, Some("KEY_HOMEPAGE")),
+    (ConsumerUsage::ACBack, "WBack", Some("KC_WBAK"), Some("C_AC_BACK"), None, Some("KEY_BACK")),
+    (ConsumerUsage::ACForward, "WFwd", Some("KC_WFWD"), Some("C_AC_FORWARD"), None, Some("KEY_FORWARD")),
+    (ConsumerUsage::ACStop, "WStop", Some("KC_WSTP"), Some("C_AC_STOP"), None, Some("KEY_STOP")),
+    (ConsumerUsage::ACRefresh, "Reload", Some("KC_WREF"), Some("C_AC_REFRESH"), None, Some("KEY_REFRESH")),
+    (ConsumerUsage::ACBookmarks, "Favs", Some("KC_WFAV"), Some("C_AC_BOOKMARKS"), None, Some("KEY_BOOKMARKS")),
+];
+
+/// Other names QMK and ZMK accept for consumer controls: the long forms
+const CONSUMER_ALIASES: [(ConsumerUsage, NamingScheme, &str); 35] = [
+    (ConsumerUsage::DisplayBrightnessIncrement, NamingScheme::Qmk, "KC_BRIGHTNESS_UP"),
+    (ConsumerUsage::DisplayBrightnessDecrement, NamingScheme::Qmk, "KC_BRIGHTNESS_DOWN"),
+    (ConsumerUsage::FastForward, NamingScheme::Qmk, "KC_MEDIA_FAST_FORWARD"),
+    (ConsumerUsage::Rewind, NamingScheme::Qmk, "KC_MEDIA_REWIND"),
+    (ConsumerUsage::ScanNextTrack, NamingScheme::Qmk, "KC_MEDIA_NEXT_TRACK"),
+    (ConsumerUsage::ScanPreviousTrack, NamingScheme::Qmk, "KC_MEDIA_PREV_TRACK"),
+    (ConsumerUsage::Stop, NamingScheme::Qmk, "KC_MEDIA_STOP"),
+    (ConsumerUsage::Eject, NamingScheme::Qmk, "KC_MEDIA_EJECT"),
+    (ConsumerUsage::PlayPause, NamingScheme::Qmk, "KC_MEDIA_PLAY_PAUSE"),
+    (ConsumerUsage::Mute, NamingScheme::Qmk, "KC_AUDIO_MUTE"),
+    (ConsumerUsage::VolumeIncrement, NamingScheme::Qmk, "KC_AUDIO_VOL_UP"),
+    (ConsumerUsage::VolumeDecrement, NamingScheme::Qmk, "KC_AUDIO_VOL_DOWN"),
+    (ConsumerUsage::ALConsumerControlConfiguration, NamingScheme::Qmk, "KC_MEDIA_SELECT"),
+    (ConsumerUsage::ALCalculator, NamingScheme::Qmk, "KC_CALCULATOR"),
+    (ConsumerUsage::ALLocalMachineBrowser, NamingScheme::Qmk, "KC_MY_COMPUTER"),
+    (ConsumerUsage::ALControlPanel, NamingScheme::Qmk, "KC_CONTROL_PANEL"),
+    (ConsumerUsage::ACSearch, NamingScheme::Qmk, "KC_WWW_SEARCH"),
+    (ConsumerUsage::ACHome, NamingScheme::Qmk, "KC_WWW_HOME"),
+    (ConsumerUsage::ACBack, NamingScheme::Qmk, "KC_WWW_BACK"),
+    (ConsumerUsage::ACForward, NamingScheme::Qmk, "KC_WWW_FORWARD"),
+    (ConsumerUsage::ACStop, NamingScheme::Qmk, "KC_WWW_STOP"),
+    (ConsumerUsage::ACRefresh, NamingScheme::Qmk, "KC_WWW_REFRESH"),
+    (ConsumerUsage::ACBookmarks, NamingScheme::Qmk, "KC_WWW_FAVORITES"),
+    (ConsumerUsage::DisplayBrightnessIncrement, NamingScheme::Zmk, "C_BRIGHTNESS_INC"),
+    (ConsumerUsage::DisplayBrightnessDecrement, NamingScheme::Zmk, "C_BRIGHTNESS_DEC"),
+    (ConsumerUsage::FastForward, NamingScheme::Zmk, "C_FAST_FORWARD"),
+    (ConsumerUsage::Rewind, NamingScheme::Zmk, "C_REWIND"),
+    (ConsumerUsage::PlayPause, NamingScheme::Zmk, "C_PLAY_PAUSE"),
+    (ConsumerUsage::VolumeIncrement, NamingScheme::Zmk, "C_VOLUME_UP"),
+    (ConsumerUsage::VolumeDecrement, NamingScheme::Zmk, "C_VOLUME_DOWN"),
+    (ConsumerUsage::ALConsumerControlConfiguration, NamingScheme::Zmk, "C_AL_CONSUMER_CONTROL_CONFIGURATION"),
+    (ConsumerUsage::ALEmailReader, NamingScheme::Zmk, "C_AL_EMAIL"),
+    (ConsumerUsage::ALCalculator, NamingScheme::Zmk, "C_AL_CALCULATOR"),
+    (ConsumerUsage::ALLocalMachineBrowser, NamingScheme::Zmk, "C_AL_LOCAL_BROWSER"),
+    (ConsumerUsage::ACBookmarks, NamingScheme::Zmk, "C_AC_FAVORITES"),
+];
+
+impl ConsumerUsage {
+    /// Every consumer control, in usage order
+    pub fn all() -> impl Iterator<Item = Self> {
+        CONSUMER_NAMES.iter().map(|&(usage, ..)| usage)
+    }
+
+    /// Look up a control by its label or any of its names, e.g. `Play`, `KC_MPLY` or `C_PP`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::keycodes::ConsumerUsage;
+    ///
+    /// assert_eq!(ConsumerUsage::from_label("Play"), Some(ConsumerUsage::PlayPause));
+    /// assert_eq!(ConsumerUsage::from_label("KC_VOLU"), Some(ConsumerUsage::VolumeIncrement));
+    /// assert_eq!(ConsumerUsage::from_label("A"), None);
+    /// ```
+    pub fn from_label(label: &str) -> Option<Self> {
+        CONSUMER_NAMES.iter()
+            .find(|&&(_, name, ..)| name == label)
+            .map(|&(usage, ..)| usage)
+            .or_else(|| NamingScheme::ALL.into_iter().find_map(|scheme| Self::from_alias(scheme, label)))
+    }
+
+    /// The control's name in a naming scheme, e.g. `KC_MPLY` for Play/Pause in QMK
+    pub fn alias(self, scheme: NamingScheme) -> Option<&'static str> {
+        let &(_, _, qmk, zmk, kanata, evdev) = CONSUMER_NAMES.iter().find(|&&(usage, ..)| usage == self)?;
+        match scheme {
+            NamingScheme::Qmk => qmk,
+            NamingScheme::Zmk => zmk,
+            NamingScheme::Kanata => kanata,
+            NamingScheme::Evdev => evdev,
+        }
+    }
+
+    /// The control a name stands for in a naming scheme, by its name or any other the scheme accepts
+    pub fn from_alias(scheme: NamingScheme, name: &str) -> Option<Self> {
+        Self::all().find(|usage| usage.alias(scheme) == Some(name)).or_else(|| {
+            CONSUMER_ALIASES.iter()
+                .find(|&&(_, other, alias)| other == scheme && alias == name)
+                .map(|&(usage, ..)| usage)
+        })
+    }
+}
+
+impl From<ConsumerUsage> for &'static str {
+    /// Convert a consumer control to its display label
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use layout_rs::keycodes::ConsumerUsage;
+    ///
+    /// let label: &str = ConsumerUsage::VolumeIncrement.into();
+    /// assert_eq!(label, "Vol+");
+    /// ```
+    fn from(usage: ConsumerUsage) -> Self {
+        CONSUMER_NAMES.iter()
+            .find(|&&(other, ..)| other == usage)
+            .map(|&(_, label, ..)| label)
+            .unwrap_or("Unknown")
+    }
+}
+
+impl TryFrom<u16> for ConsumerUsage {
+    type Error = u16;
+
+    /// Convert a Consumer page usage code, returning the code back if it is not supported
+    fn try_from(code: u16) -> Result<Self, u16> {
+        Self::all().find(|&usage| usage as u16 == code).ok_or(code)
+    }
+}
+
+impl Serialize for ConsumerUsage {
+    /// Serialize as the HID usage code, like [`KeyboardUsage`]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(*self as u16)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConsumerUsage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = u16::deserialize(deserializer)?;
+        Self::try_from(code).map_err(|code| {
+            de::Error::invalid_value(de::Unexpected::Unsigned(code.into()), &"a supported HID consumer usage code")
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1003,6 +2333,74 @@ mod tests {
         assert_eq!(original, converted_back);
     }
 
+    #[test]
+    fn test_every_usage_round_trips_through_its_label() {
+        let mut labels = HashMap::new();
+        for usage in (0..=u8::MAX).map(KeyboardUsage::from) {
+            let label: &str = usage.into();
+            if usage != KeyboardUsage::Reserved {
+                assert_ne!(label, "Unknown", "{:?} has no label", usage);
+            }
+            assert_eq!(KeyboardUsage::from(label), usage, "label '{}'", label);
+            assert_eq!(<&str>::from(KeyboardUsage::from(label)), label);
+            if let Some(other) = labels.insert(label, usage) {
+                assert_eq!(other, usage, "'{}' labels both {:?} and {:?}", label, other, usage);
+            }
+        }
+    }
+
+    #[test]
+    fn test_aliases_round_trip() {
+        for scheme in NamingScheme::ALL {
+            for usage in (0..=u8::MAX).map(KeyboardUsage::from) {
+                if let Some(name) = usage.alias(scheme) {
+                    assert_eq!(KeyboardUsage::from_alias(scheme, name), Some(usage), "{:?} '{}'", scheme, name);
+                }
+            }
+        }
+        assert_eq!(KeyboardUsage::KeyboardAa.alias(NamingScheme::Kanata), Some("a"));
+        assert_eq!(KeyboardUsage::KeyboardAa.alias(NamingScheme::Evdev), Some("KEY_A"));
+        assert_eq!(KeyboardUsage::from_alias(NamingScheme::Qmk, "KC_ESCAPE"), Some(KeyboardUsage::KeyboardEscape));
+        assert_eq!(KeyboardUsage::from_alias(NamingScheme::Evdev, "KEY_A"), Some(KeyboardUsage::KeyboardAa));
+        assert_eq!(KeyboardUsage::KeypadHexadecimal.alias(NamingScheme::Qmk), None);
+    }
+
+    #[test]
+    fn test_labels_accept_aliases() {
+        for label in ["Esc", "KC_ESC", "KC_ESCAPE", "ESC", "esc", "KEY_ESC"] {
+            assert_eq!(KeyboardUsage::from(label), KeyboardUsage::KeyboardEscape, "{}", label);
+        }
+        assert_eq!(KeyboardUsage::from("lsft"), KeyboardUsage::KeyboardLeftShift);
+        // Labels come first where a name means something else in another scheme
+        assert_eq!(KeyboardUsage::from("A"), KeyboardUsage::KeyboardAa);
+        let label: &str = KeyboardUsage::from("KC_ESC").into();
+        assert_eq!(label, "Esc");
+    }
+
+    #[test]
+    fn test_consumer_usages() {
+        for usage in ConsumerUsage::all() {
+            let label: &str = usage.into();
+            assert_eq!(ConsumerUsage::from_label(label), Some(usage), "{}", label);
+            assert_eq!(KeyboardUsage::from(label), KeyboardUsage::Reserved, "'{}' is also a key label", label);
+            assert_eq!(ConsumerUsage::try_from(usage as u16), Ok(usage));
+            for scheme in NamingScheme::ALL {
+                if let Some(name) = usage.alias(scheme) {
+                    assert_eq!(ConsumerUsage::from_alias(scheme, name), Some(usage), "{:?} '{}'", scheme, name);
+                }
+            }
+        }
+        assert_eq!(ConsumerUsage::all().count(), CONSUMER_NAMES.len());
+        assert_eq!(ConsumerUsage::from_label("KEY_PLAYPAUSE"), Some(ConsumerUsage::PlayPause));
+        assert_eq!(ConsumerUsage::from_alias(NamingScheme::Qmk, "KC_AUDIO_VOL_UP"), Some(ConsumerUsage::VolumeIncrement));
+        assert_eq!(ConsumerUsage::from_alias(NamingScheme::Zmk, "C_PLAY_PAUSE"), Some(ConsumerUsage::PlayPause));
+        assert_eq!(ConsumerUsage::try_from(0x00E8), Err(0x00E8));
+
+        assert_eq!(serde_json::to_string(&ConsumerUsage::PlayPause).unwrap(), "205");
+        assert_eq!(serde_json::from_str::<ConsumerUsage>("548").unwrap(), ConsumerUsage::ACBack);
+        assert!(serde_json::from_str::<ConsumerUsage>("1").is_err());
+    }
+
     #[test]
     fn test_lazy_static_performance() {
         // Test that multiple calls to get label don't regenerate the map
@@ -1050,6 +2448,18 @@ mod tests {
         assert_eq!(keycode, KeyboardUsage::Reserved);
     }
 
+    #[test]
+    fn test_serde_uses_usage_codes() {
+        assert_eq!(serde_json::to_string(&KeyboardUsage::KeyboardEnter).unwrap(), "40");
+        assert_eq!(serde_json::from_str::<KeyboardUsage>("40").unwrap(), KeyboardUsage::KeyboardEnter);
+        assert_eq!(serde_json::from_str::<KeyboardUsage>("\"KeyboardEnter\"").unwrap(), KeyboardUsage::KeyboardEnter);
+
+        // Unassigned codes and unknown names are errors rather than Reserved
+        assert!(serde_json::from_str::<KeyboardUsage>("165").is_err());
+        assert!(serde_json::from_str::<KeyboardUsage>("300").is_err());
+        assert!(serde_json::from_str::<KeyboardUsage>("\"KeyboardRaise\"").is_err());
+    }
+
     #[test]
     fn test_from_implementations_consistency() {
         // Test that all From implementations are consistent
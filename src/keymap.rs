@@ -0,0 +1,1488 @@
+//! # Keymap Model
+//!
+//! [`Keymap`] is the editable state of one keyboard: its layers, the saved copy they are
+//! compared against, the profile list and the undo history. It reads and writes layouts
+//! through a [`KeymapStore`] and knows nothing about the UI, so it can be used from native
+//! tools as well as from the editor.
+
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use serde_json;
+use serde::{Serialize, Deserialize};
+use crate::actions::KeyAction;
+use crate::formats::{kle, Conversion, ExportFormat, ImportFormat};
+use crate::geometry::KeyboardGeometry;
+use crate::keyboards::{self, KeyboardModel};
+use crate::keycodes::KeyboardUsage;
+use crate::storage::{self, ProfileIndex};
+use crate::store::KeymapStore;
+
+/// localStorage key remembering which keyboard model was last edited
+const SELECTED_KEYBOARD_KEY: &str = "selected_keyboard";
+
+/// localStorage key holding the keyboard models imported from layout files
+const CUSTOM_KEYBOARDS_KEY: &str = "custom_keyboards";
+
+#[derive(Serialize, Deserialize)]
+struct KeymapExport {
+    metadata: ExportMetadata,
+    layers: Vec<LayerExport>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportMetadata {
+    version: String,
+    keyboard: String,
+    keyboard_id: String,
+    exported_at: String,
+    total_layers: usize,
+    total_keys: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayerExport {
+    name: String,
+    keys: Vec<KeymapEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeymapEntry {
+    position: (usize, usize),
+    label: String,
+    action: KeyAction,
+}
+
+/// Version written to `ExportMetadata.version`. Files with the same major version, or an
+/// older one, can be imported.
+const EXPORT_VERSION: &str = "3.0";
+
+/// Lenient view of an exported keymap file, covering every export version:
+/// 1.x has a single `keys` list, 2.x adds `layers`, 3.x replaces each entry's HID `keycode`
+/// with an `action`
+#[derive(Deserialize)]
+struct KeymapImport {
+    metadata: ImportMetadata,
+    #[serde(default)]
+    layers: Vec<LayerImport>,
+    #[serde(default)]
+    keys: Vec<EntryImport>,
+}
+
+#[derive(Deserialize)]
+struct ImportMetadata {
+    version: String,
+    #[serde(default)]
+    keyboard_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LayerImport {
+    name: String,
+    keys: Vec<EntryImport>,
+}
+
+#[derive(Deserialize)]
+struct EntryImport {
+    position: (usize, usize),
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    keycode: Option<u8>,
+    #[serde(default)]
+    action: Option<serde_json::Value>,
+}
+
+/// Labels that named keyboard page keys before the editor had consumer controls, which now
+/// take these labels. Only entries without an action, written before 3.0, are read with them.
+const LEGACY_LABELS: [(&str, KeyboardUsage); 4] = [
+    ("Vol+", KeyboardUsage::KeyboardVolumeUp),
+    ("Vol-", KeyboardUsage::KeyboardVolumeDown),
+    ("Mute", KeyboardUsage::KeyboardMute),
+    ("Stop", KeyboardUsage::KeyboardStop),
+];
+
+/// Number of edits that can be undone
+const HISTORY_LIMIT: usize = 100;
+
+/// Layers and active layer before an edit, for undo/redo
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    layers: Vec<Layer>,
+    active_layer: usize,
+}
+
+/// A named layer of key assignments
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    pub keys: HashMap<(usize, usize), KeyAction>,
+    /// Keycodes imported from firmware files that no [`KeyAction`] can represent. They are
+    /// exported unchanged to the same format until the key is edited.
+    pub unmapped: HashMap<(usize, usize), UnmappedKeycode>,
+}
+
+impl Layer {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), keys: HashMap::new(), unmapped: HashMap::new() }
+    }
+}
+
+/// A keycode kept verbatim from an imported firmware file
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct UnmappedKeycode {
+    /// Format the keycode was written in, e.g. `"qmk"`
+    pub format: String,
+    pub keycode: String,
+}
+
+#[derive(Clone)]
+pub struct Keymap {
+    store: Rc<dyn KeymapStore>,
+    keyboard: Rc<KeyboardModel>,
+    current: Vec<Layer>,
+    saved: Vec<Layer>,
+    active_layer: usize,
+    /// Layout profiles of this keyboard; `current` and `saved` belong to the active one
+    profiles: ProfileIndex,
+    /// States before each edit, most recent last
+    undo_stack: VecDeque<Snapshot>,
+    /// States undone since the last edit, most recent last
+    redo_stack: Vec<Snapshot>,
+}
+
+impl Keymap {
+    /// Open the keyboard model that was edited last, or the default model
+    pub fn with_store(store: Rc<dyn KeymapStore>) -> Self {
+        let keyboard = store.get(SELECTED_KEYBOARD_KEY).ok().flatten()
+            .and_then(|id| Self::find_keyboard(store.as_ref(), &id))
+            .unwrap_or_else(|| keyboards::default_keyboard().clone());
+        Self::for_keyboard(store, &keyboard)
+    }
+
+    /// Open the last used profile of a keyboard model, with its saved layout or the
+    /// model's default layers if none is saved
+    pub fn for_keyboard(store: Rc<dyn KeymapStore>, keyboard: &KeyboardModel) -> Self {
+        let mut keymap = Self::with_layers(store, Rc::new(keyboard.clone()), Vec::new());
+        keymap.profiles = keymap.load_profiles();
+        keymap.open_active_profile();
+
+        log(&format!(
+            "Keymap for {} initialized with {} layers (profile '{}')",
+            keyboard.name, keymap.current.len(), keymap.profiles.active().name
+        ));
+
+        keymap
+    }
+
+    /// Create a keymap from the given layers, treating them as the saved state
+    pub fn with_layers(store: Rc<dyn KeymapStore>, keyboard: Rc<KeyboardModel>, layers: Vec<Layer>) -> Self {
+        let layers = if layers.is_empty() { keyboard.default_layers.clone() } else { layers };
+        Self {
+            store,
+            keyboard,
+            current: layers.clone(),
+            saved: layers,
+            active_layer: 0,
+            profiles: ProfileIndex::default(),
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn keyboard(&self) -> &Rc<KeyboardModel> {
+        &self.keyboard
+    }
+
+    pub fn geometry(&self) -> &KeyboardGeometry {
+        &self.keyboard.geometry
+    }
+
+    /// Switch the editor to another keyboard model. Unsaved changes to the current
+    /// model are discarded; the new model's saved layout is loaded.
+    pub fn switch_keyboard(&mut self, id: &str) -> Result<(), String> {
+        let keyboard = Self::find_keyboard(self.store.as_ref(), id)
+            .ok_or_else(|| format!("Unknown keyboard model '{}'", id))?;
+        *self = Self::for_keyboard(self.store.clone(), &keyboard);
+
+        if let Err(e) = self.store.set(SELECTED_KEYBOARD_KEY, id) {
+            log(&format!("Failed to remember keyboard selection: {}", e));
+        }
+        Ok(())
+    }
+
+    /// Keyboard models imported from layout files, in the order they were added
+    pub fn custom_keyboards(&self) -> Vec<KeyboardModel> {
+        Self::load_custom_keyboards(self.store.as_ref())
+    }
+
+    /// Save an imported keyboard model, replacing a saved model with the same id, and
+    /// switch the editor to it
+    pub fn add_keyboard(&mut self, keyboard: KeyboardModel) -> Result<(), String> {
+        if keyboards::find_keyboard(&keyboard.id).is_some() {
+            return Err(format!("'{}' is the id of a built-in keyboard model", keyboard.id));
+        }
+
+        let mut custom = self.custom_keyboards();
+        match custom.iter_mut().find(|model| model.id == keyboard.id) {
+            Some(model) => *model = keyboard.clone(),
+            None => custom.push(keyboard.clone()),
+        }
+        self.store.set(CUSTOM_KEYBOARDS_KEY, &storage::serialize_keyboards(&custom)?)?;
+
+        log(&format!("Saved keyboard model {} with {} keys", keyboard.name, keyboard.geometry.keys().len()));
+        self.switch_keyboard(&keyboard.id)
+    }
+
+    pub fn profiles(&self) -> &ProfileIndex {
+        &self.profiles
+    }
+
+    /// Open another profile of this keyboard. Unsaved changes are discarded.
+    pub fn switch_profile(&mut self, id: &str) -> Result<(), String> {
+        self.profiles.set_active(id)?;
+        self.open_active_profile();
+        self.save_profiles()
+    }
+
+    /// Create a profile with the keyboard's default layout and open it
+    pub fn create_profile(&mut self, name: String) -> Result<(), String> {
+        let id = self.profiles.create(&name)?;
+        self.switch_profile(&id)
+    }
+
+    /// Copy the active profile, including unsaved changes, into a new profile, save it
+    /// and open it
+    pub async fn duplicate_profile(&mut self) -> Result<(), String> {
+        let save = self.start_duplicate_profile()?.wait().await?;
+        self.finish_save(save);
+        Ok(())
+    }
+
+    /// Copy the active profile into a new profile and open it, starting its save as
+    /// [`Keymap::start_save`] does
+    pub fn start_duplicate_profile(&mut self) -> Result<PendingSave, String> {
+        let name = self.profiles.copy_name(&self.profiles.active().name);
+        let id = self.profiles.create(&name)?;
+        self.profiles.set_active(&id)?;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.start_save()
+    }
+
+    pub fn rename_profile(&mut self, name: String) -> Result<(), String> {
+        let id = self.profiles.active().id.clone();
+        self.profiles.rename(&id, &name)?;
+        self.save_profiles()
+    }
+
+    /// Delete the active profile and its saved layout, then open the first remaining profile
+    pub fn delete_profile(&mut self) -> Result<(), String> {
+        let id = self.profiles.active().id.clone();
+        let storage_key = self.profile_storage_key();
+        self.profiles.remove(&id)?;
+        self.store.remove(&storage_key)?;
+        self.open_active_profile();
+        self.save_profiles()
+    }
+
+    /// Key assignments of the active layer
+    pub fn current(&self) -> &HashMap<(usize, usize), KeyAction> {
+        &self.current[self.active_layer].keys
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.current
+    }
+
+    pub fn active_layer(&self) -> usize {
+        self.active_layer
+    }
+
+    pub fn set_active_layer(&mut self, index: usize) -> Result<(), String> {
+        self.check_layer_index(index)?;
+        self.active_layer = index;
+        Ok(())
+    }
+
+    /// Update a key on the active layer from its editor label
+    pub fn update_key(&mut self, row: usize, col: usize, label: String) -> Result<(), String> {
+        let before = self.snapshot();
+        if !self.geometry().contains((row, col)) {
+            return Err(format!("No key at position [{}, {}] on {}", row, col, self.keyboard.name));
+        }
+        let action: KeyAction = label.parse()?;
+        let layer = &mut self.current[self.active_layer];
+        layer.keys.insert((row, col), action);
+        layer.unmapped.remove(&(row, col));
+        self.record_edit(before);
+        Ok(())
+    }
+
+    /// Append an empty layer and make it active
+    pub fn add_layer(&mut self, name: String) -> Result<(), String> {
+        let before = self.snapshot();
+        let name = self.validate_layer_name(&name, None)?;
+        self.current.push(Layer::new(name));
+        self.active_layer = self.current.len() - 1;
+        self.record_edit(before);
+        Ok(())
+    }
+
+    pub fn rename_layer(&mut self, index: usize, name: String) -> Result<(), String> {
+        let before = self.snapshot();
+        self.check_layer_index(index)?;
+        let name = self.validate_layer_name(&name, Some(index))?;
+        self.current[index].name = name;
+        self.record_edit(before);
+        Ok(())
+    }
+
+    /// Move a layer to a new position, keeping it active if it was
+    pub fn move_layer(&mut self, from: usize, to: usize) -> Result<(), String> {
+        let before = self.snapshot();
+        self.check_layer_index(from)?;
+        self.check_layer_index(to)?;
+
+        // New index of every layer, indexed by its old position
+        let mut order: Vec<usize> = (0..self.current.len()).collect();
+        let moved = order.remove(from);
+        order.insert(to, moved);
+        let mut new_index = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+
+        let layer = self.current.remove(from);
+        self.current.insert(to, layer);
+        self.active_layer = new_index[self.active_layer];
+        self.remap_layer_references(|layer| Some(new_index.get(layer).copied().unwrap_or(layer)));
+        self.record_edit(before);
+        Ok(())
+    }
+
+    /// Insert a copy of a layer right after it and make the copy active
+    pub fn duplicate_layer(&mut self, index: usize) -> Result<(), String> {
+        let before = self.snapshot();
+        self.check_layer_index(index)?;
+
+        let mut copy = self.current[index].clone();
+        let base_name = format!("{} copy", copy.name);
+        let mut name = base_name.clone();
+        let mut suffix = 2;
+        while self.current.iter().any(|layer| layer.name == name) {
+            name = format!("{} {}", base_name, suffix);
+            suffix += 1;
+        }
+        copy.name = name;
+
+        self.current.insert(index + 1, copy);
+        self.active_layer = index + 1;
+        self.remap_layer_references(|layer| Some(if layer > index { layer + 1 } else { layer }));
+        self.record_edit(before);
+        Ok(())
+    }
+
+    pub fn delete_layer(&mut self, index: usize) -> Result<(), String> {
+        let before = self.snapshot();
+        self.check_layer_index(index)?;
+        if self.current.len() == 1 {
+            return Err("Cannot delete the only layer".to_string());
+        }
+
+        self.current.remove(index);
+        if self.active_layer > index || self.active_layer == self.current.len() {
+            self.active_layer -= 1;
+        }
+        self.remap_layer_references(|layer| match layer {
+            layer if layer == index => None,
+            layer if layer > index => Some(layer - 1),
+            layer => Some(layer),
+        });
+        self.record_edit(before);
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Revert the most recent edit
+    pub fn undo(&mut self) -> Result<(), String> {
+        let previous = self.undo_stack.pop_back().ok_or("Nothing to undo")?;
+        self.redo_stack.push(self.snapshot());
+        self.restore(previous);
+        Ok(())
+    }
+
+    /// Reapply the most recently undone edit
+    pub fn redo(&mut self) -> Result<(), String> {
+        let next = self.redo_stack.pop().ok_or("Nothing to redo")?;
+        self.undo_stack.push_back(self.snapshot());
+        self.restore(next);
+        Ok(())
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.current != self.saved
+    }
+
+    /// Save the layout to the active profile. It only counts as saved once the store has
+    /// confirmed the write.
+    pub async fn save(&mut self) -> Result<(), String> {
+        let save = self.start_save()?.wait().await?;
+        self.finish_save(save);
+        Ok(())
+    }
+
+    /// Hand the layout of the active profile to the store. The layout counts as saved once
+    /// [`PendingSave::wait`] has confirmed the writes and [`Keymap::finish_save`] applied them,
+    /// which lets the editor keep working while the store writes.
+    pub fn start_save(&mut self) -> Result<PendingSave, String> {
+        let storage_key = self.profile_storage_key();
+        self.save_to_storage(&storage_key, &self.current)
+            .and_then(|_| {
+                let id = self.profiles.active().id.clone();
+                self.profiles.set_modified(&id, Some(timestamp()));
+                self.save_profiles()
+            })
+            .map_err(save_error)?;
+        Ok(PendingSave { store: self.store.clone(), storage_key, layers: self.current.clone() })
+    }
+
+    /// Mark the layout of a confirmed save as saved, unless another profile or keyboard has
+    /// been opened since
+    pub fn finish_save(&mut self, save: PendingSave) {
+        if save.storage_key != self.profile_storage_key() {
+            return;
+        }
+        log(&format!("Layout saved to profile '{}'! {} layers", self.profiles.active().name, save.layers.len()));
+        self.saved = save.layers;
+    }
+
+    pub fn load(&mut self) -> Result<(), String> {
+        let before = self.snapshot();
+        match self.load_from_storage_result(&self.profile_storage_key()) {
+            Ok(layers) => {
+                self.current = layers.clone();
+                self.saved = layers;
+                self.clamp_active_layer();
+                self.record_edit(before);
+                log(&format!("Layout loaded! {} layers", self.current.len()));
+                Ok(())
+            }
+            Err(e) => {
+                let error_msg = format!("Failed to load layout: {}", e);
+                log(&error_msg);
+                Err(error_msg)
+            }
+        }
+    }
+
+    pub fn reset(&mut self) -> Result<(), String> {
+        let before = self.snapshot();
+        // Reset current to saved state (discard unsaved changes)
+        self.current = self.saved.clone();
+        self.clamp_active_layer();
+        log(&format!("Reset to saved state! {} layers", self.current.len()));
+        self.record_edit(before);
+        Ok(())
+    }
+
+    pub fn factory_reset(&mut self) -> Result<(), String> {
+        let before = self.snapshot();
+        // Clear the active profile's saved layout
+        self.store.remove(&self.profile_storage_key())?;
+        let id = self.profiles.active().id.clone();
+        self.profiles.set_modified(&id, None);
+        self.save_profiles()?;
+
+        // Reset to the model's default keymap
+        let default_layers = self.keyboard.default_layers.clone();
+        self.current = default_layers.clone();
+        self.saved = default_layers;
+        self.active_layer = 0;
+
+        log(&format!("Factory reset! {} layers", self.current.len()));
+        self.record_edit(before);
+        Ok(())
+    }
+
+    pub fn export_json(&self) -> Result<String, String> {
+        let layers: Vec<LayerExport> = self.current.iter()
+            .map(|layer| {
+                // Convert HashMap to a Vec of structured entries
+                let mut keymap_entries: Vec<KeymapEntry> = layer.keys.iter()
+                    .map(|(&position, &action)| KeymapEntry {
+                        position,
+                        label: action.to_string(),
+                        action,
+                    })
+                    .collect();
+
+                // Sort by position for consistent output
+                keymap_entries.sort_by(|a, b| {
+                    a.position.0.cmp(&b.position.0)
+                        .then(a.position.1.cmp(&b.position.1))
+                });
+
+                LayerExport {
+                    name: layer.name.clone(),
+                    keys: keymap_entries,
+                }
+            })
+            .collect();
+
+        // Create export structure with metadata
+        let export = KeymapExport {
+            metadata: ExportMetadata {
+                version: EXPORT_VERSION.to_string(),
+                keyboard: self.keyboard.name.clone(),
+                keyboard_id: self.keyboard.id.clone(),
+                exported_at: timestamp(),
+                total_layers: layers.len(),
+                total_keys: layers.iter().map(|layer| layer.keys.len()).sum(),
+            },
+            layers,
+        };
+
+        // Serialize to pretty JSON
+        serde_json::to_string_pretty(&export)
+            .map_err(|e| format!("JSON serialization failed: {}", e))
+    }
+
+    /// Convert the current layers to a firmware or configurator format
+    pub fn export_as(&self, format: ExportFormat) -> Conversion<String> {
+        format.export(&self.keyboard, &self.current)
+    }
+
+    /// Load a file written by [`Keymap::export_json`] as unsaved changes.
+    ///
+    /// If the file names another keyboard model, the editor switches to it once the layers
+    /// have been read. Entries that cannot be loaded are skipped; the returned list
+    /// describes each of them.
+    pub fn import_json(&mut self, json: &str) -> Result<Vec<String>, String> {
+        let file: KeymapImport = serde_json::from_str(json)
+            .map_err(|e| format!("Not a keymap export: {}", e))?;
+        Self::check_export_version(&file.metadata.version)?;
+
+        let switch_to = file.metadata.keyboard_id.clone().filter(|id| *id != self.keyboard.id);
+        let keyboard = match switch_to.as_deref() {
+            Some(id) => Rc::new(Self::find_keyboard(self.store.as_ref(), id)
+                .ok_or_else(|| format!("Unknown keyboard model '{}'", id))?),
+            None => self.keyboard.clone(),
+        };
+        let (layers, problems) = Self::layers_from_import(file, &keyboard)?;
+        if let Some(id) = switch_to {
+            self.switch_keyboard(&id)?;
+        }
+
+        let before = self.snapshot();
+        self.current = layers;
+        self.clamp_active_layer();
+        self.record_edit(before);
+
+        log(&format!(
+            "Imported {} layers, skipped {} entries", self.current.len(), problems.len()
+        ));
+        Ok(problems)
+    }
+
+    /// Load a file in any supported format as unsaved changes: a firmware file such as a
+    /// QMK `keymap.json`, or a file written by [`Keymap::export_json`]. Returns a
+    /// description of every key that was skipped or approximated.
+    ///
+    /// A KLE layout adds a new keyboard model instead, and switches the editor to it.
+    pub fn import_file(&mut self, file_name: &str, contents: &str) -> Result<Vec<String>, String> {
+        if kle::is_kle(contents) {
+            let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+            let conversion = kle::import_kle(stem, contents)?;
+            self.add_keyboard(conversion.output)?;
+            return Ok(conversion.warnings);
+        }
+
+        let Some(format) = ImportFormat::detect(file_name, contents) else {
+            return self.import_json(contents);
+        };
+
+        let conversion = format.import(&self.keyboard, contents)?;
+        let before = self.snapshot();
+        self.current = conversion.output;
+        self.clamp_active_layer();
+        self.record_edit(before);
+
+        log(&format!("Imported {} layers from {:?}", self.current.len(), format));
+        Ok(conversion.warnings)
+    }
+
+    /// A built-in keyboard model or one imported into `store`
+    fn find_keyboard(store: &dyn KeymapStore, id: &str) -> Option<KeyboardModel> {
+        keyboards::find_keyboard(id).cloned()
+            .or_else(|| Self::load_custom_keyboards(store).into_iter().find(|model| model.id == id))
+    }
+
+    fn load_custom_keyboards(store: &dyn KeymapStore) -> Vec<KeyboardModel> {
+        let saved = match store.get(CUSTOM_KEYBOARDS_KEY) {
+            Ok(Some(saved)) => saved,
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                log(&format!("Failed to read imported keyboards: {}", e));
+                return Vec::new();
+            }
+        };
+        storage::parse_keyboards(&saved).unwrap_or_else(|e| {
+            log(&format!("Ignoring unreadable imported keyboards: {}", e));
+            Vec::new()
+        })
+    }
+
+    fn check_export_version(version: &str) -> Result<(), String> {
+        let major = |version: &str| version.split('.').next().and_then(|major| major.parse::<u32>().ok());
+        match (major(version), major(EXPORT_VERSION)) {
+            (Some(found), Some(supported)) if (1..=supported).contains(&found) => Ok(()),
+            _ => Err(format!("Unsupported export version '{}' (expected {} or older)", version, EXPORT_VERSION)),
+        }
+    }
+
+    /// Convert an imported file into layers for `keyboard`, collecting a description of
+    /// every entry that had to be skipped
+    fn layers_from_import(file: KeymapImport, keyboard: &KeyboardModel) -> Result<(Vec<Layer>, Vec<String>), String> {
+        let mut problems = Vec::new();
+
+        // Version 1 exports have a single layer of keys
+        let imported = if file.layers.is_empty() && !file.keys.is_empty() {
+            vec![LayerImport { name: keyboard.default_layers[0].name.clone(), keys: file.keys }]
+        } else {
+            file.layers
+        };
+        if imported.is_empty() {
+            return Err("File contains no layers".to_string());
+        }
+
+        let mut layers: Vec<Layer> = Vec::new();
+        for (index, imported_layer) in imported.into_iter().enumerate() {
+            let mut name = imported_layer.name.trim().to_string();
+            if name.is_empty() || layers.iter().any(|layer| layer.name == name) {
+                let renamed = format!("Layer {}", index);
+                problems.push(format!("Layer {} '{}': renamed to '{}'", index, name, renamed));
+                name = renamed;
+            }
+
+            let mut layer = Layer::new(name);
+            for entry in imported_layer.keys {
+                let (row, col) = entry.position;
+                let location = format!("Layer '{}' [{}, {}]", layer.name, row, col);
+                if !keyboard.geometry.contains(entry.position) {
+                    problems.push(format!("{}: no such key on {}", location, keyboard.name));
+                    continue;
+                }
+                match Self::action_from_import(&entry) {
+                    Ok(action) => {
+                        layer.keys.insert(entry.position, action);
+                    }
+                    Err(e) => problems.push(format!("{}: {}", location, e)),
+                }
+            }
+            layers.push(layer);
+        }
+
+        Ok((layers, problems))
+    }
+
+    /// Read an entry's action. Files written before actions were exported store a HID
+    /// keycode instead, which is read before the label: keys such as F13 were exported with
+    /// the label "Unknown".
+    fn action_from_import(entry: &EntryImport) -> Result<KeyAction, String> {
+        if let Some(action) = entry.action.clone().and_then(|value| serde_json::from_value(value).ok()) {
+            return Ok(action);
+        }
+        match entry.keycode {
+            // Raise, Lower and the empty key
+            Some(0xE9) => return Ok(KeyAction::MomentaryLayer(2)),
+            Some(0xEA) => return Ok(KeyAction::MomentaryLayer(1)),
+            Some(0xFF) => return Ok(KeyAction::NoOp),
+            Some(code) if KeyboardUsage::from(code) != KeyboardUsage::Reserved => {
+                return Ok(KeyAction::Key(KeyboardUsage::from(code)));
+            }
+            _ => {}
+        }
+
+        let label = entry.label.as_deref().ok_or("entry has neither an action nor a label")?;
+        if let Some(&(_, usage)) = LEGACY_LABELS.iter().find(|&&(legacy, _)| legacy == label.trim()) {
+            return Ok(KeyAction::Key(usage));
+        }
+        label.parse::<KeyAction>()
+            .map_err(|_| format!("unknown label '{}'", label))
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { layers: self.current.clone(), active_layer: self.active_layer }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.current = snapshot.layers;
+        self.active_layer = snapshot.active_layer;
+        self.clamp_active_layer();
+    }
+
+    /// Add the state before an edit to the undo history, unless the edit changed nothing
+    fn record_edit(&mut self, before: Snapshot) {
+        if before.layers == self.current {
+            return;
+        }
+        self.undo_stack.push_back(before);
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.redo_stack.clear();
+    }
+
+    fn check_layer_index(&self, index: usize) -> Result<(), String> {
+        if index < self.current.len() {
+            Ok(())
+        } else {
+            Err(format!("Layer {} does not exist", index))
+        }
+    }
+
+    /// Trim a layer name and make sure it is non-empty and not used by another layer
+    fn validate_layer_name(&self, name: &str, index: Option<usize>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Layer name cannot be empty".to_string());
+        }
+
+        let taken = self.current.iter()
+            .enumerate()
+            .any(|(i, layer)| Some(i) != index && layer.name == name);
+        if taken {
+            return Err(format!("A layer named '{}' already exists", name));
+        }
+
+        Ok(name.to_string())
+    }
+
+    /// Rewrite layer actions after layers were moved or removed. `remap` returns the
+    /// new index of a layer, or `None` if the layer no longer exists.
+    fn remap_layer_references(&mut self, remap: impl Fn(usize) -> Option<usize>) {
+        for layer in &mut self.current {
+            for action in layer.keys.values_mut() {
+                if let Some(target) = action.layer() {
+                    *action = action.retarget_layer(remap(target));
+                }
+            }
+        }
+    }
+
+    fn clamp_active_layer(&mut self) {
+        self.active_layer = self.active_layer.min(self.current.len() - 1);
+    }
+
+    fn profile_storage_key(&self) -> String {
+        storage::profile_storage_key(&self.keyboard.storage_key, &self.profiles.active().id)
+    }
+
+    /// Replace the layers and history with the active profile's saved layout
+    fn open_active_profile(&mut self) {
+        let saved = self.load_from_storage(&self.profile_storage_key());
+        for layer in &saved {
+            if let Err(unknown) = self.keyboard.geometry.validate_keymap(&layer.keys) {
+                log(&format!(
+                    "Layer '{}' has keys outside {}: {:?}", layer.name, self.keyboard.name, unknown
+                ));
+            }
+        }
+
+        self.current = saved.clone();
+        self.saved = saved;
+        self.active_layer = 0;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    fn load_profiles(&self) -> ProfileIndex {
+        let saved = self.store.get(&storage::profile_index_key(&self.keyboard.storage_key));
+        match saved {
+            Ok(Some(json)) => ProfileIndex::parse(&json).unwrap_or_else(|e| {
+                log(&format!("Ignoring profile list: {}", e));
+                ProfileIndex::default()
+            }),
+            Ok(None) => ProfileIndex::default(),
+            Err(e) => {
+                log(&format!("Failed to read profile list: {}", e));
+                ProfileIndex::default()
+            }
+        }
+    }
+
+    fn save_profiles(&self) -> Result<(), String> {
+        self.store.set(&storage::profile_index_key(&self.keyboard.storage_key), &self.profiles.to_json()?)
+    }
+
+    fn save_to_storage(&self, storage_key: &str, layers: &[Layer]) -> Result<(), String> {
+        let keymap_json = storage::serialize_layers(layers)?;
+        self.store.set(storage_key, &keymap_json)
+    }
+
+    fn load_from_storage(&self, storage_key: &str) -> Vec<Layer> {
+        self.load_from_storage_result(storage_key)
+            .unwrap_or_else(|_| self.keyboard.default_layers.clone())
+    }
+
+    /// Read a saved layout. Data that can't be read is copied to a backup key first, so
+    /// saving over it later does not lose it.
+    fn load_from_storage_result(&self, storage_key: &str) -> Result<Vec<Layer>, String> {
+        let saved_keymap = self.store.get(storage_key)?
+            .ok_or("No saved layout found")?;
+
+        log(&format!("Found saved data, length: {}", saved_keymap.len()));
+
+        storage::parse_layers(&saved_keymap).map_err(|e| {
+            let backup_key = format!("{}_backup", storage_key);
+            let backed_up = self.store.get(&backup_key).ok().flatten().as_deref() == Some(saved_keymap.as_str())
+                || self.store.set(&backup_key, &saved_keymap).is_ok();
+            if backed_up {
+                log(&format!("Unreadable saved layout backed up to '{}'", backup_key));
+                format!("{} (original data kept in '{}')", e, backup_key)
+            } else {
+                format!("{} (backing up the original data failed)", e)
+            }
+        })
+    }
+
+    /// Move this editor's entries (layouts, profile lists, keyboard selection) from one
+    /// store to another, keeping entries the target already has. Returns how many moved.
+    /// Nothing is removed from the source until the target has stored every entry for good.
+    pub async fn move_entries(from: &dyn KeymapStore, to: &dyn KeymapStore) -> Result<usize, String> {
+        let owned = |key: &str| {
+            key == SELECTED_KEYBOARD_KEY
+                || key == CUSTOM_KEYBOARDS_KEY
+                || key.starts_with(kle::ID_PREFIX)
+                || keyboards::keyboard_models().iter().any(|model| key.starts_with(&model.storage_key))
+        };
+
+        let keys: Vec<String> = from.keys()?.into_iter().filter(|key| owned(key)).collect();
+        let mut moved = 0;
+        for key in &keys {
+            if to.get(key)?.is_none() {
+                if let Some(value) = from.get(key)? {
+                    to.set(key, &value)?;
+                    moved += 1;
+                }
+            }
+        }
+        to.flush().await?;
+
+        for key in &keys {
+            from.remove(key)?;
+        }
+        Ok(moved)
+    }
+}
+
+/// Layout writes handed to the store by [`Keymap::start_save`]
+pub struct PendingSave {
+    store: Rc<dyn KeymapStore>,
+    /// Storage key of the profile the layout was written for
+    storage_key: String,
+    layers: Vec<Layer>,
+}
+
+impl PendingSave {
+    /// Wait until the store has written the layout for good
+    pub async fn wait(self) -> Result<Self, String> {
+        match self.store.flush().await {
+            Ok(()) => Ok(self),
+            Err(e) => Err(save_error(e)),
+        }
+    }
+}
+
+fn save_error(e: String) -> String {
+    let error_msg = format!("Failed to save layout: {}", e);
+    log(&error_msg);
+    error_msg
+}
+
+/// Current time as an ISO 8601 string
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+fn timestamp() -> String {
+    js_sys::Date::new_0().to_iso_string().as_string().unwrap_or_default()
+}
+
+/// Current time as an ISO 8601 string
+#[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+fn timestamp() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let time = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z",
+        year, month, day, time / 3_600, time / 60 % 60, time % 60
+    )
+}
+
+/// Log to the browser console. Native and headless builds have no console to log to.
+fn log(message: &str) {
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    web_sys::console::log_1(&message.into());
+    #[cfg(not(all(target_arch = "wasm32", feature = "web")))]
+    let _ = message;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{block_on, Flush, MemoryStore};
+
+    fn test_keymap() -> Keymap {
+        Keymap::with_layers(Rc::new(MemoryStore::default()), Rc::new(keyboards::default_keyboard().clone()), Vec::new())
+    }
+
+    /// Store whose writes fail, either straight away or only when they are flushed
+    #[derive(Default)]
+    struct FailingStore {
+        items: MemoryStore,
+        fails_at_flush: bool,
+    }
+
+    impl KeymapStore for FailingStore {
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            self.items.get(key)
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            match self.fails_at_flush {
+                true => self.items.set(key, value),
+                false => Err("Quota exceeded".to_string()),
+            }
+        }
+
+        fn remove(&self, key: &str) -> Result<(), String> {
+            self.items.remove(key)
+        }
+
+        fn keys(&self) -> Result<Vec<String>, String> {
+            self.items.keys()
+        }
+
+        fn flush(&self) -> Flush<'_> {
+            let fails = self.fails_at_flush;
+            Box::pin(async move { if fails { Err("Transaction aborted".to_string()) } else { Ok(()) } })
+        }
+    }
+
+    fn layer_names(keymap: &Keymap) -> Vec<&str> {
+        keymap.layers().iter().map(|layer| layer.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_default_layers() {
+        let keymap = test_keymap();
+        assert_eq!(layer_names(&keymap), ["Base", "Lower", "Raise"]);
+        assert_eq!(keymap.active_layer(), 0);
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+        assert_eq!(keymap.current().get(&(4, 3)), Some(&KeyAction::MomentaryLayer(1)));
+        assert_eq!(keymap.current().get(&(4, 10)), Some(&KeyAction::MomentaryLayer(2)));
+        assert!(!keymap.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_keymaps_use_their_model_defaults() {
+        let corne = keyboards::find_keyboard("corne").unwrap();
+        let mut keymap = Keymap::with_layers(Rc::new(MemoryStore::default()), Rc::new(corne.clone()), Vec::new());
+        assert_eq!(keymap.layers(), corne.default_layers.as_slice());
+        assert_eq!(keymap.current().get(&(3, 4)), Some(&KeyAction::MomentaryLayer(1)));
+
+        // (4, 3) exists on the Dactyl but not on the Corne
+        assert!(keymap.update_key(4, 3, "A".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_update_key_only_touches_active_layer() {
+        let mut keymap = test_keymap();
+        keymap.set_active_layer(1).unwrap();
+        keymap.update_key(0, 0, "F1".to_string()).unwrap();
+
+        assert_eq!(keymap.layers()[1].keys.get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF1)));
+        assert_eq!(keymap.layers()[0].keys.get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+        assert!(keymap.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_update_key_rejects_unknown_labels() {
+        let mut keymap = test_keymap();
+        assert!(keymap.update_key(0, 0, "Invalid".to_string()).is_err());
+        assert!(keymap.update_key(3, 6, "A".to_string()).is_err());
+        assert!(!keymap.has_unsaved_changes());
+
+        keymap.update_key(0, 0, "LT(1, Esc)".to_string()).unwrap();
+        assert_eq!(
+            keymap.current().get(&(0, 0)),
+            Some(&KeyAction::LayerTap { layer: 1, tap: KeyboardUsage::KeyboardEscape })
+        );
+    }
+
+    #[test]
+    fn test_add_and_rename_layer() {
+        let mut keymap = test_keymap();
+        keymap.add_layer("Adjust".to_string()).unwrap();
+        assert_eq!(keymap.active_layer(), 3);
+
+        keymap.rename_layer(3, "  Media ".to_string()).unwrap();
+        assert_eq!(layer_names(&keymap), ["Base", "Lower", "Raise", "Media"]);
+
+        assert!(keymap.add_layer("Lower".to_string()).is_err());
+        assert!(keymap.rename_layer(3, "".to_string()).is_err());
+        assert!(keymap.rename_layer(3, "Media".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_move_layer_keeps_active_layer() {
+        let mut keymap = test_keymap();
+        keymap.set_active_layer(1).unwrap();
+        keymap.move_layer(2, 0).unwrap();
+
+        assert_eq!(layer_names(&keymap), ["Raise", "Base", "Lower"]);
+        assert_eq!(keymap.layers()[keymap.active_layer()].name, "Lower");
+        assert!(keymap.move_layer(0, 3).is_err());
+
+        // Layer keys follow the layers they point at
+        assert_eq!(keymap.layers()[1].keys.get(&(4, 3)), Some(&KeyAction::MomentaryLayer(2)));
+        assert_eq!(keymap.layers()[1].keys.get(&(4, 10)), Some(&KeyAction::MomentaryLayer(0)));
+    }
+
+    #[test]
+    fn test_duplicate_layer() {
+        let mut keymap = test_keymap();
+        keymap.duplicate_layer(0).unwrap();
+        keymap.duplicate_layer(0).unwrap();
+
+        assert_eq!(layer_names(&keymap), ["Base", "Base copy 2", "Base copy", "Lower", "Raise"]);
+        assert_eq!(keymap.active_layer(), 1);
+        assert_eq!(keymap.layers()[1].keys, keymap.layers()[0].keys);
+        assert_eq!(keymap.layers()[0].keys.get(&(4, 10)), Some(&KeyAction::MomentaryLayer(4)));
+    }
+
+    #[test]
+    fn test_delete_layer() {
+        let mut keymap = test_keymap();
+        keymap.set_active_layer(2).unwrap();
+        keymap.delete_layer(2).unwrap();
+        assert_eq!(keymap.active_layer(), 1);
+        assert_eq!(keymap.layers()[0].keys.get(&(4, 10)), Some(&KeyAction::NoOp));
+
+        keymap.delete_layer(0).unwrap();
+        assert_eq!(layer_names(&keymap), ["Lower"]);
+        assert_eq!(keymap.active_layer(), 0);
+        assert!(keymap.delete_layer(0).is_err());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut keymap = test_keymap();
+        assert!(!keymap.can_undo());
+        assert!(keymap.undo().is_err());
+
+        keymap.update_key(0, 0, "F1".to_string()).unwrap();
+        keymap.add_layer("Adjust".to_string()).unwrap();
+        keymap.set_active_layer(0).unwrap();
+
+        keymap.undo().unwrap();
+        assert_eq!(layer_names(&keymap), ["Base", "Lower", "Raise"]);
+        assert_eq!(keymap.active_layer(), 0);
+        assert!(keymap.can_redo());
+
+        keymap.undo().unwrap();
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+        assert!(!keymap.can_undo());
+        assert!(!keymap.has_unsaved_changes());
+
+        keymap.redo().unwrap();
+        keymap.redo().unwrap();
+        assert_eq!(layer_names(&keymap), ["Base", "Lower", "Raise", "Adjust"]);
+        assert!(keymap.redo().is_err());
+    }
+
+    #[test]
+    fn test_undo_history() {
+        let mut keymap = test_keymap();
+
+        // Failed and no-op edits are not recorded
+        assert!(keymap.update_key(0, 0, "Invalid".to_string()).is_err());
+        keymap.update_key(0, 0, "Esc".to_string()).unwrap();
+        assert!(!keymap.can_undo());
+
+        // Bulk edits are a single step
+        keymap.update_key(0, 0, "F1".to_string()).unwrap();
+        keymap.delete_layer(1).unwrap();
+        keymap.undo().unwrap();
+        assert_eq!(layer_names(&keymap), ["Base", "Lower", "Raise"]);
+        assert_eq!(keymap.layers()[0].keys.get(&(4, 10)), Some(&KeyAction::MomentaryLayer(2)));
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF1)));
+
+        // A new edit clears the redo stack
+        keymap.undo().unwrap();
+        keymap.update_key(0, 1, "F2".to_string()).unwrap();
+        assert!(!keymap.can_redo());
+
+        // The history is bounded
+        for i in 0..HISTORY_LIMIT + 10 {
+            keymap.update_key(0, 0, format!("F{}", i % 12 + 1)).unwrap();
+        }
+        let mut undone = 0;
+        while keymap.undo().is_ok() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+    }
+
+    #[test]
+    fn test_save_and_load_through_store() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        keymap.update_key(0, 0, "F1".to_string()).unwrap();
+        block_on(keymap.save()).unwrap();
+        assert!(!keymap.has_unsaved_changes());
+        assert!(store.get("dactyl_keymap").unwrap().unwrap().starts_with(r#"{"version":3"#));
+
+        // A fresh editor on the same store sees the saved layout
+        let mut reopened = Keymap::with_store(store.clone());
+        assert_eq!(reopened.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF1)));
+        assert!(reopened.profiles().active().modified_at.is_some());
+
+        reopened.update_key(0, 0, "F2".to_string()).unwrap();
+        reopened.load().unwrap();
+        assert_eq!(reopened.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF1)));
+
+        reopened.factory_reset().unwrap();
+        assert_eq!(store.get("dactyl_keymap"), Ok(None));
+        assert_eq!(reopened.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+
+        // Factory reset can be undone as an unsaved change
+        reopened.undo().unwrap();
+        assert!(reopened.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_unreadable_layout_is_backed_up() {
+        let store = Rc::new(MemoryStore::default());
+        store.set("dactyl_keymap", "{broken").unwrap();
+
+        let mut keymap = Keymap::with_store(store.clone());
+        assert_eq!(keymap.layers(), keyboards::default_keyboard().default_layers.as_slice());
+        assert_eq!(store.get("dactyl_keymap_backup"), Ok(Some("{broken".to_string())));
+
+        // Saving over the broken data keeps the backup
+        block_on(keymap.save()).unwrap();
+        assert_eq!(store.get("dactyl_keymap_backup"), Ok(Some("{broken".to_string())));
+    }
+
+    #[test]
+    fn test_profiles_are_saved_separately() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        keymap.update_key(0, 0, "F1".to_string()).unwrap();
+        block_on(keymap.save()).unwrap();
+
+        keymap.create_profile("Gaming".to_string()).unwrap();
+        assert_eq!(keymap.profiles().active().name, "Gaming");
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+
+        keymap.update_key(0, 0, "F5".to_string()).unwrap();
+        block_on(keymap.duplicate_profile()).unwrap();
+        assert_eq!(keymap.profiles().active().name, "Gaming copy");
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF5)));
+        assert!(!keymap.has_unsaved_changes());
+
+        keymap.rename_profile("Experimental".to_string()).unwrap();
+        keymap.switch_profile("default").unwrap();
+        assert_eq!(keymap.current().get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardF1)));
+
+        // The profile list and active profile survive reopening
+        let mut reopened = Keymap::with_store(store.clone());
+        let names: Vec<&str> = reopened.profiles().profiles().iter().map(|profile| profile.name.as_str()).collect();
+        assert_eq!(names, ["Default", "Gaming", "Experimental"]);
+        assert_eq!(reopened.profiles().active().name, "Default");
+
+        reopened.switch_profile("profile2").unwrap();
+        reopened.delete_profile().unwrap();
+        assert_eq!(store.get("dactyl_keymap_profile2"), Ok(None));
+        assert_eq!(reopened.profiles().active().name, "Default");
+    }
+
+    #[test]
+    fn test_switch_keyboard_remembers_selection() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        keymap.switch_keyboard("corne").unwrap();
+        keymap.update_key(3, 4, "Space".to_string()).unwrap();
+        block_on(keymap.save()).unwrap();
+        assert!(keymap.switch_keyboard("unknown").is_err());
+
+        let reopened = Keymap::with_store(store.clone());
+        assert_eq!(reopened.keyboard().id, "corne");
+        assert_eq!(reopened.current().get(&(3, 4)), Some(&KeyAction::Key(KeyboardUsage::KeyboardSpacebar)));
+        assert!(store.get("dactyl_keymap").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_move_entries() {
+        let from = MemoryStore::default();
+        let to = MemoryStore::default();
+        from.set("dactyl_keymap", "old").unwrap();
+        from.set("corne_keymap_profiles", "{}").unwrap();
+        from.set("selected_keyboard", "corne").unwrap();
+        from.set("another_app", "x").unwrap();
+        to.set("dactyl_keymap", "new").unwrap();
+
+        assert_eq!(block_on(Keymap::move_entries(&from, &to)), Ok(2));
+        assert_eq!(to.get("dactyl_keymap"), Ok(Some("new".to_string())));
+        assert_eq!(to.get("selected_keyboard"), Ok(Some("corne".to_string())));
+        assert_eq!(from.keys(), Ok(vec!["another_app".to_string()]));
+    }
+
+    #[test]
+    fn test_move_entries_keeps_source_when_writes_fail() {
+        for fails_at_flush in [false, true] {
+            let from = MemoryStore::default();
+            from.set("dactyl_keymap", "old").unwrap();
+            from.set("selected_keyboard", "corne").unwrap();
+            let to = FailingStore { fails_at_flush, ..Default::default() };
+
+            assert!(block_on(Keymap::move_entries(&from, &to)).is_err());
+            assert_eq!(from.get("dactyl_keymap"), Ok(Some("old".to_string())));
+            assert_eq!(from.get("selected_keyboard"), Ok(Some("corne".to_string())));
+        }
+    }
+
+    #[test]
+    fn test_save_fails_until_written() {
+        for fails_at_flush in [false, true] {
+            let mut keymap = Keymap::with_store(Rc::new(FailingStore { fails_at_flush, ..Default::default() }));
+            keymap.update_key(0, 0, "Tab".to_string()).unwrap();
+
+            assert!(block_on(keymap.save()).is_err());
+            assert!(keymap.has_unsaved_changes());
+        }
+    }
+
+    #[test]
+    fn test_edits_during_save_stay_unsaved() {
+        let mut keymap = test_keymap();
+        keymap.update_key(0, 0, "Tab".to_string()).unwrap();
+        let save = keymap.start_save().unwrap();
+
+        keymap.update_key(0, 1, "Esc".to_string()).unwrap();
+        keymap.finish_save(block_on(save.wait()).unwrap());
+        assert_eq!(keymap.current().get(&(0, 1)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEscape)));
+        assert!(keymap.has_unsaved_changes());
+        keymap.undo().unwrap();
+        assert!(!keymap.has_unsaved_changes());
+
+        // A save finishing after another profile was opened leaves that profile alone
+        let save = keymap.start_save().unwrap();
+        keymap.create_profile("Gaming".to_string()).unwrap();
+        keymap.update_key(0, 0, "Q".to_string()).unwrap();
+        keymap.finish_save(block_on(save.wait()).unwrap());
+        assert!(keymap.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_import_qmk_keymap_json() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        let json = r#"{"keyboard": "handwired/dactyl_manuform/5x7", "layout": "LAYOUT_5x7", "layers": [["QK_BOOT", "KC_Q"], ["KC_TRNS"]]}"#;
+
+        let warnings = keymap.import_file("keymap.json", json).unwrap();
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(layer_names(&keymap), ["Base", "Lower"]);
+        assert_eq!(keymap.current().get(&(0, 1)), Some(&KeyAction::Key(KeyboardUsage::KeyboardQq)));
+        assert_eq!(keymap.layers()[0].unmapped[&(0, 0)].keycode, "QK_BOOT");
+
+        // Saved with the layout, and dropped once the key is edited
+        block_on(keymap.save()).unwrap();
+        assert_eq!(Keymap::with_store(store.clone()).layers(), keymap.layers());
+        keymap.update_key(0, 0, "Esc".to_string()).unwrap();
+        assert!(keymap.layers()[0].unmapped.is_empty());
+
+        keymap.undo().unwrap();
+        assert_eq!(keymap.layers()[0].unmapped.len(), 1);
+    }
+
+    #[test]
+    fn test_import_kle_layout() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        let kle = r#"[{"name": "Macro Pad"}, ["Esc", "Q", "Knob"], [{"w": 2}, "Space"]]"#;
+
+        let warnings = keymap.import_file("macro-pad.json", kle).unwrap();
+        assert_eq!(warnings, ["Key [0, 2]: no key matches the legend 'Knob'"]);
+        assert_eq!(keymap.keyboard().id, "kle_macro_pad");
+        assert_eq!(keymap.geometry().keys().len(), 4);
+        assert_eq!(keymap.current().get(&(1, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardSpacebar)));
+        assert_eq!(keymap.custom_keyboards().len(), 1);
+
+        // Importing it again replaces the saved model
+        keymap.update_key(0, 2, "Enter".to_string()).unwrap();
+        block_on(keymap.save()).unwrap();
+        keymap.import_file("macro-pad.json", kle).unwrap();
+        assert_eq!(keymap.custom_keyboards().len(), 1);
+
+        let reopened = Keymap::with_store(store.clone());
+        assert_eq!(reopened.keyboard().name, "Macro Pad");
+        assert_eq!(reopened.current().get(&(0, 2)), Some(&KeyAction::Key(KeyboardUsage::KeyboardEnter)));
+        assert!(block_on(Keymap::move_entries(store.as_ref(), &MemoryStore::default())).unwrap() >= 3);
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let now = timestamp();
+        assert_eq!(now.len(), "2024-01-01T00:00:00.000Z".len());
+        assert!(now.ends_with(".000Z") && now.as_bytes()[10] == b'T', "{}", now);
+    }
+
+    fn import(json: &str) -> Result<(Vec<Layer>, Vec<String>), String> {
+        let file: KeymapImport = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Keymap::check_export_version(&file.metadata.version)?;
+        Keymap::layers_from_import(file, keyboards::default_keyboard())
+    }
+
+    #[test]
+    fn test_import_current_export() {
+        let json = r#"{
+            "metadata": {"version": "3.0", "keyboard": "Dactyl Manuform 5x7", "keyboard_id": "dactyl_manuform_5x7",
+                         "exported_at": "", "total_layers": 2, "total_keys": 3},
+            "layers": [
+                {"name": "Base", "keys": [
+                    {"position": [0, 0], "label": "Esc", "action": {"Key": "KeyboardEscape"}},
+                    {"position": [4, 3], "label": "MO(1)", "action": {"MomentaryLayer": 1}}
+                ]},
+                {"name": "Nav", "keys": [{"position": [0, 0], "label": "Trans", "action": "Transparent"}]}
+            ]
+        }"#;
+        let (layers, problems) = import(json).unwrap();
+
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].keys.get(&(4, 3)), Some(&KeyAction::MomentaryLayer(1)));
+        assert_eq!(layers[1].name, "Nav");
+        assert_eq!(layers[1].keys.get(&(0, 0)), Some(&KeyAction::Transparent));
+    }
+
+    #[test]
+    fn test_failed_import_keeps_keyboard() {
+        let store = Rc::new(MemoryStore::default());
+        let mut keymap = Keymap::with_store(store.clone());
+        let json = r#"{
+            "metadata": {"version": "3.0", "keyboard": "Corne (crkbd)", "keyboard_id": "corne",
+                         "exported_at": "", "total_layers": 0, "total_keys": 0},
+            "layers": []
+        }"#;
+
+        assert_eq!(keymap.import_json(json).unwrap_err(), "File contains no layers");
+        assert_eq!(keymap.keyboard().id, "dactyl_manuform_5x7");
+        assert_eq!(store.get(SELECTED_KEYBOARD_KEY), Ok(None));
+    }
+
+    #[test]
+    fn test_import_reports_bad_entries() {
+        let json = r#"{
+            "metadata": {"version": "2.0", "keyboard": "Dactyl Manuform 5x7", "exported_at": "", "total_layers": 1, "total_keys": 3},
+            "layers": [{"name": "Base", "keys": [
+                {"position": [0, 0], "label": "Lower", "keycode": 234},
+                {"position": [0, 1], "label": "Bogus", "keycode": 0},
+                {"position": [3, 6], "label": "A", "keycode": 4}
+            ]}]
+        }"#;
+        let (layers, problems) = import(json).unwrap();
+
+        assert_eq!(layers[0].keys.len(), 1);
+        assert_eq!(layers[0].keys.get(&(0, 0)), Some(&KeyAction::MomentaryLayer(1)));
+        assert_eq!(problems, [
+            "Layer 'Base' [0, 1]: unknown label 'Bogus'",
+            "Layer 'Base' [3, 6]: no such key on Dactyl Manuform 5x7",
+        ]);
+    }
+
+    #[test]
+    fn test_import_reads_keycodes_before_labels() {
+        // Keys without a label of their own were exported as "Unknown", with their HID code
+        let json = r#"{
+            "metadata": {"version": "1.0", "keyboard": "Dactyl Manuform 5x7", "exported_at": "", "total_keys": 4},
+            "keys": [
+                {"position": [0, 0], "label": "Unknown", "keycode": 104},
+                {"position": [0, 1], "label": "Unknown", "keycode": 144},
+                {"position": [0, 2], "label": "", "keycode": 255},
+                {"position": [0, 3], "label": "Raise", "keycode": 233}
+            ]
+        }"#;
+        let (layers, problems) = import(json).unwrap();
+
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(layers[0].keys[&(0, 0)], KeyAction::Key(KeyboardUsage::KeyboardF13));
+        assert_eq!(layers[0].keys[&(0, 1)], KeyAction::Key(KeyboardUsage::KeyboardLANG1));
+        assert_eq!(layers[0].keys[&(0, 2)], KeyAction::NoOp);
+        assert_eq!(layers[0].keys[&(0, 3)], KeyAction::MomentaryLayer(2));
+    }
+
+    #[test]
+    fn test_import_legacy_media_labels() {
+        // Before consumer controls, these labels were the keyboard page media keys
+        let json = r#"{
+            "metadata": {"version": "2.0", "keyboard": "Dactyl Manuform 5x7", "exported_at": "", "total_layers": 1, "total_keys": 4},
+            "layers": [{"name": "Base", "keys": [
+                {"position": [0, 0], "label": "Vol+", "keycode": 128},
+                {"position": [0, 1], "label": "Vol-", "keycode": 129},
+                {"position": [0, 2], "label": "Mute"},
+                {"position": [0, 3], "label": "Stop"}
+            ]}]
+        }"#;
+        let (layers, problems) = import(json).unwrap();
+
+        assert!(problems.is_empty(), "{:?}", problems);
+        use KeyboardUsage::*;
+        assert_eq!(layers[0].keys[&(0, 0)], KeyAction::Key(KeyboardVolumeUp));
+        assert_eq!(layers[0].keys[&(0, 1)], KeyAction::Key(KeyboardVolumeDown));
+        assert_eq!(layers[0].keys[&(0, 2)], KeyAction::Key(KeyboardMute));
+        assert_eq!(layers[0].keys[&(0, 3)], KeyAction::Key(KeyboardStop));
+    }
+
+    #[test]
+    fn test_import_single_layer_export() {
+        let json = r#"{
+            "metadata": {"version": "1.0", "keyboard": "Dactyl Manuform 5x7", "exported_at": "", "total_keys": 1},
+            "keys": [{"position": [0, 0], "label": "Tab", "keycode": 43}]
+        }"#;
+        let (layers, _) = import(json).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, "Base");
+        assert_eq!(layers[0].keys.get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardTab)));
+    }
+
+    #[test]
+    fn test_import_rejects_unsupported_versions() {
+        assert!(Keymap::check_export_version("3.1").is_ok());
+        assert!(Keymap::check_export_version("1.0").is_ok());
+        assert!(Keymap::check_export_version("4.0").is_err());
+        assert!(Keymap::check_export_version("latest").is_err());
+        assert!(import(r#"{"metadata": {"version": "3.0"}, "layers": []}"#).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! # layout-rs
+//!
+//! Keyboard layout model behind the layout-rs editor: HID keycodes, key actions, keyboard
+//! geometries, keymaps with layers and profiles, and their storage and import/export formats.
+//!
+//! The library builds for native targets. The Yew editor and the browser storage backends
+//! are behind the default `web` feature; depend on the crate with `default-features = false`
+//! to use the model without `web-sys`.
+//!
+//! ```rust
+//! use std::rc::Rc;
+//! use layout_rs::keymap::Keymap;
+//! use layout_rs::store::MemoryStore;
+//!
+//! let mut keymap = Keymap::with_store(Rc::new(MemoryStore::default()));
+//! keymap.update_key(0, 0, "Ctrl+C".to_string()).unwrap();
+//! let json = keymap.export_json().unwrap();
+//! assert!(json.contains("Ctrl+C"));
+//! ```
+
+pub mod actions;
+pub mod formats;
+pub mod geometry;
+pub mod keyboards;
+pub mod keycodes;
+pub mod keymap;
+pub mod storage;
+pub mod store;
+
+#[cfg(feature = "web")]
+pub mod app;
+#[cfg(feature = "web")]
+pub mod components;
+#[cfg(feature = "web")]
+mod download;
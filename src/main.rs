@@ -1,8 +1,4 @@
-mod app;
-mod components;
-mod keycodes;
-
-use app::App;
+use layout_rs::app::App;
 
 fn main() {
     yew::Renderer::<App>::new().render();
@@ -0,0 +1,457 @@
+//! # Saved Layout Schema
+//!
+//! Format of the layouts kept in browser storage, and the migrations that bring data saved
+//! by older versions up to date.
+//!
+//! Every payload is a [`StoredKeymap`] envelope carrying an explicit version. Versions
+//! before the envelope existed are recognised by their shape:
+//!
+//! | Version | Shape |
+//! |---------|-------|
+//! | 0 | `[[[row, col], "KeyboardUsage variant"], ...]`, a single unnamed layer |
+//! | 1 | `[{ "name", "keys": [[[row, col], "KeyboardUsage variant"], ...] }, ...]`, including the `KeyboardLower`/`KeyboardRaise`/`KeyboardEmpty` pseudo-keys |
+//! | 2 | Same list of layers, with a `KeyAction` per key |
+//! | 3 | `{ "version": 3, "layers": [...] }`, with usages and consumer controls saved as HID codes and mouse keys by name |
+//!
+//! Versions 1 and 2 can't be told apart by shape, so unversioned layer lists are read as
+//! version 1. The 1 → 2 migration leaves version 2 actions untouched.
+//!
+//! Each keyboard model can keep several named layout profiles. A [`ProfileIndex`] listing
+//! them is saved next to the layouts; the default profile uses the model's own storage key,
+//! so layouts saved before profiles existed open as the "Default" profile.
+//!
+//! Keyboard models imported from layout files are saved as a list of [`StoredKeyboard`]s:
+//! their geometry and base layer, from which the model is rebuilt on load.
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use crate::actions::KeyAction;
+use crate::geometry::KeyboardGeometry;
+use crate::keyboards::KeyboardModel;
+use crate::keymap::{Layer, UnmappedKeycode};
+
+/// Version written by [`serialize_layers`]
+pub const STORAGE_VERSION: u64 = 3;
+
+/// Migrations between storage versions: `MIGRATIONS[n]` upgrades version `n` to `n + 1`
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; STORAGE_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+];
+
+/// Versioned envelope around the saved layers
+#[derive(Serialize, Deserialize)]
+struct StoredKeymap {
+    version: u64,
+    layers: Vec<StoredLayer>,
+}
+
+/// Serializable form of a [`Layer`]
+#[derive(Serialize, Deserialize)]
+struct StoredLayer {
+    name: String,
+    keys: Vec<((usize, usize), KeyAction)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unmapped: Vec<((usize, usize), UnmappedKeycode)>,
+}
+
+/// Serializable form of an imported [`KeyboardModel`]
+#[derive(Serialize, Deserialize)]
+struct StoredKeyboard {
+    id: String,
+    name: String,
+    geometry: KeyboardGeometry,
+    base: Vec<((usize, usize), KeyAction)>,
+}
+
+/// Id of the profile every keyboard model starts with
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// A named layout profile
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// ISO 8601 time of the last save, if the profile was ever saved
+    #[serde(default)]
+    pub modified_at: Option<String>,
+}
+
+/// The profiles of one keyboard model and which of them is open
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProfileIndex {
+    active: String,
+    profiles: Vec<Profile>,
+}
+
+impl Default for ProfileIndex {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "Default".to_string(),
+                modified_at: None,
+            }],
+        }
+    }
+}
+
+impl ProfileIndex {
+    /// Parse a saved index, repairing an active id that no longer exists
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let mut index: Self = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid profile list: {}", e))?;
+        if index.profiles.is_empty() {
+            return Err("Profile list is empty".to_string());
+        }
+        if index.get(&index.active).is_none() {
+            index.active = index.profiles[0].id.clone();
+        }
+        Ok(index)
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Serialization failed: {}", e))
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    pub fn active(&self) -> &Profile {
+        self.get(&self.active).expect("active profile exists")
+    }
+
+    pub fn set_active(&mut self, id: &str) -> Result<(), String> {
+        self.get(id).ok_or_else(|| format!("Profile '{}' does not exist", id))?;
+        self.active = id.to_string();
+        Ok(())
+    }
+
+    /// Add a profile and return its id
+    pub fn create(&mut self, name: &str) -> Result<String, String> {
+        let name = self.validate_name(name, None)?;
+        let id = (1..)
+            .map(|n| format!("profile{}", n))
+            .find(|id| self.get(id).is_none())
+            .expect("unbounded ids");
+        self.profiles.push(Profile { id: id.clone(), name, modified_at: None });
+        Ok(id)
+    }
+
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<(), String> {
+        let name = self.validate_name(name, Some(id))?;
+        let profile = self.profiles.iter_mut()
+            .find(|profile| profile.id == id)
+            .ok_or_else(|| format!("Profile '{}' does not exist", id))?;
+        profile.name = name;
+        Ok(())
+    }
+
+    /// Remove a profile. If it was active, the first remaining profile becomes active.
+    pub fn remove(&mut self, id: &str) -> Result<(), String> {
+        if self.profiles.len() == 1 {
+            return Err("Cannot delete the only profile".to_string());
+        }
+        let index = self.profiles.iter()
+            .position(|profile| profile.id == id)
+            .ok_or_else(|| format!("Profile '{}' does not exist", id))?;
+        self.profiles.remove(index);
+        if self.active == id {
+            self.active = self.profiles[0].id.clone();
+        }
+        Ok(())
+    }
+
+    pub fn set_modified(&mut self, id: &str, modified_at: Option<String>) {
+        if let Some(profile) = self.profiles.iter_mut().find(|profile| profile.id == id) {
+            profile.modified_at = modified_at;
+        }
+    }
+
+    /// First unused name of the form "{name} copy", "{name} copy 2", ...
+    pub fn copy_name(&self, name: &str) -> String {
+        let base_name = format!("{} copy", name);
+        let mut copy_name = base_name.clone();
+        let mut suffix = 2;
+        while self.profiles.iter().any(|profile| profile.name == copy_name) {
+            copy_name = format!("{} {}", base_name, suffix);
+            suffix += 1;
+        }
+        copy_name
+    }
+
+    /// Trim a profile name and make sure it is non-empty and not used by another profile
+    fn validate_name(&self, name: &str, id: Option<&str>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        if self.profiles.iter().any(|profile| Some(profile.id.as_str()) != id && profile.name == name) {
+            return Err(format!("A profile named '{}' already exists", name));
+        }
+        Ok(name.to_string())
+    }
+}
+
+/// Storage key of the profile list of a keyboard model
+pub fn profile_index_key(storage_key: &str) -> String {
+    format!("{}_profiles", storage_key)
+}
+
+/// Storage key of a profile's layout
+pub fn profile_storage_key(storage_key: &str, profile_id: &str) -> String {
+    if profile_id == DEFAULT_PROFILE_ID {
+        storage_key.to_string()
+    } else {
+        format!("{}_{}", storage_key, profile_id)
+    }
+}
+
+/// Serialize layers in the current storage format
+pub fn serialize_layers(layers: &[Layer]) -> Result<String, String> {
+    let stored = StoredKeymap {
+        version: STORAGE_VERSION,
+        layers: layers.iter()
+            .map(|layer| {
+                // Sort entries so unchanged layouts serialize identically
+                let mut keys: Vec<((usize, usize), KeyAction)> = layer.keys.iter()
+                    .map(|(&position, &action)| (position, action))
+                    .collect();
+                keys.sort_by_key(|&(position, _)| position);
+                let mut unmapped: Vec<((usize, usize), UnmappedKeycode)> = layer.unmapped.iter()
+                    .map(|(&position, keycode)| (position, keycode.clone()))
+                    .collect();
+                unmapped.sort_by_key(|(position, _)| *position);
+                StoredLayer { name: layer.name.clone(), keys, unmapped }
+            })
+            .collect(),
+    };
+
+    serde_json::to_string(&stored).map_err(|e| format!("Serialization failed: {}", e))
+}
+
+/// Parse a saved layout of any known version, migrating it to the current one
+pub fn parse_layers(saved_keymap: &str) -> Result<Vec<Layer>, String> {
+    let mut value: Value = serde_json::from_str(saved_keymap)
+        .map_err(|e| format!("Saved layout is not valid JSON: {}", e))?;
+
+    let version = stored_version(&value)?;
+    if version > STORAGE_VERSION {
+        return Err(format!(
+            "Saved layout has version {}, but this editor only understands up to version {}",
+            version, STORAGE_VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+
+    let stored: StoredKeymap = serde_json::from_value(value)
+        .map_err(|e| format!("Invalid saved layout: {}", e))?;
+    if stored.layers.is_empty() {
+        return Err("Saved layout has no layers".to_string());
+    }
+
+    Ok(stored.layers.into_iter()
+        .map(|layer| Layer {
+            name: layer.name,
+            keys: layer.keys.into_iter().collect::<HashMap<_, _>>(),
+            unmapped: layer.unmapped.into_iter().collect(),
+        })
+        .collect())
+}
+
+/// Serialize imported keyboard models with their geometry and base layer
+pub fn serialize_keyboards(models: &[KeyboardModel]) -> Result<String, String> {
+    let stored: Vec<StoredKeyboard> = models.iter()
+        .map(|model| {
+            let mut base: Vec<((usize, usize), KeyAction)> = model.default_layers.first()
+                .map(|layer| layer.keys.iter().map(|(&position, &action)| (position, action)).collect())
+                .unwrap_or_default();
+            base.sort_by_key(|&(position, _)| position);
+            StoredKeyboard {
+                id: model.id.clone(),
+                name: model.name.clone(),
+                geometry: model.geometry.clone(),
+                base,
+            }
+        })
+        .collect();
+
+    serde_json::to_string(&stored).map_err(|e| format!("Serialization failed: {}", e))
+}
+
+/// Parse the imported keyboard models saved by [`serialize_keyboards`]
+pub fn parse_keyboards(json: &str) -> Result<Vec<KeyboardModel>, String> {
+    let stored: Vec<StoredKeyboard> = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid keyboard list: {}", e))?;
+
+    stored.into_iter()
+        .map(|keyboard| {
+            // Re-validate the geometry, which deserializing does not check
+            let geometry = KeyboardGeometry::new(keyboard.geometry.name.clone(), keyboard.geometry.keys().to_vec())?;
+            Ok(KeyboardModel::new(&keyboard.id, &keyboard.name, geometry, keyboard.base.into_iter().collect()))
+        })
+        .collect()
+}
+
+/// Version of a saved payload, from its envelope or, for older data, its shape
+fn stored_version(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Object(envelope) => envelope.get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| "Saved layout has no version".to_string()),
+        Value::Array(items) if !items.is_empty() && items.iter().all(|item| item.get("name").is_some()) => Ok(1),
+        Value::Array(_) => Ok(0),
+        _ => Err("Unrecognised saved layout".to_string()),
+    }
+}
+
+/// Wrap a single-layer layout in the Base/Lower/Raise layer stack it was edited with
+fn migrate_v0_to_v1(value: Value) -> Result<Value, String> {
+    Ok(json!([
+        { "name": "Base", "keys": value },
+        { "name": "Lower", "keys": [] },
+        { "name": "Raise", "keys": [] },
+    ]))
+}
+
+/// Turn bare usage names into key actions, and the old pseudo-keycodes into layer actions
+fn migrate_v1_to_v2(mut value: Value) -> Result<Value, String> {
+    let layers = value.as_array_mut().ok_or("Expected a list of layers")?;
+    for layer in layers {
+        let keys = layer.get_mut("keys")
+            .and_then(Value::as_array_mut)
+            .ok_or("Expected a list of keys in every layer")?;
+        for entry in keys {
+            let action = entry.get_mut(1).ok_or("Expected [position, key] entries")?;
+            let migrated = match action.as_str() {
+                Some("KeyboardLower") => json!({ "MomentaryLayer": 1 }),
+                Some("KeyboardRaise") => json!({ "MomentaryLayer": 2 }),
+                Some("KeyboardEmpty") => json!("NoOp"),
+                // Unit variants of KeyAction, already in version 2 form
+                Some("Transparent") | Some("NoOp") => continue,
+                Some(usage) => json!({ "Key": usage }),
+                None => continue,
+            };
+            *action = migrated;
+        }
+    }
+    Ok(value)
+}
+
+/// Add the version envelope. Usage names are still accepted when reading, and are
+/// rewritten as HID codes on the next save.
+fn migrate_v2_to_v3(value: Value) -> Result<Value, String> {
+    Ok(json!({ "version": 3, "layers": value }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::MouseAction;
+    use crate::keycodes::{ConsumerUsage, KeyboardUsage};
+
+    #[test]
+    fn test_round_trip() {
+        let mut base = Layer::new("Base");
+        base.keys.insert((0, 0), KeyAction::Key(KeyboardUsage::KeyboardEscape));
+        base.keys.insert((4, 3), KeyAction::LayerTap { layer: 1, tap: KeyboardUsage::KeyboardSpacebar });
+        let mut nav = Layer::new("Nav");
+        nav.keys.insert((0, 0), KeyAction::Consumer(ConsumerUsage::PlayPause));
+        nav.keys.insert((1, 0), KeyAction::Mouse(MouseAction::Button1));
+        nav.unmapped.insert((0, 1), UnmappedKeycode { format: "qmk".to_string(), keycode: "RGB_TOG".to_string() });
+        let layers = vec![base, nav];
+
+        let json = serialize_layers(&layers).unwrap();
+        assert!(json.starts_with(r#"{"version":3,"layers":[{"name":"Base","keys":[[[0,0],{"Key":41}]"#), "{}", json);
+        assert!(json.contains(r#"{"name":"Nav","keys":[[[0,0],{"Consumer":205}],[[1,0],{"Mouse":"Button1"}]]"#), "{}", json);
+        assert!(json.ends_with(r#""unmapped":[[[0,1],{"format":"qmk","keycode":"RGB_TOG"}]]}]}"#), "{}", json);
+        assert_eq!(parse_layers(&json).unwrap(), layers);
+    }
+
+    #[test]
+    fn test_migrate_single_layer() {
+        let legacy = r#"[[[0,0],"KeyboardAa"],[[0,1],"KeyboardBb"],[[4,3],"KeyboardLower"]]"#;
+        let layers = parse_layers(legacy).unwrap();
+
+        assert_eq!(layers.len(), 3);
+        assert_eq!(layers[0].name, "Base");
+        assert_eq!(layers[0].keys.len(), 3);
+        assert_eq!(layers[0].keys.get(&(0, 1)), Some(&KeyAction::Key(KeyboardUsage::KeyboardBb)));
+        assert_eq!(layers[0].keys.get(&(4, 3)), Some(&KeyAction::MomentaryLayer(1)));
+        assert!(layers[2].keys.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_unversioned_layers() {
+        let stored = r#"[
+            {"name":"Base","keys":[[[0,0],"KeyboardAa"],[[0,1],{"Key":"KeyboardBb"}],[[4,10],"KeyboardRaise"]]},
+            {"name":"Nav","keys":[[[0,0],"Transparent"],[[0,1],"KeyboardEmpty"]]}
+        ]"#;
+        let layers = parse_layers(stored).unwrap();
+
+        assert_eq!(layers[0].keys.get(&(0, 0)), Some(&KeyAction::Key(KeyboardUsage::KeyboardAa)));
+        assert_eq!(layers[0].keys.get(&(0, 1)), Some(&KeyAction::Key(KeyboardUsage::KeyboardBb)));
+        assert_eq!(layers[0].keys.get(&(4, 10)), Some(&KeyAction::MomentaryLayer(2)));
+        assert_eq!(layers[1].keys.get(&(0, 0)), Some(&KeyAction::Transparent));
+        assert_eq!(layers[1].keys.get(&(0, 1)), Some(&KeyAction::NoOp));
+    }
+
+    #[test]
+    fn test_profile_index() {
+        let mut index = ProfileIndex::default();
+        assert_eq!(index.active().name, "Default");
+
+        let work = index.create(" Work ").unwrap();
+        let gaming = index.create("Gaming").unwrap();
+        assert_eq!((work.as_str(), gaming.as_str()), ("profile1", "profile2"));
+        assert!(index.create("work").is_ok());
+        assert!(index.create("Work").is_err());
+        assert!(index.create("  ").is_err());
+
+        index.set_active(&work).unwrap();
+        index.rename(&work, "Office").unwrap();
+        assert_eq!(index.active().name, "Office");
+        assert!(index.rename(&work, "Gaming").is_err());
+        assert_eq!(index.copy_name("Office"), "Office copy");
+
+        index.remove(&work).unwrap();
+        assert_eq!(index.active().id, DEFAULT_PROFILE_ID);
+        assert_eq!(index.create("Office").unwrap(), "profile1");
+
+        let parsed = ProfileIndex::parse(&index.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, index);
+    }
+
+    #[test]
+    fn test_profile_storage_keys() {
+        assert_eq!(profile_storage_key("dactyl_keymap", DEFAULT_PROFILE_ID), "dactyl_keymap");
+        assert_eq!(profile_storage_key("dactyl_keymap", "profile1"), "dactyl_keymap_profile1");
+        assert_eq!(profile_index_key("dactyl_keymap"), "dactyl_keymap_profiles");
+
+        // Only one profile left: it cannot be deleted
+        let mut index = ProfileIndex::default();
+        assert!(index.remove(DEFAULT_PROFILE_ID).is_err());
+        assert!(ProfileIndex::parse(r#"{"active":"gone","profiles":[{"id":"a","name":"A"}]}"#).unwrap().active().id == "a");
+    }
+
+    #[test]
+    fn test_unreadable_data_is_an_error() {
+        assert!(parse_layers("not json").is_err());
+        assert!(parse_layers(r#"{"layers":[]}"#).is_err());
+        assert!(parse_layers(r#"{"version":3,"layers":[]}"#).is_err());
+        assert!(parse_layers(r#"{"version":3,"layers":[{"name":"Base","keys":[[[0,0],{"Key":"NotAKey"}]]}]}"#).is_err());
+
+        let newer = parse_layers(r#"{"version":4,"layers":[]}"#).unwrap_err();
+        assert!(newer.contains("version 4"), "{}", newer);
+    }
+}
@@ -0,0 +1,334 @@
+//! # Keymap Stores
+//!
+//! Key/value backends that [`Keymap`](crate::keymap::Keymap) saves layouts, profile
+//! lists and settings to. Values are the JSON strings produced by [`crate::storage`].
+//!
+//! - `LocalStorageStore`: the browser's localStorage, limited to a few megabytes
+//! - `IndexedDbStore`: an IndexedDB object store, for large multi-layer layouts
+//! - [`MemoryStore`]: a plain map, for native tests and tools
+//!
+//! The browser stores need the `web` feature.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+#[cfg(feature = "web")]
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(feature = "web")]
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+#[cfg(feature = "web")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "web")]
+use web_sys::{IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+/// Future returned by [`KeymapStore::flush`]
+pub type Flush<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + 'a>>;
+
+/// Persistent string storage used by [`Keymap`](crate::keymap::Keymap)
+pub trait KeymapStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+
+    fn remove(&self, key: &str) -> Result<(), String>;
+
+    /// All keys currently stored
+    fn keys(&self) -> Result<Vec<String>, String>;
+
+    /// Wait until every write so far is stored for good, failing if any of them was lost.
+    /// Stores that write synchronously have nothing to wait for.
+    fn flush(&self) -> Flush<'_> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Store backed by `window.localStorage`
+#[cfg(feature = "web")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalStorageStore;
+
+#[cfg(feature = "web")]
+impl LocalStorageStore {
+    fn storage() -> Result<web_sys::Storage, String> {
+        let window = web_sys::window().ok_or("Window not available")?;
+        window.local_storage()
+            .map_err(|_| "Failed to access localStorage")?
+            .ok_or_else(|| "localStorage not available".to_string())
+    }
+}
+
+#[cfg(feature = "web")]
+impl KeymapStore for LocalStorageStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Self::storage()?.get_item(key)
+            .map_err(|_| "Failed to read from localStorage".to_string())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        Self::storage()?.set_item(key, value)
+            .map_err(|_| "Failed to save to localStorage".to_string())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        Self::storage()?.remove_item(key)
+            .map_err(|_| "Failed to clear localStorage".to_string())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        let storage = Self::storage()?;
+        let length = storage.length().map_err(|_| "Failed to read from localStorage")?;
+        Ok((0..length).filter_map(|index| storage.key(index).ok().flatten()).collect())
+    }
+}
+
+/// Store backed by an IndexedDB object store.
+///
+/// IndexedDB is asynchronous, so every entry is read into memory when the database is
+/// opened. Reads are served from that cache; writes update it and are sent to the database
+/// in the background, where they only count as stored once [`KeymapStore::flush`] has seen
+/// their transactions commit. Writes whose transactions abort are rolled back in the cache,
+/// so it never holds values the database does not.
+#[cfg(feature = "web")]
+pub struct IndexedDbStore {
+    database: IdbDatabase,
+    cache: RefCell<HashMap<String, String>>,
+    /// Writes not yet flushed, oldest first
+    pending: RefCell<Vec<PendingWrite>>,
+}
+
+/// A write sent to IndexedDB but not yet confirmed by a flush
+#[cfg(feature = "web")]
+struct PendingWrite {
+    /// Settled when the write's transaction commits or aborts
+    committed: js_sys::Promise,
+    key: String,
+    /// Cached value before the write, restored if it aborts
+    previous: Option<String>,
+}
+
+#[cfg(feature = "web")]
+impl IndexedDbStore {
+    const OBJECT_STORE: &'static str = "keymaps";
+
+    /// Open (creating if needed) the named database and load its entries
+    pub async fn open(name: &str) -> Result<Self, String> {
+        let factory = web_sys::window()
+            .ok_or("Window not available")?
+            .indexed_db()
+            .map_err(|_| "Failed to access IndexedDB")?
+            .ok_or("IndexedDB not available")?;
+        let request = factory.open_with_u32(name, 1)
+            .map_err(|_| "Failed to open IndexedDB")?;
+
+        let on_upgrade = Closure::once_into_js(move |event: web_sys::Event| {
+            let database = event.target()
+                .and_then(|target| target.dyn_into::<IdbOpenDbRequest>().ok())
+                .and_then(|request| request.result().ok())
+                .and_then(|result| result.dyn_into::<IdbDatabase>().ok());
+            if let Some(database) = database {
+                if !database.object_store_names().contains(Self::OBJECT_STORE) {
+                    let _ = database.create_object_store(Self::OBJECT_STORE);
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+        let database: IdbDatabase = wait_for(&request).await?
+            .dyn_into()
+            .map_err(|_| "IndexedDB did not return a database")?;
+
+        let store = database.transaction_with_str(Self::OBJECT_STORE)
+            .and_then(|transaction| transaction.object_store(Self::OBJECT_STORE))
+            .map_err(|_| "Failed to read IndexedDB")?;
+        let keys = wait_for(&store.get_all_keys().map_err(|_| "Failed to read IndexedDB")?).await?;
+        let values = wait_for(&store.get_all().map_err(|_| "Failed to read IndexedDB")?).await?;
+
+        let cache = js_sys::Array::from(&keys).iter()
+            .zip(js_sys::Array::from(&values).iter())
+            .filter_map(|(key, value)| Some((key.as_string()?, value.as_string()?)))
+            .collect();
+
+        Ok(Self { database, cache: RefCell::new(cache), pending: RefCell::new(Vec::new()) })
+    }
+
+    /// Start a write to `key` on the object store, to be confirmed by the next flush
+    fn write(&self, key: &str, write: impl FnOnce(&web_sys::IdbObjectStore) -> Result<IdbRequest, JsValue>) -> Result<(), String> {
+        let transaction = self.database
+            .transaction_with_str_and_mode(Self::OBJECT_STORE, IdbTransactionMode::Readwrite)
+            .map_err(|_| "Failed to write to IndexedDB".to_string())?;
+        transaction.object_store(Self::OBJECT_STORE)
+            .and_then(|store| write(&store))
+            .map_err(|_| "Failed to write to IndexedDB".to_string())?;
+
+        self.pending.borrow_mut().push(PendingWrite {
+            committed: committed(&transaction),
+            key: key.to_string(),
+            previous: self.cache.borrow().get(key).cloned(),
+        });
+        Ok(())
+    }
+
+    /// Undo the cached values of flushed writes that were not stored. `flushed` holds each
+    /// write with whether it was stored, oldest first.
+    fn roll_back(&self, flushed: Vec<(PendingWrite, bool)>) {
+        // A key stored by a later write already holds the right value
+        let mut stored_later = HashSet::new();
+        for (write, stored) in flushed.into_iter().rev() {
+            if stored {
+                stored_later.insert(write.key);
+                continue;
+            }
+            if stored_later.contains(&write.key) {
+                continue;
+            }
+            // A write queued since the flush started now replaces the value this one left
+            // behind, so it takes over the value to restore
+            let mut pending = self.pending.borrow_mut();
+            if let Some(newer) = pending.iter_mut().find(|newer| newer.key == write.key) {
+                newer.previous = write.previous;
+                continue;
+            }
+            let mut cache = self.cache.borrow_mut();
+            match write.previous {
+                Some(previous) => cache.insert(write.key, previous),
+                None => cache.remove(&write.key),
+            };
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+impl KeymapStore for IndexedDbStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.cache.borrow().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.write(key, |store| store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key)))?;
+        self.cache.borrow_mut().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.write(key, |store| store.delete(&JsValue::from_str(key)))?;
+        self.cache.borrow_mut().remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        Ok(self.cache.borrow().keys().cloned().collect())
+    }
+
+    fn flush(&self) -> Flush<'_> {
+        let pending: Vec<PendingWrite> = self.pending.borrow_mut().drain(..).collect();
+        Box::pin(async move {
+            let mut flushed = Vec::with_capacity(pending.len());
+            for write in pending {
+                let stored = JsFuture::from(write.committed.clone()).await.is_ok();
+                flushed.push((write, stored));
+            }
+            let failed = flushed.iter().filter(|(_, stored)| !stored).count();
+            self.roll_back(flushed);
+            match failed {
+                0 => Ok(()),
+                failed => Err(format!("{} IndexedDB writes were not stored", failed)),
+            }
+        })
+    }
+}
+
+/// Resolve once a transaction has committed; reject if it is aborted, which failed
+/// requests and exceeded quotas both do
+#[cfg(feature = "web")]
+fn committed(transaction: &IdbTransaction) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, reject| {
+        let on_complete = Closure::once_into_js(move || {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+        let on_abort = Closure::once_into_js(move || {
+            let _ = reject.call0(&JsValue::NULL);
+        });
+        transaction.set_oncomplete(Some(on_complete.unchecked_ref()));
+        transaction.set_onabort(Some(on_abort.unchecked_ref()));
+    })
+}
+
+/// Resolve with the result of an IndexedDB request
+#[cfg(feature = "web")]
+async fn wait_for(request: &IdbRequest) -> Result<JsValue, String> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let on_success = {
+            let request = request.clone();
+            Closure::once_into_js(move || {
+                let _ = resolve.call1(&JsValue::NULL, &request.result().unwrap_or(JsValue::UNDEFINED));
+            })
+        };
+        let on_error = Closure::once_into_js(move || {
+            let _ = reject.call0(&JsValue::NULL);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+
+    JsFuture::from(promise).await
+        .map_err(|_| "IndexedDB request failed".to_string())
+}
+
+/// Store that keeps everything in memory
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    items: RefCell<HashMap<String, String>>,
+}
+
+impl KeymapStore for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.items.borrow().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.items.borrow_mut().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), String> {
+        self.items.borrow_mut().remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<String>, String> {
+        Ok(self.items.borrow().keys().cloned().collect())
+    }
+}
+
+/// Run a future that never waits, such as a flush of a synchronous store
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future was not ready"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryStore::default();
+        assert_eq!(store.get("layout"), Ok(None));
+
+        store.set("layout", "{}").unwrap();
+        store.set("other", "[]").unwrap();
+        assert_eq!(store.get("layout"), Ok(Some("{}".to_string())));
+
+        store.remove("layout").unwrap();
+        assert_eq!(store.get("layout"), Ok(None));
+        assert_eq!(store.keys(), Ok(vec!["other".to_string()]));
+        assert_eq!(block_on(store.flush()), Ok(()));
+    }
+}